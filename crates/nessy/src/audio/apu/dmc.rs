@@ -0,0 +1,223 @@
+use crate::roms::region::Region;
+
+// Timer periods in CPU cycles, selected by the lower four bits of $4010.
+const NTSC_RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+const PAL_RATES: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+
+const SAMPLE_ADDRESS_START: u16 = 0xC000;
+const MAX_LEVEL: u8 = 127;
+
+// The delta modulation channel, which plays 1-bit delta encoded samples fetched from
+// cartridge memory, or takes 7-bit PCM written straight to its output level.
+#[derive(Debug)]
+pub struct Dmc {
+    rates: &'static [u16; 16],
+    irq_enabled: bool,
+    looping: bool,
+    period: u16,
+    timer: u16,
+    level: u8,
+
+    sample_address: u16,
+    sample_length: u16,
+    current_address: u16,
+    bytes_remaining: u16,
+    sample_buffer: Option<u8>,
+
+    shift_register: u8,
+    bits_remaining: u8,
+    silence: bool,
+
+    pub(crate) interrupt: bool,
+}
+
+impl Dmc {
+    pub fn new(region: Region) -> Self {
+        let rates = match region {
+            Region::Pal => &PAL_RATES,
+            _ => &NTSC_RATES,
+        };
+
+        Self {
+            rates,
+            irq_enabled: false,
+            looping: false,
+            period: rates[0],
+            timer: 0,
+            level: 0,
+            sample_address: SAMPLE_ADDRESS_START,
+            sample_length: 1,
+            current_address: SAMPLE_ADDRESS_START,
+            bytes_remaining: 0,
+            sample_buffer: None,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+            interrupt: false,
+        }
+    }
+
+    // Writes one of $4010-$4013.
+    pub fn write(&mut self, register: u16, data: u8) {
+        match register & 0b11 {
+            0 => {
+                self.irq_enabled = data & 0b1000_0000 != 0;
+                self.looping = data & 0b0100_0000 != 0;
+                self.period = self.rates[(data & 0x0F) as usize];
+
+                if !self.irq_enabled {
+                    self.interrupt = false;
+                }
+            }
+            1 => self.level = data & MAX_LEVEL,
+            2 => self.sample_address = SAMPLE_ADDRESS_START + data as u16 * 64,
+            _ => self.sample_length = data as u16 * 16 + 1,
+        }
+    }
+
+    // Bit 4 of $4015 starts the sample over if it had finished, or stops it.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.interrupt = false;
+
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    // The address of the next sample byte, when the buffer has run dry and there's more to
+    // play. The bus fetches it and hands it back through `fill`.
+    pub fn pending_fetch(&self) -> Option<u16> {
+        (self.sample_buffer.is_none() && self.bytes_remaining > 0).then_some(self.current_address)
+    }
+
+    pub fn fill(&mut self, data: u8) {
+        self.sample_buffer = Some(data);
+
+        // Samples wrap around to $8000 rather than the zero page.
+        self.current_address = self.current_address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.interrupt = true;
+            }
+        }
+    }
+
+    // Clocked every CPU cycle, the rates being in CPU cycles.
+    pub fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+
+        self.timer = self.period - 1;
+
+        if !self.silence {
+            if self.shift_register & 0x1 != 0 {
+                if self.level <= MAX_LEVEL - 2 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+        }
+
+        self.shift_register >>= 1;
+        self.bits_remaining -= 1;
+
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+
+            match self.sample_buffer.take() {
+                Some(sample) => {
+                    self.silence = false;
+                    self.shift_register = sample;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn play(dmc: &mut Dmc, memory: &[u8], cycles: usize) {
+        for _ in 0..cycles {
+            if let Some(address) = dmc.pending_fetch() {
+                dmc.fill(memory[(address - dmc.sample_address) as usize]);
+            }
+
+            dmc.clock_timer();
+        }
+    }
+
+    #[test]
+    fn test_write_sets_level_directly() {
+        let mut dmc = Dmc::new(Region::Ntsc);
+
+        dmc.write(1, 0xFF);
+
+        assert_eq!(MAX_LEVEL, dmc.output());
+    }
+
+    #[test]
+    fn test_sample_moves_level() {
+        let mut dmc = Dmc::new(Region::Ntsc);
+        dmc.write(0, 0x0F);
+        dmc.write(1, 64);
+        dmc.set_enabled(true);
+
+        // The first byte is only loaded once the silent output cycle in progress has ended.
+        play(&mut dmc, &[0xFF], 54 * 8 + 54 * 8);
+
+        assert_eq!(64 + 16, dmc.output());
+        assert!(!dmc.is_active());
+    }
+
+    #[test]
+    fn test_sample_end_raises_interrupt() {
+        let mut dmc = Dmc::new(Region::Ntsc);
+        dmc.write(0, 0x8F);
+        dmc.set_enabled(true);
+
+        play(&mut dmc, &[0x00], 1);
+
+        assert!(dmc.interrupt);
+    }
+
+    #[test]
+    fn test_sample_loops() {
+        let mut dmc = Dmc::new(Region::Ntsc);
+        dmc.write(0, 0x4F);
+        dmc.set_enabled(true);
+
+        play(&mut dmc, &[0x00], 54 * 8 * 4);
+
+        assert!(dmc.is_active());
+        assert_eq!(SAMPLE_ADDRESS_START, dmc.current_address);
+    }
+}
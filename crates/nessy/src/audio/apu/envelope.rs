@@ -0,0 +1,98 @@
+// The volume generator shared by the pulse and noise channels: either a constant volume, or
+// a sawtooth decaying from 15 to 0 once every (period + 1) quarter frames.
+#[derive(Debug, Default)]
+pub struct Envelope {
+    start: bool,
+    looping: bool,
+    constant_volume: bool,
+    // The constant volume, or the decay period.
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    // The lower six bits of the channel's first register: --LC VVVV.
+    pub fn write_control(&mut self, data: u8) {
+        self.looping = data & 0b10_0000 != 0;
+        self.constant_volume = data & 0b1_0000 != 0;
+        self.volume = data & 0x0F;
+    }
+
+    pub fn restart(&mut self) {
+        self.start = true;
+    }
+
+    pub fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    pub fn volume(&self) -> u8 {
+        if self.constant_volume {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_volume_is_constant_given_constant_volume() {
+        let mut envelope = Envelope::default();
+
+        envelope.write_control(0b1_0111);
+        envelope.restart();
+        envelope.clock();
+        envelope.clock();
+
+        assert_eq!(7, envelope.volume());
+    }
+
+    #[test]
+    fn test_volume_decays_once_per_period() {
+        let mut envelope = Envelope::default();
+
+        envelope.write_control(0b0_0001);
+        envelope.restart();
+        envelope.clock();
+        assert_eq!(15, envelope.volume());
+
+        envelope.clock();
+        envelope.clock();
+        assert_eq!(14, envelope.volume());
+    }
+
+    #[test]
+    fn test_volume_loops_given_looping() {
+        let mut envelope = Envelope::default();
+
+        envelope.write_control(0b10_0000);
+        envelope.restart();
+
+        for _ in 0..16 {
+            envelope.clock();
+        }
+        assert_eq!(0, envelope.volume());
+
+        envelope.clock();
+        assert_eq!(15, envelope.volume());
+    }
+}
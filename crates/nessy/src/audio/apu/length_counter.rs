@@ -0,0 +1,96 @@
+// Lengths loaded by the upper five bits of a channel's last register, in half frames.
+const LENGTHS: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+// Silences a channel once a set number of half frames have passed, unless it's halted.
+#[derive(Debug, Default)]
+pub struct LengthCounter {
+    enabled: bool,
+    halted: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    // Disabling a channel through $4015 also silences it straight away.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    pub fn set_halted(&mut self, halted: bool) {
+        self.halted = halted;
+    }
+
+    pub fn load(&mut self, index: u8) {
+        if self.enabled {
+            self.counter = LENGTHS[(index & 0x1F) as usize];
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if !self.halted && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.counter > 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_is_ignored_when_disabled() {
+        let mut length_counter = LengthCounter::default();
+
+        length_counter.load(0);
+
+        assert!(!length_counter.is_active());
+    }
+
+    #[test]
+    fn test_clock_counts_down_to_silence() {
+        let mut length_counter = LengthCounter::default();
+        length_counter.set_enabled(true);
+
+        // An index of 3 is 2 half frames.
+        length_counter.load(3);
+        length_counter.clock();
+        assert!(length_counter.is_active());
+
+        length_counter.clock();
+        assert!(!length_counter.is_active());
+    }
+
+    #[test]
+    fn test_clock_does_nothing_when_halted() {
+        let mut length_counter = LengthCounter::default();
+        length_counter.set_enabled(true);
+        length_counter.set_halted(true);
+
+        length_counter.load(3);
+        length_counter.clock();
+        length_counter.clock();
+
+        assert!(length_counter.is_active());
+    }
+
+    #[test]
+    fn test_disabling_clears_counter() {
+        let mut length_counter = LengthCounter::default();
+        length_counter.set_enabled(true);
+        length_counter.load(1);
+
+        length_counter.set_enabled(false);
+
+        assert!(!length_counter.is_active());
+    }
+}
@@ -0,0 +1,332 @@
+use std::cell::Cell;
+
+use crate::{
+    audio::apu::{
+        dmc::Dmc,
+        noise::Noise,
+        pulse::{Pulse, PulseChannel},
+        triangle::Triangle,
+    },
+    roms::region::Region,
+};
+
+pub mod dmc;
+pub mod envelope;
+pub mod length_counter;
+pub mod noise;
+pub mod pulse;
+pub mod triangle;
+
+const PULSE_1_START: u16 = 0x4000;
+const PULSE_1_END: u16 = 0x4003;
+const PULSE_2_START: u16 = 0x4004;
+const PULSE_2_END: u16 = 0x4007;
+const TRIANGLE_START: u16 = 0x4008;
+const TRIANGLE_END: u16 = 0x400B;
+const NOISE_START: u16 = 0x400C;
+const NOISE_END: u16 = 0x400F;
+const DMC_START: u16 = 0x4010;
+const DMC_END: u16 = 0x4013;
+pub const STATUS: u16 = 0x4015;
+pub const FRAME_COUNTER: u16 = 0x4017;
+
+const FIVE_STEP_MODE: u8 = 0b1000_0000;
+const FRAME_INTERRUPT_INHIBIT: u8 = 0b0100_0000;
+
+// CPU cycles into the frame at which the frame counter clocks the envelopes and linear
+// counter (every step) and the length counters and sweeps (every other step), followed by
+// the length of the whole sequence. The four step sequence raises an IRQ on its last step.
+const NTSC_FOUR_STEP: [u32; 5] = [7457, 14913, 22371, 29829, 29830];
+const NTSC_FIVE_STEP: [u32; 5] = [7457, 14913, 22371, 37281, 37282];
+const PAL_FOUR_STEP: [u32; 5] = [8313, 16627, 24939, 33253, 33254];
+const PAL_FIVE_STEP: [u32; 5] = [8313, 16627, 24939, 41565, 41566];
+
+// The 2A03's audio processing unit: two pulse channels, a triangle, noise and the delta
+// modulation channel, sequenced by a frame counter running off the CPU clock.
+//
+// As on hardware, the output rests at the triangle's first step rather than 0, which only
+// shifts it by a constant.
+#[derive(Debug)]
+pub struct Apu {
+    region: Region,
+    pulse_1: Pulse,
+    pulse_2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+
+    five_step_mode: bool,
+    frame_interrupt_inhibit: bool,
+    // Reading $4015 acknowledges the frame interrupt, which reads can't otherwise change.
+    frame_interrupt: Cell<bool>,
+    frame_cycle: u32,
+    // The pulse and noise timers tick on every other CPU cycle.
+    odd_cycle: bool,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new(Region::Ntsc)
+    }
+}
+
+impl Apu {
+    pub fn new(region: Region) -> Self {
+        Self {
+            region,
+            pulse_1: Pulse::new(PulseChannel::One),
+            pulse_2: Pulse::new(PulseChannel::Two),
+            triangle: Triangle::default(),
+            noise: Noise::new(region),
+            dmc: Dmc::new(region),
+            five_step_mode: false,
+            frame_interrupt_inhibit: false,
+            frame_interrupt: Cell::new(false),
+            frame_cycle: 0,
+            odd_cycle: false,
+        }
+    }
+
+    // Only $4015 can be read back; everything else reads as open bus, which is left as 0.
+    pub fn read(&self, address: u16) -> u8 {
+        let status = self.peek(address);
+
+        if address == STATUS {
+            self.frame_interrupt.set(false);
+        }
+
+        status
+    }
+
+    // Reads without acknowledging the frame interrupt, for debuggers and trace logs.
+    pub fn peek(&self, address: u16) -> u8 {
+        if address != STATUS {
+            return 0;
+        }
+
+        (self.pulse_1.length_counter.is_active() as u8)
+            | (self.pulse_2.length_counter.is_active() as u8) << 1
+            | (self.triangle.length_counter.is_active() as u8) << 2
+            | (self.noise.length_counter.is_active() as u8) << 3
+            | (self.dmc.is_active() as u8) << 4
+            | (self.frame_interrupt.get() as u8) << 6
+            | (self.dmc.interrupt as u8) << 7
+    }
+
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            PULSE_1_START..=PULSE_1_END => self.pulse_1.write(address, data),
+            PULSE_2_START..=PULSE_2_END => self.pulse_2.write(address, data),
+            TRIANGLE_START..=TRIANGLE_END => self.triangle.write(address, data),
+            NOISE_START..=NOISE_END => self.noise.write(address, data),
+            DMC_START..=DMC_END => self.dmc.write(address, data),
+            STATUS => {
+                self.pulse_1.length_counter.set_enabled(data & 0b1 != 0);
+                self.pulse_2.length_counter.set_enabled(data & 0b10 != 0);
+                self.triangle.length_counter.set_enabled(data & 0b100 != 0);
+                self.noise.length_counter.set_enabled(data & 0b1000 != 0);
+                self.dmc.set_enabled(data & 0b1_0000 != 0);
+            }
+            FRAME_COUNTER => {
+                self.five_step_mode = data & FIVE_STEP_MODE != 0;
+                self.frame_interrupt_inhibit = data & FRAME_INTERRUPT_INHIBIT != 0;
+                self.frame_cycle = 0;
+
+                if self.frame_interrupt_inhibit {
+                    self.frame_interrupt.set(false);
+                }
+
+                // Switching to the five step sequence clocks everything straight away.
+                if self.five_step_mode {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Advances the APU by a single CPU cycle.
+    pub fn clock(&mut self) {
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+
+        if self.odd_cycle {
+            self.pulse_1.clock_timer();
+            self.pulse_2.clock_timer();
+        }
+        self.odd_cycle = !self.odd_cycle;
+
+        self.clock_frame_counter();
+    }
+
+    fn clock_frame_counter(&mut self) {
+        let steps = match (self.region, self.five_step_mode) {
+            (Region::Pal, false) => &PAL_FOUR_STEP,
+            (Region::Pal, true) => &PAL_FIVE_STEP,
+            (_, false) => &NTSC_FOUR_STEP,
+            (_, true) => &NTSC_FIVE_STEP,
+        };
+
+        self.frame_cycle += 1;
+
+        match steps.iter().position(|&cycle| cycle == self.frame_cycle) {
+            Some(0) | Some(2) => self.clock_quarter_frame(),
+            Some(1) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            Some(3) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+
+                if !self.five_step_mode && !self.frame_interrupt_inhibit {
+                    self.frame_interrupt.set(true);
+                }
+            }
+            Some(_) => self.frame_cycle = 0,
+            None => {}
+        }
+    }
+
+    fn clock_quarter_frame(&mut self) {
+        self.pulse_1.clock_quarter_frame();
+        self.pulse_2.clock_quarter_frame();
+        self.triangle.clock_quarter_frame();
+        self.noise.clock_quarter_frame();
+    }
+
+    fn clock_half_frame(&mut self) {
+        self.pulse_1.clock_half_frame();
+        self.pulse_2.clock_half_frame();
+        self.triangle.clock_half_frame();
+        self.noise.clock_half_frame();
+    }
+
+    // The DMC's sample fetches are made over the CPU bus, so whoever owns the bus answers
+    // them. The cycles the fetch steals from the CPU aren't modelled.
+    pub fn pending_dmc_fetch(&self) -> Option<u16> {
+        self.dmc.pending_fetch()
+    }
+
+    pub fn fill_dmc_sample(&mut self, data: u8) {
+        self.dmc.fill(data);
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.frame_interrupt.get() || self.dmc.interrupt
+    }
+
+    // Mixes the channels with the 2A03's non-linear DACs, giving 0.0 to about 1.0.
+    pub fn output(&self) -> f32 {
+        let pulse = (self.pulse_1.output() + self.pulse_2.output()) as f32;
+        let pulse_out = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+
+        let tnd = self.triangle.output() as f32 / 8227.0
+            + self.noise.output() as f32 / 12241.0
+            + self.dmc.output() as f32 / 22638.0;
+        let tnd_out = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+
+        pulse_out + tnd_out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clock(apu: &mut Apu, cycles: u32) {
+        for _ in 0..cycles {
+            apu.clock();
+        }
+    }
+
+    #[test]
+    fn test_output_holds_still_by_default() {
+        let mut apu = Apu::default();
+        let output = apu.output();
+
+        clock(&mut apu, 1000);
+
+        assert_eq!(output, apu.output());
+    }
+
+    #[test]
+    fn test_pulse_tone_is_heard() {
+        let mut apu = Apu::default();
+        apu.write(STATUS, 0b1);
+        apu.write(0x4000, 0b1011_1111);
+        apu.write(0x4002, 0xFD);
+        apu.write(0x4003, 0x00);
+
+        let mut outputs = vec![];
+        for _ in 0..1000 {
+            apu.clock();
+            outputs.push(apu.output());
+        }
+
+        let lowest = outputs.iter().copied().fold(f32::MAX, f32::min);
+        let highest = outputs.iter().copied().fold(f32::MIN, f32::max);
+
+        assert!(highest - lowest > 0.1);
+    }
+
+    #[test]
+    fn test_status_reports_length_counters() {
+        let mut apu = Apu::default();
+        apu.write(STATUS, 0b0101);
+        apu.write(0x4003, 0b0000_1000);
+        apu.write(0x400B, 0b0000_1000);
+        apu.write(0x400F, 0b0000_1000);
+
+        assert_eq!(0b0101, apu.read(STATUS));
+    }
+
+    #[test]
+    fn test_frame_interrupt_raised_by_four_step_sequence() {
+        let mut apu = Apu::default();
+
+        clock(&mut apu, NTSC_FOUR_STEP[3] - 1);
+        assert!(!apu.irq_pending());
+
+        clock(&mut apu, 1);
+        assert!(apu.irq_pending());
+
+        // Reading the status acknowledges it.
+        assert_eq!(0b0100_0000, apu.read(STATUS));
+        assert!(!apu.irq_pending());
+    }
+
+    #[test]
+    fn test_frame_interrupt_inhibited() {
+        let mut apu = Apu::default();
+        apu.write(FRAME_COUNTER, FRAME_INTERRUPT_INHIBIT);
+
+        clock(&mut apu, NTSC_FOUR_STEP[4] * 2);
+
+        assert!(!apu.irq_pending());
+    }
+
+    #[test]
+    fn test_length_counter_runs_out_after_half_frames() {
+        let mut apu = Apu::default();
+        apu.write(STATUS, 0b1);
+
+        // An index of 3 is 2 half frames, which the four step sequence gives in one frame.
+        apu.write(0x4003, 0b0001_1000);
+        clock(&mut apu, NTSC_FOUR_STEP[1]);
+        assert_eq!(0b1, apu.read(STATUS) & 0b1);
+
+        clock(&mut apu, NTSC_FOUR_STEP[3] - NTSC_FOUR_STEP[1]);
+        assert_eq!(0b0, apu.read(STATUS) & 0b1);
+    }
+}
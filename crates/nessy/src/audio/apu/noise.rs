@@ -0,0 +1,133 @@
+use crate::{
+    audio::apu::{envelope::Envelope, length_counter::LengthCounter},
+    roms::region::Region,
+};
+
+// Timer periods in CPU cycles, selected by the lower four bits of $400E.
+const NTSC_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+const PAL_PERIODS: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+
+// Pseudo-random noise from a 15-bit LFSR, with an envelope for volume. Short mode taps bit
+// 6 instead of bit 1, giving a metallic loop of only 93 or 31 steps.
+#[derive(Debug)]
+pub struct Noise {
+    periods: &'static [u16; 16],
+    short_mode: bool,
+    period: u16,
+    timer: u16,
+    shift_register: u16,
+    envelope: Envelope,
+    pub(crate) length_counter: LengthCounter,
+}
+
+impl Noise {
+    pub fn new(region: Region) -> Self {
+        Self {
+            periods: match region {
+                Region::Pal => &PAL_PERIODS,
+                _ => &NTSC_PERIODS,
+            },
+            short_mode: false,
+            period: NTSC_PERIODS[0],
+            timer: 0,
+            shift_register: 1,
+            envelope: Envelope::default(),
+            length_counter: LengthCounter::default(),
+        }
+    }
+
+    // Writes one of $400C-$400F, $400D being unused.
+    pub fn write(&mut self, register: u16, data: u8) {
+        match register & 0b11 {
+            0 => {
+                self.length_counter.set_halted(data & 0b10_0000 != 0);
+                self.envelope.write_control(data);
+            }
+            2 => {
+                self.short_mode = data & 0b1000_0000 != 0;
+                self.period = self.periods[(data & 0x0F) as usize];
+            }
+            3 => {
+                self.length_counter.load(data >> 3);
+                self.envelope.restart();
+            }
+            _ => {}
+        }
+    }
+
+    // Clocked every CPU cycle, the periods being in CPU cycles.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period - 1;
+
+            let tap = if self.short_mode { 6 } else { 1 };
+            let feedback = (self.shift_register ^ (self.shift_register >> tap)) & 0x1;
+            self.shift_register = (self.shift_register >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length_counter.is_active() || self.shift_register & 0x1 != 0 {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    // Steps until the shift register comes back to where it started.
+    fn sequence_length(short_mode: bool) -> usize {
+        let mut noise = Noise::new(Region::Ntsc);
+        noise.short_mode = short_mode;
+        noise.period = 1;
+
+        let start = noise.shift_register;
+        let mut steps = 0;
+
+        loop {
+            noise.clock_timer();
+            steps += 1;
+
+            if noise.shift_register == start {
+                return steps;
+            }
+        }
+    }
+
+    #[parameterized]
+    #[case(false, 32767)]
+    #[case(true, 93)]
+    fn test_shift_register_sequence_length(short_mode: bool, expected_length: usize) {
+        assert_eq!(expected_length, sequence_length(short_mode));
+    }
+
+    #[test]
+    fn test_output_is_silent_given_no_length() {
+        let mut noise = Noise::new(Region::Ntsc);
+
+        noise.write(0, 0b1_1111);
+        noise.clock_timer();
+
+        assert_eq!(0, noise.output());
+    }
+}
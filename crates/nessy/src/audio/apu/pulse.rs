@@ -0,0 +1,206 @@
+use crate::audio::apu::{envelope::Envelope, length_counter::LengthCounter};
+
+// The 8-step waveforms for 12.5%, 25%, 50% and 25% negated duty cycles.
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+// Periods past this are out of the 11-bit timer's range, so the sweep mutes the channel.
+const MAX_PERIOD: u16 = 0x7FF;
+
+// Periods below this are ultrasonic, and muted by the sweep whether it's enabled or not.
+const MIN_PERIOD: u16 = 8;
+
+// The two pulse channels only differ in how their sweeps negate: the first adds the ones'
+// complement of the change, the second the two's complement.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PulseChannel {
+    #[default]
+    One,
+    Two,
+}
+
+#[derive(Debug, Default)]
+struct Sweep {
+    enabled: bool,
+    period: u8,
+    negate: bool,
+    shift: u8,
+    divider: u8,
+    reload: bool,
+}
+
+// A square wave at one of four duty cycles, with an envelope and a sweep unit which can
+// bend its pitch up or down.
+#[derive(Debug, Default)]
+pub struct Pulse {
+    channel: PulseChannel,
+    duty: u8,
+    step: u8,
+    period: u16,
+    timer: u16,
+    envelope: Envelope,
+    sweep: Sweep,
+    pub(crate) length_counter: LengthCounter,
+}
+
+impl Pulse {
+    pub fn new(channel: PulseChannel) -> Self {
+        Self {
+            channel,
+            ..Default::default()
+        }
+    }
+
+    // Writes one of the channel's four registers, $4000-$4003 or $4004-$4007.
+    pub fn write(&mut self, register: u16, data: u8) {
+        match register & 0b11 {
+            0 => {
+                self.duty = data >> 6;
+                self.length_counter.set_halted(data & 0b10_0000 != 0);
+                self.envelope.write_control(data);
+            }
+            1 => {
+                self.sweep.enabled = data & 0b1000_0000 != 0;
+                self.sweep.period = (data >> 4) & 0b111;
+                self.sweep.negate = data & 0b1000 != 0;
+                self.sweep.shift = data & 0b111;
+                self.sweep.reload = true;
+            }
+            2 => self.period = (self.period & 0x0700) | data as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((data as u16 & 0b111) << 8);
+                self.length_counter.load(data >> 3);
+                self.step = 0;
+                self.envelope.restart();
+            }
+        }
+    }
+
+    // Clocked every APU cycle, which is every other CPU cycle.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+
+        if self.sweep.divider == 0 && self.sweep.enabled && self.sweep.shift > 0 && !self.muted() {
+            self.period = self.target_period();
+        }
+
+        if self.sweep.divider == 0 || self.sweep.reload {
+            self.sweep.divider = self.sweep.period;
+            self.sweep.reload = false;
+        } else {
+            self.sweep.divider -= 1;
+        }
+    }
+
+    // The period the sweep is heading for, which mutes the channel when out of range even
+    // if the sweep is disabled.
+    fn target_period(&self) -> u16 {
+        let change = self.period >> self.sweep.shift;
+
+        if !self.sweep.negate {
+            self.period + change
+        } else if self.channel == PulseChannel::One {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+
+    fn muted(&self) -> bool {
+        self.period < MIN_PERIOD || self.target_period() > MAX_PERIOD
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length_counter.is_active()
+            || self.muted()
+            || DUTY_CYCLES[self.duty as usize][self.step as usize] == 0
+        {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    fn create_pulse(channel: PulseChannel, period: u16) -> Pulse {
+        let mut pulse = Pulse::new(channel);
+        pulse.length_counter.set_enabled(true);
+
+        // 50% duty, constant volume of 15, halted length counter.
+        pulse.write(0, 0b1011_1111);
+        pulse.write(2, period as u8);
+        pulse.write(3, (period >> 8) as u8);
+        pulse
+    }
+
+    #[test]
+    fn test_output_follows_duty_cycle() {
+        let mut pulse = create_pulse(PulseChannel::One, 8);
+        let mut outputs = vec![];
+
+        for _ in 0..8 {
+            pulse.clock_timer();
+            outputs.push(pulse.output());
+            for _ in 0..8 {
+                pulse.clock_timer();
+            }
+        }
+
+        assert_eq!(vec![15, 15, 15, 15, 0, 0, 0, 0], outputs);
+    }
+
+    #[test]
+    fn test_output_is_silent_given_low_period() {
+        let mut pulse = create_pulse(PulseChannel::One, 7);
+        pulse.clock_timer();
+
+        assert_eq!(0, pulse.output());
+    }
+
+    #[test]
+    fn test_output_is_silent_given_sweep_target_out_of_range() {
+        let mut pulse = create_pulse(PulseChannel::One, 0x7F0);
+        pulse.clock_timer();
+
+        // The sweep is disabled, but still mutes given a shift of 0.
+        assert_eq!(0, pulse.output());
+    }
+
+    #[parameterized]
+    #[case(PulseChannel::One, 0b1000_1001, 0x07F)]
+    #[case(PulseChannel::Two, 0b1000_1001, 0x080)]
+    #[case(PulseChannel::One, 0b1000_0001, 0x180)]
+    #[case(PulseChannel::One, 0b1000_1010, 0x0BF)]
+    #[case(PulseChannel::Two, 0b1000_1010, 0x0C0)]
+    fn test_sweep_changes_period(channel: PulseChannel, sweep: u8, expected_period: u16) {
+        let mut pulse = create_pulse(channel, 0x100);
+        pulse.write(1, sweep);
+
+        pulse.clock_half_frame();
+
+        assert_eq!(expected_period, pulse.period);
+    }
+}
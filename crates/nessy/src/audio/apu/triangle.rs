@@ -0,0 +1,117 @@
+use crate::audio::apu::length_counter::LengthCounter;
+
+// The 32-step triangle the sequencer walks through.
+const SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12,
+    13, 14, 15,
+];
+
+// A triangle wave with no volume control, silenced by a linear counter as well as the usual
+// length counter. When silenced it holds its last step rather than dropping to 0.
+#[derive(Debug, Default)]
+pub struct Triangle {
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+    period: u16,
+    timer: u16,
+    step: u8,
+    pub(crate) length_counter: LengthCounter,
+}
+
+impl Triangle {
+    // Writes one of $4008-$400B, $4009 being unused.
+    pub fn write(&mut self, register: u16, data: u8) {
+        match register & 0b11 {
+            0 => {
+                // The control flag doubles as the length counter halt.
+                self.control = data & 0b1000_0000 != 0;
+                self.length_counter.set_halted(self.control);
+                self.linear_reload_value = data & 0x7F;
+            }
+            2 => self.period = (self.period & 0x0700) | data as u16,
+            3 => {
+                self.period = (self.period & 0x00FF) | ((data as u16 & 0b111) << 8);
+                self.length_counter.load(data >> 3);
+                self.linear_reload = true;
+            }
+            _ => {}
+        }
+    }
+
+    // Unlike the other channels, the triangle's timer runs off the CPU clock.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+
+            if self.length_counter.is_active() && self.linear_counter > 0 {
+                self.step = (self.step + 1) % 32;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length_counter.clock();
+    }
+
+    pub fn output(&self) -> u8 {
+        SEQUENCE[self.step as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_triangle(linear_counter: u8) -> Triangle {
+        let mut triangle = Triangle::default();
+        triangle.length_counter.set_enabled(true);
+
+        triangle.write(0, linear_counter);
+        triangle.write(2, 0);
+        triangle.write(3, 0);
+        triangle.clock_quarter_frame();
+        triangle
+    }
+
+    #[test]
+    fn test_output_walks_the_sequence() {
+        let mut triangle = create_triangle(0x7F);
+        let mut outputs = vec![];
+
+        for _ in 0..32 {
+            triangle.clock_timer();
+            outputs.push(triangle.output());
+        }
+
+        assert_eq!(SEQUENCE[1..], outputs[..31]);
+        assert_eq!(SEQUENCE[0], outputs[31]);
+    }
+
+    #[test]
+    fn test_output_holds_when_linear_counter_runs_out() {
+        let mut triangle = create_triangle(1);
+
+        triangle.clock_timer();
+        triangle.clock_quarter_frame();
+        triangle.clock_timer();
+        triangle.clock_timer();
+
+        assert_eq!(SEQUENCE[1], triangle.output());
+    }
+}
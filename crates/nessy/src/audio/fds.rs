@@ -0,0 +1,385 @@
+use crate::audio::ExpansionAudio;
+
+// Registers, as seen from the CPU.
+const WAVE_TABLE_START: u16 = 0x4040;
+const WAVE_TABLE_END: u16 = 0x407F;
+const VOLUME_ENVELOPE: u16 = 0x4080;
+const FREQUENCY_LO: u16 = 0x4082;
+const FREQUENCY_HI: u16 = 0x4083;
+const MOD_ENVELOPE: u16 = 0x4084;
+const MOD_COUNTER: u16 = 0x4085;
+const MOD_FREQUENCY_LO: u16 = 0x4086;
+const MOD_FREQUENCY_HI: u16 = 0x4087;
+const MOD_TABLE_WRITE: u16 = 0x4088;
+const MASTER_VOLUME: u16 = 0x4089;
+const ENVELOPE_SPEED: u16 = 0x408A;
+const VOLUME_GAIN: u16 = 0x4090;
+const MOD_GAIN: u16 = 0x4092;
+
+const WAVE_TABLE_SIZE: usize = 64;
+const MOD_TABLE_SIZE: usize = 32;
+
+const ENVELOPE_DISABLE: u8 = 0b1000_0000;
+const ENVELOPE_INCREASE: u8 = 0b0100_0000;
+
+const WAVE_HALT: u8 = 0b1000_0000;
+const ENVELOPES_HALT: u8 = 0b0100_0000;
+const MOD_HALT: u8 = 0b1000_0000;
+const WAVE_WRITE_ENABLE: u8 = 0b1000_0000;
+
+// Gains above this are allowed, but the output clips to it.
+const MAX_VOLUME_GAIN: u8 = 32;
+
+const MAX_WAVE_SAMPLE: u8 = 63;
+
+// The BIOS sets the envelope speed to this on boot, and nothing really changes it.
+const DEFAULT_ENVELOPE_SPEED: u8 = 0xE8;
+
+// How each mod table entry moves the mod counter. Entry 4 resets it instead.
+const MOD_TABLE_STEPS: [i8; 8] = [0, 1, 2, 4, 0, -4, -2, -1];
+const MOD_TABLE_RESET: u8 = 4;
+
+// The master volume divides the output by 2, 3, 4 or 5, relative to the first.
+const MASTER_VOLUME_LEVELS: [f32; 4] = [1.0, 2.0 / 3.0, 2.0 / 4.0, 2.0 / 5.0];
+
+#[derive(Debug, Default)]
+struct Envelope {
+    control: u8,
+    gain: u8,
+    counter: u32,
+}
+
+impl Envelope {
+    fn write(&mut self, data: u8) {
+        self.control = data;
+        self.counter = 0;
+
+        // A disabled envelope sets the gain directly.
+        if data & ENVELOPE_DISABLE != 0 {
+            self.gain = data & 0x3F;
+        }
+    }
+
+    fn clock(&mut self, envelope_speed: u8) {
+        if self.control & ENVELOPE_DISABLE != 0 {
+            return;
+        }
+
+        self.counter += 1;
+
+        let period = 8 * (envelope_speed as u32 + 1) * ((self.control & 0x3F) as u32 + 1);
+
+        if self.counter < period {
+            return;
+        }
+
+        self.counter = 0;
+
+        if self.control & ENVELOPE_INCREASE != 0 {
+            self.gain = (self.gain + 1).min(MAX_VOLUME_GAIN);
+        } else {
+            self.gain = self.gain.saturating_sub(1);
+        }
+    }
+}
+
+// The Famicom Disk System's sound, a single channel playing a 64 step wavetable of 6-bit
+// samples, with its pitch bent by a modulator stepping through a table of its own.
+#[derive(Debug)]
+pub struct FdsAudio {
+    wave_table: [u8; WAVE_TABLE_SIZE],
+    wave_accumulator: u32,
+    frequency: u16,
+    frequency_control: u8,
+    volume_envelope: Envelope,
+    mod_envelope: Envelope,
+    mod_table: [u8; MOD_TABLE_SIZE],
+    mod_position: u8,
+    mod_accumulator: u32,
+    mod_frequency: u16,
+    mod_control: u8,
+    // A signed 7-bit value, -64 to 63.
+    mod_counter: i8,
+    master_volume: u8,
+    envelope_speed: u8,
+}
+
+impl Default for FdsAudio {
+    fn default() -> Self {
+        Self {
+            wave_table: [0; WAVE_TABLE_SIZE],
+            wave_accumulator: 0,
+            frequency: 0,
+            frequency_control: 0,
+            volume_envelope: Envelope::default(),
+            mod_envelope: Envelope::default(),
+            mod_table: [0; MOD_TABLE_SIZE],
+            mod_position: 0,
+            mod_accumulator: 0,
+            mod_frequency: 0,
+            mod_control: MOD_HALT,
+            mod_counter: 0,
+            master_volume: 0,
+            envelope_speed: DEFAULT_ENVELOPE_SPEED,
+        }
+    }
+}
+
+impl FdsAudio {
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            WAVE_TABLE_START..=WAVE_TABLE_END => {
+                self.wave_table[(address - WAVE_TABLE_START) as usize]
+            }
+            VOLUME_GAIN => self.volume_envelope.gain,
+            MOD_GAIN => self.mod_envelope.gain,
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            WAVE_TABLE_START..=WAVE_TABLE_END if self.wave_write_enabled() => {
+                self.wave_table[(address - WAVE_TABLE_START) as usize] = data & 0x3F;
+            }
+            VOLUME_ENVELOPE => self.volume_envelope.write(data),
+            FREQUENCY_LO => self.frequency = (self.frequency & 0x0F00) | data as u16,
+            FREQUENCY_HI => {
+                self.frequency = (self.frequency & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.frequency_control = data;
+
+                if data & WAVE_HALT != 0 {
+                    self.wave_accumulator = 0;
+                }
+
+                if data & ENVELOPES_HALT != 0 {
+                    self.volume_envelope.counter = 0;
+                    self.mod_envelope.counter = 0;
+                }
+            }
+            MOD_ENVELOPE => self.mod_envelope.write(data),
+            MOD_COUNTER => self.mod_counter = ((data << 1) as i8) >> 1,
+            MOD_FREQUENCY_LO => {
+                self.mod_frequency = (self.mod_frequency & 0x0F00) | data as u16;
+            }
+            MOD_FREQUENCY_HI => {
+                self.mod_frequency = (self.mod_frequency & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                self.mod_control = data;
+            }
+            // The mod table can only be filled while the modulator is halted, each write
+            // taking up two of its 64 steps.
+            MOD_TABLE_WRITE if self.mod_control & MOD_HALT != 0 => {
+                self.mod_table[(self.mod_position >> 1) as usize] = data & 0x7;
+                self.mod_position = (self.mod_position + 2) & 0x3F;
+            }
+            MASTER_VOLUME => self.master_volume = data,
+            ENVELOPE_SPEED => self.envelope_speed = data,
+            _ => {}
+        }
+    }
+
+    fn wave_write_enabled(&self) -> bool {
+        self.master_volume & WAVE_WRITE_ENABLE != 0
+    }
+
+    fn step_modulator(&mut self) {
+        let entry = self.mod_table[(self.mod_position >> 1) as usize];
+
+        self.mod_counter = if entry == MOD_TABLE_RESET {
+            0
+        } else {
+            // Wrap within 7 bits.
+            ((self.mod_counter + MOD_TABLE_STEPS[entry as usize]) << 1) >> 1
+        };
+
+        self.mod_position = (self.mod_position + 1) & 0x3F;
+    }
+
+    // The wave frequency after the modulator has bent it, following the hardware's own
+    // fixed point arithmetic, rounding and all.
+    fn modulated_frequency(&self) -> u32 {
+        let mut temp = self.mod_counter as i32 * self.mod_envelope.gain as i32;
+        let remainder = temp & 0xF;
+        temp >>= 4;
+
+        if remainder > 0 && temp & 0x80 == 0 {
+            temp += if self.mod_counter < 0 { -1 } else { 2 };
+        }
+
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+
+        temp *= self.frequency as i32;
+        let remainder = temp & 0x3F;
+        temp >>= 6;
+
+        if remainder >= 32 {
+            temp += 1;
+        }
+
+        (self.frequency as i32 + temp).max(0) as u32
+    }
+}
+
+impl ExpansionAudio for FdsAudio {
+    fn clock(&mut self) {
+        let wave_halted = self.frequency_control & WAVE_HALT != 0;
+
+        if !wave_halted && self.frequency_control & ENVELOPES_HALT == 0 && self.envelope_speed != 0
+        {
+            self.volume_envelope.clock(self.envelope_speed);
+            self.mod_envelope.clock(self.envelope_speed);
+        }
+
+        if self.mod_control & MOD_HALT == 0 && self.mod_frequency != 0 {
+            self.mod_accumulator += self.mod_frequency as u32;
+
+            if self.mod_accumulator >= 0x10000 {
+                self.mod_accumulator -= 0x10000;
+                self.step_modulator();
+            }
+        }
+
+        // The wave holds its position while halted, or while the table is being written.
+        if !wave_halted && !self.wave_write_enabled() {
+            self.wave_accumulator = (self.wave_accumulator + self.modulated_frequency())
+                % ((WAVE_TABLE_SIZE as u32) << 16);
+        }
+    }
+
+    fn output(&self) -> f32 {
+        if self.wave_write_enabled() {
+            return 0.0;
+        }
+
+        let sample = self.wave_table[(self.wave_accumulator >> 16) as usize];
+        let gain = self.volume_envelope.gain.min(MAX_VOLUME_GAIN);
+
+        let level =
+            (sample as f32 * gain as f32) / (MAX_WAVE_SAMPLE as f32 * MAX_VOLUME_GAIN as f32);
+
+        level * MASTER_VOLUME_LEVELS[(self.master_volume & 0x3) as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    fn create_playing_audio() -> FdsAudio {
+        let mut audio = FdsAudio::default();
+
+        audio.write(MASTER_VOLUME, WAVE_WRITE_ENABLE);
+        for (step, address) in (WAVE_TABLE_START..=WAVE_TABLE_END).enumerate() {
+            audio.write(address, step as u8);
+        }
+        audio.write(MASTER_VOLUME, 0x00);
+
+        audio.write(VOLUME_ENVELOPE, ENVELOPE_DISABLE | MAX_VOLUME_GAIN);
+        audio
+    }
+
+    #[test]
+    fn test_write_ignores_wave_table_unless_enabled() {
+        let mut audio = FdsAudio::default();
+
+        audio.write(0x4041, 0x3F);
+        assert_eq!(0x00, audio.read(0x4041));
+
+        audio.write(MASTER_VOLUME, WAVE_WRITE_ENABLE);
+        audio.write(0x4041, 0xFF);
+        assert_eq!(0x3F, audio.read(0x4041));
+    }
+
+    #[test]
+    fn test_wave_advances_with_frequency() {
+        let mut audio = create_playing_audio();
+
+        // A frequency of $800 moves one step every 32 cycles.
+        audio.write(FREQUENCY_LO, 0x00);
+        audio.write(FREQUENCY_HI, 0x08);
+
+        for _ in 0..32 * 10 {
+            audio.clock();
+        }
+
+        assert_eq!(10, audio.wave_accumulator >> 16);
+    }
+
+    #[parameterized]
+    #[case(0, 1.0)]
+    #[case(1, 2.0 / 3.0)]
+    #[case(3, 2.0 / 5.0)]
+    fn test_output_scales_by_master_volume(master_volume: u8, expected: f32) {
+        let mut audio = create_playing_audio();
+        audio.wave_accumulator = (MAX_WAVE_SAMPLE as u32) << 16;
+
+        audio.write(MASTER_VOLUME, master_volume);
+
+        assert!((expected - audio.output()).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_volume_envelope_increases_gain() {
+        let mut audio = FdsAudio::default();
+        audio.write(ENVELOPE_SPEED, 0x01);
+        audio.write(VOLUME_ENVELOPE, ENVELOPE_INCREASE);
+
+        // A master speed of 1 and envelope speed of 0 ticks every 16 cycles.
+        for _ in 0..16 * 3 {
+            audio.clock();
+        }
+
+        assert_eq!(3, audio.read(VOLUME_GAIN));
+    }
+
+    #[test]
+    fn test_mod_table_writes_only_while_halted() {
+        let mut audio = FdsAudio::default();
+
+        audio.write(MOD_TABLE_WRITE, 0x03);
+        audio.write(MOD_TABLE_WRITE, 0x0F);
+        assert_eq!([3, 7], audio.mod_table[..2]);
+
+        audio.write(MOD_FREQUENCY_HI, 0x00);
+        audio.write(MOD_TABLE_WRITE, 0x01);
+        assert_eq!(0, audio.mod_table[2]);
+    }
+
+    #[parameterized]
+    #[case(0, 10, 10)]
+    #[case(4, 10, 0)]
+    #[case(3, 62, -62)]
+    #[case(5, -62, 62)]
+    fn test_step_modulator_wraps_counter(entry: u8, counter: i8, expected: i8) {
+        let mut audio = FdsAudio::default();
+        audio.mod_table[0] = entry;
+        audio.mod_counter = counter;
+
+        audio.step_modulator();
+
+        assert_eq!(expected, audio.mod_counter);
+        assert_eq!(1, audio.mod_position);
+    }
+
+    #[test]
+    fn test_modulated_frequency_bends_pitch() {
+        let mut audio = FdsAudio::default();
+        audio.write(FREQUENCY_LO, 0x00);
+        audio.write(FREQUENCY_HI, 0x01);
+        audio.write(MOD_ENVELOPE, ENVELOPE_DISABLE | 0x20);
+
+        assert_eq!(0x100, audio.modulated_frequency());
+
+        audio.write(MOD_COUNTER, 0x08);
+        assert!(audio.modulated_frequency() > 0x100);
+
+        audio.write(MOD_COUNTER, 0x78);
+        assert!(audio.modulated_frequency() < 0x100);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod apu;
+pub mod fds;
+pub mod namco_163;
+pub mod sunsoft_5b;
+pub mod wav;
+
+// A sound generator living on the cartridge, whose output is mixed with the APU's.
+pub trait ExpansionAudio {
+    // Advances the sound generator by a single CPU cycle.
+    fn clock(&mut self);
+
+    // The current output level, between 0.0 and 1.0.
+    fn output(&self) -> f32;
+}
@@ -0,0 +1,265 @@
+use std::cell::Cell;
+
+use crate::audio::ExpansionAudio;
+
+// The 163 services one channel every 15 CPU cycles, rather than mixing them all together.
+const CYCLES_PER_CHANNEL: u8 = 15;
+
+const INTERNAL_RAM_SIZE: usize = 128;
+
+// Channel registers start at $40, 8 bytes per channel, with channel 7 at the top.
+const CHANNEL_REGISTERS_START: usize = 0x40;
+const CHANNEL_REGISTERS_SIZE: usize = 8;
+const CHANNEL_COUNT: u8 = 8;
+
+// The last register holds the number of enabled channels in its upper bits.
+const ENABLED_CHANNELS_REGISTER: usize = 0x7F;
+
+const ADDRESS_AUTO_INCREMENT: u8 = 0b1000_0000;
+
+// The loudest a channel can be: sample of 15 at volume 15.
+const MAX_OUTPUT: f32 = 225.0;
+
+// The Namco 163 wavetable sound generator.
+//
+// Up to eight channels read 4-bit samples out of 128 bytes of internal RAM, which also holds
+// their registers. The more channels enabled, the less often each one is updated, and as
+// only the channel being serviced reaches the output, 8 channels come with an audible whine.
+#[derive(Debug)]
+pub struct Namco163Audio {
+    ram: [u8; INTERNAL_RAM_SIZE],
+    // Reads through the data port can bump the address, so it has to change behind `&self`.
+    address: Cell<u8>,
+    current_channel: u8,
+    divider: u8,
+    channel_output: u8,
+}
+
+impl Default for Namco163Audio {
+    fn default() -> Self {
+        Self {
+            ram: [0; INTERNAL_RAM_SIZE],
+            address: Cell::new(0),
+            current_channel: CHANNEL_COUNT - 1,
+            divider: 0,
+            channel_output: 0,
+        }
+    }
+}
+
+impl Namco163Audio {
+    pub fn set_address(&mut self, data: u8) {
+        self.address.set(data);
+    }
+
+    pub fn read_data(&self) -> u8 {
+        let value = self.peek_data();
+        self.increment_address();
+        value
+    }
+
+    // Reads the data port without moving the address on.
+    pub fn peek_data(&self) -> u8 {
+        self.ram[self.ram_address()]
+    }
+
+    pub fn write_data(&mut self, data: u8) {
+        self.ram[self.ram_address()] = data;
+        self.increment_address();
+    }
+
+    fn ram_address(&self) -> usize {
+        (self.address.get() & !ADDRESS_AUTO_INCREMENT) as usize
+    }
+
+    fn increment_address(&self) {
+        let address = self.address.get();
+
+        if address & ADDRESS_AUTO_INCREMENT != 0 {
+            let next = (address.wrapping_add(1) & !ADDRESS_AUTO_INCREMENT) | ADDRESS_AUTO_INCREMENT;
+            self.address.set(next);
+        }
+    }
+
+    fn enabled_channels(&self) -> u8 {
+        ((self.ram[ENABLED_CHANNELS_REGISTER] >> 4) & 0x7) + 1
+    }
+
+    // Reads one of the 4-bit samples packed two to a byte, low nibble first.
+    fn sample(&self, sample_address: u8) -> u8 {
+        let byte = self.ram[(sample_address >> 1) as usize & (INTERNAL_RAM_SIZE - 1)];
+
+        if sample_address & 0x1 == 0 {
+            byte & 0x0F
+        } else {
+            byte >> 4
+        }
+    }
+
+    // Steps a channel's phase along its waveform, returning the sample multiplied by volume.
+    fn update_channel(&mut self, channel: u8) -> u8 {
+        let base = CHANNEL_REGISTERS_START + channel as usize * CHANNEL_REGISTERS_SIZE;
+        let registers = &mut self.ram[base..base + CHANNEL_REGISTERS_SIZE];
+
+        let frequency =
+            registers[0] as u32 | (registers[2] as u32) << 8 | ((registers[4] & 0x03) as u32) << 16;
+        let phase = registers[1] as u32 | (registers[3] as u32) << 8 | (registers[5] as u32) << 16;
+        let length = 256 - (registers[4] & 0xFC) as u32;
+        let wave_address = registers[6];
+        let volume = registers[7] & 0x0F;
+
+        let phase = (phase + frequency) % (length << 16);
+
+        registers[1] = phase as u8;
+        registers[3] = (phase >> 8) as u8;
+        registers[5] = (phase >> 16) as u8;
+
+        let sample = self.sample(((phase >> 16) as u8).wrapping_add(wave_address));
+
+        sample * volume
+    }
+}
+
+impl ExpansionAudio for Namco163Audio {
+    fn clock(&mut self) {
+        self.divider += 1;
+
+        if self.divider < CYCLES_PER_CHANNEL {
+            return;
+        }
+
+        self.divider = 0;
+
+        self.channel_output = self.update_channel(self.current_channel);
+
+        // Channels are serviced from 7 downwards, wrapping at the last enabled one.
+        let lowest_channel = CHANNEL_COUNT - self.enabled_channels();
+
+        self.current_channel = if self.current_channel <= lowest_channel {
+            CHANNEL_COUNT - 1
+        } else {
+            self.current_channel - 1
+        };
+    }
+
+    fn output(&self) -> f32 {
+        self.channel_output as f32 / MAX_OUTPUT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+
+    fn write_ram(audio: &mut Namco163Audio, address: u8, data: &[u8]) {
+        audio.set_address(address | ADDRESS_AUTO_INCREMENT);
+
+        for byte in data {
+            audio.write_data(*byte);
+        }
+    }
+
+    // A channel playing a constant sample at full volume, from a 4 sample waveform.
+    fn write_channel(audio: &mut Namco163Audio, channel: u8, wave_address: u8, enabled: u8) {
+        let base = CHANNEL_REGISTERS_START as u8 + channel * CHANNEL_REGISTERS_SIZE as u8;
+        let enabled_bits = if channel == 7 { (enabled - 1) << 4 } else { 0 };
+
+        write_ram(
+            audio,
+            base,
+            &[
+                0x00,
+                0x00,
+                0x00,
+                0x00,
+                0xFC,
+                0x00,
+                wave_address,
+                0x0F | enabled_bits,
+            ],
+        );
+    }
+
+    fn clock_channel(audio: &mut Namco163Audio) {
+        for _ in 0..CYCLES_PER_CHANNEL {
+            audio.clock();
+        }
+    }
+
+    #[test]
+    fn test_data_port_auto_increments() {
+        let mut audio = Namco163Audio::default();
+
+        write_ram(&mut audio, 0x10, &[0xAA, 0xBB]);
+
+        audio.set_address(0x10 | ADDRESS_AUTO_INCREMENT);
+        assert_eq_hex!(0xAA, audio.read_data());
+        assert_eq_hex!(0xBB, audio.read_data());
+
+        audio.set_address(0x10);
+        assert_eq_hex!(0xAA, audio.read_data());
+        assert_eq_hex!(0xAA, audio.read_data());
+    }
+
+    #[test]
+    fn test_peek_data_does_not_increment() {
+        let mut audio = Namco163Audio::default();
+
+        write_ram(&mut audio, 0x10, &[0xAA, 0xBB]);
+
+        audio.set_address(0x10 | ADDRESS_AUTO_INCREMENT);
+        assert_eq_hex!(0xAA, audio.peek_data());
+        assert_eq_hex!(0xAA, audio.read_data());
+    }
+
+    #[parameterized]
+    #[case(0x00, 0x1)]
+    #[case(0x01, 0x2)]
+    #[case(0x02, 0x3)]
+    fn test_sample_reads_low_nibble_first(sample_address: u8, expected: u8) {
+        let mut audio = Namco163Audio::default();
+
+        write_ram(&mut audio, 0x00, &[0x21, 0x43]);
+
+        assert_eq_hex!(expected, audio.sample(sample_address));
+    }
+
+    #[test]
+    fn test_output_multiplexes_enabled_channels() {
+        let mut audio = Namco163Audio::default();
+
+        // Channel 7 plays a sample of 15, channel 6 a sample of 5.
+        write_ram(&mut audio, 0x00, &[0xFF, 0xFF, 0x55, 0x55]);
+        write_channel(&mut audio, 7, 0x00, 2);
+        write_channel(&mut audio, 6, 0x04, 2);
+
+        clock_channel(&mut audio);
+        assert_eq!(1.0, audio.output());
+
+        clock_channel(&mut audio);
+        assert_eq!(75.0 / MAX_OUTPUT, audio.output());
+
+        clock_channel(&mut audio);
+        assert_eq!(1.0, audio.output());
+    }
+
+    #[test]
+    fn test_update_channel_advances_phase() {
+        let mut audio = Namco163Audio::default();
+
+        // Frequency of $10000 steps one sample per update.
+        write_ram(&mut audio, 0x78, &[0x00, 0x00, 0x00, 0x00, 0xFD, 0x00]);
+
+        audio.update_channel(7);
+        assert_eq_hex!(0x01, audio.ram[0x7D]);
+
+        // The waveform is 4 samples long, so the phase wraps back around.
+        for _ in 0..3 {
+            audio.update_channel(7);
+        }
+        assert_eq_hex!(0x00, audio.ram[0x7D]);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::audio::ExpansionAudio;
+
+// The 5B runs its tone, noise and envelope generators off the CPU clock divided by 16.
+const CLOCK_DIVIDER: u8 = 16;
+
+const CHANNEL_COUNT: usize = 3;
+
+// Registers, selected by a write to $C000 and written through $E000.
+const TONE_PERIOD_LO_A: u8 = 0x0;
+const TONE_PERIOD_HI_C: u8 = 0x5;
+const NOISE_PERIOD: u8 = 0x6;
+const CHANNEL_DISABLE: u8 = 0x7;
+const VOLUME_A: u8 = 0x8;
+const VOLUME_C: u8 = 0xA;
+const ENVELOPE_PERIOD_LO: u8 = 0xB;
+const ENVELOPE_PERIOD_HI: u8 = 0xC;
+const ENVELOPE_SHAPE: u8 = 0xD;
+
+const ENVELOPE_CONTINUE: u8 = 0b1000;
+const ENVELOPE_ATTACK: u8 = 0b0100;
+const ENVELOPE_ALTERNATE: u8 = 0b0010;
+const ENVELOPE_HOLD: u8 = 0b0001;
+
+const VOLUME_ENVELOPE_ENABLE: u8 = 0b1_0000;
+
+// The envelope has 32 steps of 1.5dB, while the fixed channel volumes use every other one.
+const ENVELOPE_STEPS: u8 = 32;
+const DECIBELS_PER_STEP: f32 = 1.5;
+
+#[derive(Debug, Default)]
+struct Tone {
+    period: u16,
+    counter: u16,
+    output: bool,
+}
+
+impl Tone {
+    fn clock(&mut self) {
+        self.counter += 1;
+
+        if self.counter >= self.period.max(1) {
+            self.counter = 0;
+            self.output = !self.output;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Noise {
+    period: u8,
+    counter: u8,
+    shift_register: u32,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            period: 0,
+            counter: 0,
+            shift_register: 1,
+        }
+    }
+}
+
+impl Noise {
+    fn clock(&mut self) {
+        self.counter += 1;
+
+        // The noise generator runs at half the rate of the tone generators.
+        if self.counter >= self.period.max(1) * 2 {
+            self.counter = 0;
+
+            // 17-bit LFSR, tapping bits 0 and 3.
+            let feedback = (self.shift_register ^ (self.shift_register >> 3)) & 0x1;
+            self.shift_register = (self.shift_register >> 1) | (feedback << 16);
+        }
+    }
+
+    fn output(&self) -> bool {
+        self.shift_register & 0x1 != 0
+    }
+}
+
+#[derive(Debug, Default)]
+struct Envelope {
+    period: u16,
+    counter: u16,
+    shape: u8,
+    step: u8,
+    attack: bool,
+    holding: bool,
+}
+
+impl Envelope {
+    fn restart(&mut self, shape: u8) {
+        self.shape = shape;
+        self.counter = 0;
+        self.step = 0;
+        self.attack = shape & ENVELOPE_ATTACK != 0;
+        self.holding = false;
+    }
+
+    fn clock(&mut self) {
+        self.counter += 1;
+
+        if self.counter < self.period.max(1) {
+            return;
+        }
+
+        self.counter = 0;
+
+        if self.holding {
+            return;
+        }
+
+        self.step += 1;
+
+        if self.step < ENVELOPE_STEPS {
+            return;
+        }
+
+        if self.shape & ENVELOPE_CONTINUE == 0 {
+            // Without continue, every shape ends by holding at silence.
+            self.holding = true;
+            self.attack = false;
+            self.step = ENVELOPE_STEPS - 1;
+        } else {
+            if self.shape & ENVELOPE_ALTERNATE != 0 {
+                self.attack = !self.attack;
+            }
+
+            if self.shape & ENVELOPE_HOLD != 0 {
+                self.holding = true;
+                self.step = ENVELOPE_STEPS - 1;
+            } else {
+                self.step = 0;
+            }
+        }
+    }
+
+    fn level(&self) -> u8 {
+        if self.attack {
+            self.step
+        } else {
+            ENVELOPE_STEPS - 1 - self.step
+        }
+    }
+}
+
+// The Sunsoft 5B, an FME-7 with a YM2149F (AY-3-8910 variant) sound generator bolted on.
+//
+// Three square wave channels, each of which can mix in a shared noise generator and take
+// its volume from either a fixed level or a shared envelope generator. Only Gimmick! used it.
+#[derive(Debug, Default)]
+pub struct Sunsoft5b {
+    register_select: u8,
+    tones: [Tone; CHANNEL_COUNT],
+    noise: Noise,
+    envelope: Envelope,
+    tone_disabled: [bool; CHANNEL_COUNT],
+    noise_disabled: [bool; CHANNEL_COUNT],
+    volumes: [u8; CHANNEL_COUNT],
+    divider: u8,
+}
+
+impl Sunsoft5b {
+    pub fn select_register(&mut self, data: u8) {
+        self.register_select = data & 0x0F;
+    }
+
+    pub fn write_register(&mut self, data: u8) {
+        match self.register_select {
+            TONE_PERIOD_LO_A..=TONE_PERIOD_HI_C => {
+                let tone = &mut self.tones[(self.register_select / 2) as usize];
+
+                if self.register_select.is_multiple_of(2) {
+                    tone.period = (tone.period & 0x0F00) | data as u16;
+                } else {
+                    tone.period = (tone.period & 0x00FF) | ((data as u16 & 0x0F) << 8);
+                }
+            }
+            NOISE_PERIOD => self.noise.period = data & 0x1F,
+            CHANNEL_DISABLE => {
+                for channel in 0..CHANNEL_COUNT {
+                    self.tone_disabled[channel] = data & (1 << channel) != 0;
+                    self.noise_disabled[channel] = data & (1 << (channel + 3)) != 0;
+                }
+            }
+            VOLUME_A..=VOLUME_C => {
+                self.volumes[(self.register_select - VOLUME_A) as usize] = data & 0x1F;
+            }
+            ENVELOPE_PERIOD_LO => {
+                self.envelope.period = (self.envelope.period & 0xFF00) | data as u16;
+            }
+            ENVELOPE_PERIOD_HI => {
+                self.envelope.period = (self.envelope.period & 0x00FF) | ((data as u16) << 8);
+            }
+            ENVELOPE_SHAPE => self.envelope.restart(data & 0x0F),
+            _ => {}
+        }
+    }
+
+    // The 5-bit step on the logarithmic DAC for the channel, 0 being silent.
+    fn channel_level(&self, channel: usize) -> u8 {
+        let tone = self.tones[channel].output || self.tone_disabled[channel];
+        let noise = self.noise.output() || self.noise_disabled[channel];
+
+        if !(tone && noise) {
+            return 0;
+        }
+
+        let volume = self.volumes[channel];
+
+        if volume & VOLUME_ENVELOPE_ENABLE != 0 {
+            self.envelope.level()
+        } else if volume & 0x0F == 0 {
+            0
+        } else {
+            (volume & 0x0F) * 2 + 1
+        }
+    }
+}
+
+impl ExpansionAudio for Sunsoft5b {
+    fn clock(&mut self) {
+        self.divider += 1;
+
+        if self.divider < CLOCK_DIVIDER {
+            return;
+        }
+
+        self.divider = 0;
+
+        for tone in self.tones.iter_mut() {
+            tone.clock();
+        }
+
+        self.noise.clock();
+        self.envelope.clock();
+    }
+
+    fn output(&self) -> f32 {
+        let total: f32 = (0..CHANNEL_COUNT)
+            .map(|channel| match self.channel_level(channel) {
+                0 => 0.0,
+                level => {
+                    let attenuation = (ENVELOPE_STEPS - 1 - level) as f32 * DECIBELS_PER_STEP;
+                    10f32.powf(-attenuation / 20.0)
+                }
+            })
+            .sum();
+
+        total / CHANNEL_COUNT as f32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    fn write(sunsoft_5b: &mut Sunsoft5b, register: u8, data: u8) {
+        sunsoft_5b.select_register(register);
+        sunsoft_5b.write_register(data);
+    }
+
+    fn clock_internal(sunsoft_5b: &mut Sunsoft5b, ticks: usize) {
+        for _ in 0..(ticks * CLOCK_DIVIDER as usize) {
+            sunsoft_5b.clock();
+        }
+    }
+
+    #[test]
+    fn test_write_register_sets_tone_period() {
+        let mut sunsoft_5b = Sunsoft5b::default();
+
+        write(&mut sunsoft_5b, 0x2, 0x34);
+        write(&mut sunsoft_5b, 0x3, 0xF2);
+
+        assert_eq!(0x234, sunsoft_5b.tones[1].period);
+    }
+
+    #[test]
+    fn test_output_is_silent_by_default() {
+        let sunsoft_5b = Sunsoft5b::default();
+
+        assert_eq!(0.0, sunsoft_5b.output());
+    }
+
+    #[test]
+    fn test_tone_toggles_every_period() {
+        let mut sunsoft_5b = Sunsoft5b::default();
+
+        write(&mut sunsoft_5b, CHANNEL_DISABLE, 0b0011_1110);
+        write(&mut sunsoft_5b, VOLUME_A, 0x0F);
+        write(&mut sunsoft_5b, TONE_PERIOD_LO_A, 0x04);
+
+        clock_internal(&mut sunsoft_5b, 4);
+        let high = sunsoft_5b.output();
+
+        clock_internal(&mut sunsoft_5b, 4);
+        let low = sunsoft_5b.output();
+
+        assert!(high > 0.0);
+        assert_eq!(0.0, low);
+    }
+
+    #[parameterized]
+    #[case(0x0F, 31)]
+    #[case(0x01, 3)]
+    #[case(0x00, 0)]
+    fn test_channel_level_uses_fixed_volume(volume: u8, expected_level: u8) {
+        let mut sunsoft_5b = Sunsoft5b::default();
+
+        // Tone and noise disabled leaves the channel permanently high.
+        write(&mut sunsoft_5b, CHANNEL_DISABLE, 0b0011_1111);
+        write(&mut sunsoft_5b, VOLUME_A, volume);
+
+        assert_eq!(expected_level, sunsoft_5b.channel_level(0));
+    }
+
+    #[parameterized]
+    #[case(0b0000, 0)]
+    #[case(0b1000, 31)]
+    #[case(0b1001, 0)]
+    #[case(0b1010, 0)]
+    #[case(0b1011, 31)]
+    #[case(0b1100, 0)]
+    #[case(0b1101, 31)]
+    #[case(0b1110, 31)]
+    #[case(0b1111, 0)]
+    fn test_envelope_level_after_first_cycle(shape: u8, expected_level: u8) {
+        let mut sunsoft_5b = Sunsoft5b::default();
+
+        write(&mut sunsoft_5b, CHANNEL_DISABLE, 0b0011_1111);
+        write(&mut sunsoft_5b, VOLUME_A, VOLUME_ENVELOPE_ENABLE);
+        write(&mut sunsoft_5b, ENVELOPE_PERIOD_LO, 0x01);
+        write(&mut sunsoft_5b, ENVELOPE_SHAPE, shape);
+
+        clock_internal(&mut sunsoft_5b, ENVELOPE_STEPS as usize);
+
+        assert_eq!(expected_level, sunsoft_5b.channel_level(0));
+    }
+}
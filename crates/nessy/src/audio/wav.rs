@@ -0,0 +1,61 @@
+use std::io::{self, Write};
+
+const BITS_PER_SAMPLE: u16 = 16;
+const CHANNELS: u16 = 1;
+
+// Size of everything in the RIFF chunk before the sample data.
+const HEADER_SIZE_BYTES: u32 = 36;
+
+// The PCM format code in the fmt chunk.
+const FORMAT_PCM: u16 = 1;
+
+// Writes mono samples (-1.0 to 1.0, clipped outside of it) as a 16-bit PCM WAV.
+pub fn write_wav(writer: &mut impl Write, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let data_size = samples.len() as u32 * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(HEADER_SIZE_BYTES + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&FORMAT_PCM.to_le_bytes())?;
+    writer.write_all(&CHANNELS.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_wav_writes_header_and_samples() {
+        let mut wav = vec![];
+
+        write_wav(&mut wav, 44100, &[0.0, 1.0, -2.0]).unwrap();
+
+        assert_eq!(44 + 6, wav.len());
+        assert_eq!(b"RIFF", &wav[0..4]);
+        assert_eq!(42u32.to_le_bytes(), wav[4..8]);
+        assert_eq!(b"WAVEfmt ", &wav[8..16]);
+        assert_eq!(44100u32.to_le_bytes(), wav[24..28]);
+        assert_eq!(88200u32.to_le_bytes(), wav[28..32]);
+        assert_eq!(b"data", &wav[36..40]);
+        assert_eq!(6u32.to_le_bytes(), wav[40..44]);
+        assert_eq!([0x00, 0x00, 0xFF, 0x7F, 0x01, 0x80], wav[44..]);
+    }
+}
@@ -1 +1,38 @@
+use crate::{cpus::mos_6502::bus::MemoryBus, interpret_result::InstructionResult};
+
 pub mod mos_6502;
+
+// What a frontend, tracer or debugger gets to see of a core's registers, without knowing which
+// core it's looking at.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterSnapshot {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub status: u8,
+    pub stack_pointer: u8,
+    pub program_counter: u16,
+}
+
+// The parts of a CPU core that don't depend on which one it is.
+pub trait Cpu {
+    // Runs the next instruction, along with any interrupt that comes before it.
+    fn step(&mut self) -> InstructionResult;
+
+    fn reset(&mut self);
+
+    // Signals an edge on the NMI line.
+    fn trigger_nmi(&mut self);
+
+    // Holds the IRQ line, until it's released.
+    fn set_irq_line(&mut self, asserted: bool);
+
+    fn snapshot(&self) -> RegisterSnapshot;
+
+    // Total cycles spent executing instructions.
+    fn cycles(&self) -> u64;
+
+    fn bus(&self) -> &dyn MemoryBus;
+
+    fn bus_mut(&mut self) -> &mut dyn MemoryBus;
+}
@@ -1,6 +1,6 @@
 use super::cpu::Mos6502;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum AddressMode {
     Accumulator,
     Relative,
@@ -14,70 +14,122 @@ pub enum AddressMode {
     AbsoluteY,
     IndirectX,
     IndirectY,
+    // (zp), the 65C02's indirect mode without an index.
+    ZeroPageIndirect,
+    // (abs,X), which only the 65C02's JMP uses.
+    AbsoluteIndirectX,
+    // zp,rel: the 65C02's BBR and BBS test a zero page byte, then branch.
+    ZeroPageRelative,
     None,
 }
 
 pub trait MemoryAddressing {
+    // The address an instruction reads its operand from. Indexing across a page costs the
+    // 6502 a cycle, which it spends reading from the address before the carry was added.
     fn get_address(&self, address_mode: &AddressMode) -> u16;
+
+    // The address a store or read-modify-write instruction works on. These always take the
+    // extra cycle on an indexed address, so the read at the un-carried address always happens.
+    fn get_write_address(&self, address_mode: &AddressMode) -> u16;
 }
 
 impl MemoryAddressing for Mos6502 {
     fn get_address(&self, address_mode: &AddressMode) -> u16 {
-        match address_mode {
-            AddressMode::Immediate => self.program_counter,
-            AddressMode::ZeroPage => self.bus.read(self.program_counter) as u16,
-            AddressMode::ZeroPageX => {
-                let pc_address = self.bus.read(self.program_counter);
-                pc_address.wrapping_add(self.registers.x) as u16
-            }
-            AddressMode::ZeroPageY => {
-                let pc_address = self.bus.read(self.program_counter);
-                pc_address.wrapping_add(self.registers.y) as u16
-            }
-            AddressMode::Absolute => self.bus.read_u16(self.program_counter),
-            AddressMode::AbsoluteX => {
-                let pc_address = self.bus.read_u16(self.program_counter);
-                pc_address.wrapping_add(self.registers.x as u16)
-            }
-            AddressMode::AbsoluteY => {
-                let pc_address = self.bus.read_u16(self.program_counter);
-                pc_address.wrapping_add(self.registers.y as u16)
-            }
-            AddressMode::IndirectX => {
-                let base_address = self.bus.read(self.program_counter);
-
-                let pointer = base_address.wrapping_add(self.registers.x);
-                let lo = self.bus.read(pointer as u16);
-                let hi = self.bus.read(pointer.wrapping_add(1) as u16);
-
-                (hi as u16) << 8 | (lo as u16)
-            }
-            AddressMode::IndirectY => {
-                let base_address = self.bus.read(self.program_counter);
-
-                let lo = self.bus.read(base_address as u16);
-                let hi = self.bus.read((base_address).wrapping_add(1) as u16);
-
-                let dereference_base = (hi as u16) << 8 | (lo as u16);
-                dereference_base.wrapping_add(self.registers.y as u16)
-            }
-            AddressMode::None
-            | AddressMode::Implied
-            | AddressMode::Relative
-            | &AddressMode::Accumulator => {
-                panic!("Unsupported address mode: {:?}", &address_mode)
-            }
+        resolve_address(self, address_mode, false)
+    }
+
+    fn get_write_address(&self, address_mode: &AddressMode) -> u16 {
+        resolve_address(self, address_mode, true)
+    }
+}
+
+// Along with the operand's address, this makes the reads the 6502 makes on the way to it.
+fn resolve_address(cpu: &Mos6502, address_mode: &AddressMode, is_write: bool) -> u16 {
+    match address_mode {
+        AddressMode::Immediate => cpu.program_counter,
+        AddressMode::ZeroPage | AddressMode::ZeroPageRelative => {
+            cpu.bus.read(cpu.program_counter) as u16
+        }
+        AddressMode::ZeroPageX => zero_page_indexed(cpu, cpu.registers.x),
+        AddressMode::ZeroPageY => zero_page_indexed(cpu, cpu.registers.y),
+        AddressMode::Absolute => cpu.bus.read_u16(cpu.program_counter),
+        AddressMode::AbsoluteX => {
+            let base_address = cpu.bus.read_u16(cpu.program_counter);
+            indexed(cpu, base_address, cpu.registers.x, is_write)
+        }
+        AddressMode::AbsoluteY => {
+            let base_address = cpu.bus.read_u16(cpu.program_counter);
+            indexed(cpu, base_address, cpu.registers.y, is_write)
+        }
+        AddressMode::IndirectX => {
+            let base_address = cpu.bus.read(cpu.program_counter);
+
+            // The pointer is read before X gets added to it.
+            cpu.bus.read(base_address as u16);
+
+            let pointer = base_address.wrapping_add(cpu.registers.x);
+            let lo = cpu.bus.read(pointer as u16);
+            let hi = cpu.bus.read(pointer.wrapping_add(1) as u16);
+
+            (hi as u16) << 8 | (lo as u16)
+        }
+        AddressMode::IndirectY => {
+            let base_address = cpu.bus.read(cpu.program_counter);
+
+            let lo = cpu.bus.read(base_address as u16);
+            let hi = cpu.bus.read((base_address).wrapping_add(1) as u16);
+
+            let dereference_base = (hi as u16) << 8 | (lo as u16);
+            indexed(cpu, dereference_base, cpu.registers.y, is_write)
+        }
+        AddressMode::ZeroPageIndirect => {
+            let base_address = cpu.bus.read(cpu.program_counter);
+
+            let lo = cpu.bus.read(base_address as u16);
+            let hi = cpu.bus.read(base_address.wrapping_add(1) as u16);
+
+            (hi as u16) << 8 | (lo as u16)
+        }
+        AddressMode::None
+        | AddressMode::AbsoluteIndirectX
+        | AddressMode::Implied
+        | AddressMode::Relative
+        | &AddressMode::Accumulator => {
+            panic!("Unsupported address mode: {:?}", &address_mode)
         }
     }
 }
 
+// Zero page indexing never leaves the zero page, but the 6502 reads the unindexed address
+// while it adds the index.
+fn zero_page_indexed(cpu: &Mos6502, index: u8) -> u16 {
+    let base_address = cpu.bus.read(cpu.program_counter);
+    cpu.bus.read(base_address as u16);
+
+    base_address.wrapping_add(index) as u16
+}
+
+fn indexed(cpu: &Mos6502, base_address: u16, index: u8, is_write: bool) -> u16 {
+    let address = base_address.wrapping_add(index as u16);
+    let uncarried_address = (base_address & 0xFF00) | (address & 0x00FF);
+
+    if is_write || uncarried_address != address {
+        cpu.bus.read(uncarried_address);
+    }
+
+    address
+}
+
 #[cfg(test)]
 mod tests {
     use sif::parameterized;
 
     use super::*;
     use crate::cpus::mos_6502::cpu::Registers;
-    use crate::cpus::mos_6502::instruction_set::helpers::Helpers;
+    use crate::cpus::mos_6502::instruction_set::helpers::{
+        BusAccess::{self, Read},
+        Helpers, RecordingBus,
+    };
     use crate::cpus::mos_6502::{address_mode::MemoryAddressing, cpu::Mos6502};
 
     #[parameterized]
@@ -85,6 +137,7 @@ mod tests {
     #[case(AddressMode::Implied)]
     #[case(AddressMode::Relative)]
     #[case(AddressMode::Accumulator)]
+    #[case(AddressMode::AbsoluteIndirectX)]
     #[should_panic]
     fn test_get_address_with_none_return_err(address_mode: AddressMode) {
         Mos6502::default().get_address(&address_mode);
@@ -101,6 +154,7 @@ mod tests {
     #[case(0xAA, Some(vec![(0xAA, 0x34), (0xAB, 0x12)]), Some(Registers{a:0, x:0, y:0x01}), AddressMode::AbsoluteY, 0x1235)]
     #[case(0xAA, Some(vec![(0xAA, 0x13), (0x14, 0xFC), (0x15, 0xBA)]), Some(Registers{a:0, x:0x1, y:0}), AddressMode::IndirectX, 0xBAFC)]
     #[case(0xAA, Some(vec![(0xAA, 0x50), (0x50, 0xFB), (0x51, 0xFF)]), Some(Registers{a:0, x:0, y:0x1}), AddressMode::IndirectY, 0xFFFC)]
+    #[case(0xAA, Some(vec![(0xAA, 0xFF), (0xFF, 0x34), (0x00, 0x12)]), None, AddressMode::ZeroPageIndirect, 0x1234)]
     fn test_get_address_with_valid(
         program_counter: u16,
         memory: Option<Vec<(u16, u8)>>,
@@ -114,4 +168,39 @@ mod tests {
 
         assert_eq!(expected_result, result);
     }
+
+    #[parameterized]
+    // No page crossed, so no dummy read.
+    #[case(AddressMode::AbsoluteX, false, 0x05, vec![Read(0x0600), Read(0x0601)])]
+    #[case(AddressMode::AbsoluteX, false, 0xF0, vec![Read(0x0600), Read(0x0601), Read(0x12E0)])]
+    #[case(AddressMode::AbsoluteX, true, 0x05, vec![Read(0x0600), Read(0x0601), Read(0x12F5)])]
+    #[case(AddressMode::AbsoluteY, false, 0xF0, vec![Read(0x0600), Read(0x0601), Read(0x12E0)])]
+    #[case(AddressMode::ZeroPageX, false, 0x10, vec![Read(0x0600), Read(0x00F0)])]
+    #[case(AddressMode::IndirectX, false, 0x10, vec![Read(0x0600), Read(0x00F0), Read(0x0000), Read(0x0001)])]
+    #[case(AddressMode::IndirectY, false, 0x10, vec![Read(0x0600), Read(0x00F0), Read(0x00F1)])]
+    #[case(AddressMode::IndirectY, true, 0x10, vec![Read(0x0600), Read(0x00F0), Read(0x00F1), Read(0x0010)])]
+    fn test_get_address_makes_dummy_reads(
+        address_mode: AddressMode,
+        is_write: bool,
+        index: u8,
+        expected_accesses: Vec<BusAccess>,
+    ) {
+        let (cpu, accesses) = RecordingBus::create_cpu(
+            0x0600,
+            vec![(0x0600, 0xF0), (0x0601, 0x12)],
+            Registers {
+                a: 0,
+                x: index,
+                y: index,
+            },
+        );
+
+        if is_write {
+            cpu.get_write_address(&address_mode);
+        } else {
+            cpu.get_address(&address_mode);
+        }
+
+        assert_eq!(expected_accesses, *accesses.borrow());
+    }
 }
@@ -1,11 +1,16 @@
-use crate::roms::ROM;
+use crate::{
+    audio::apu::{self, Apu},
+    ppu::Ppu,
+    roms::{
+        ROM,
+        mappers::{self, Mapper},
+        region::Region,
+    },
+};
 
 // Constants
 const MEMORY_SIZE: usize = 2048;
 
-// Records how large a 16KB block is, for PRG ROM mirroring.
-const SIXTEEN_KILOBYTES: usize = 0x4000;
-
 // NES RAM Mirroring:
 //
 // The NES CPU RAM has 2k KiB available, by nature of the 11 lines attached from CPU to RAM.
@@ -28,6 +33,11 @@ const PPU_REGISTERS_START: u16 = 0x2000;
 const PPU_REGISTERS_MIRROR_RANGE_END: u16 = 0x3FFF;
 const PPU_REGISTERS_MASK: u16 = 0x2007;
 
+const APU_REGISTERS_START: u16 = 0x4000;
+const APU_REGISTERS_END: u16 = 0x401F;
+
+const APU_STATUS: u16 = apu::STATUS;
+
 const CARTRIDGE_START: u16 = 0x4020;
 const CARTRIDGE_END: u16 = 0xFFFF;
 
@@ -39,13 +49,62 @@ pub trait MemoryBus {
     fn read_u16(&self, address: u16) -> u16;
     fn write_u16(&mut self, address: u16, data: u16);
 
+    // Reads without the side effects some registers have on being read, like $2002
+    // acknowledging vblank, so debuggers and trace logs don't change what the program sees.
+    fn peek(&self, address: u16) -> u8 {
+        self.read(address)
+    }
+
     fn insert_rom(&mut self, rom: ROM);
+
+    // For cartridges which aren't built from a ROM image, like the Famicom Disk System.
+    fn insert_cartridge(&mut self, cartridge: Box<dyn Mapper>);
+
+    // Lets the devices on the bus catch up with the cycles the CPU has just spent.
+    fn tick(&mut self, _cycles: u8) {}
+
+    // Whether anything on the bus has raised an NMI since this was last asked.
+    fn take_nmi(&mut self) -> bool {
+        false
+    }
+
+    // Whether anything on the bus is asserting the IRQ line.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    // The mixed output of the sound generators on the bus.
+    fn audio_output(&self) -> f32 {
+        0.0
+    }
+
+    fn switch_disk_side(&mut self) {}
+
+    // Characters a program has printed through a memory-mapped output port, if there is one.
+    fn character_output(&self) -> &[u8] {
+        &[]
+    }
+
+    // Whatever the cartridge wants kept between sessions.
+    fn save_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    // Whether BRK ends the program. Games on the NES use it as just another interrupt.
+    fn ends_on_brk(&self) -> bool {
+        false
+    }
 }
 
+const PATTERN_TABLE_START: u16 = 0x0000;
+const PATTERN_TABLE_END: u16 = 0x1FFF;
+
 #[derive(Debug)]
 pub struct Bus {
     cpu_memory: [u8; MEMORY_SIZE],
-    rom: Option<ROM>,
+    cartridge: Option<Box<dyn Mapper>>,
+    ppu: Ppu,
+    apu: Apu,
 }
 
 impl Default for Bus {
@@ -53,14 +112,49 @@ impl Default for Bus {
         // Zero inits the RAM but NES state could be garbage on hardware.
         Self {
             cpu_memory: [0; MEMORY_SIZE],
-            rom: None,
+            cartridge: None,
+            ppu: Ppu::default(),
+            apu: Apu::default(),
         }
     }
 }
 
 impl Bus {
     pub fn new(cpu_memory: [u8; MEMORY_SIZE], rom: Option<ROM>) -> Self {
-        Self { cpu_memory, rom }
+        let region = rom
+            .as_ref()
+            .map_or(Region::Ntsc, |rom| rom.metadata().region());
+
+        Self {
+            cpu_memory,
+            cartridge: rom.map(mappers::create),
+            ppu: Ppu::default(),
+            apu: Apu::new(region),
+        }
+    }
+
+    // The APU's timings depend on the region, which cartridges without a header can't give.
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.apu = Apu::new(region);
+        self
+    }
+
+    // Pattern table fetches made by the PPU, which always go through the cartridge mapper.
+    pub fn ppu_read(&mut self, address: u16) -> u8 {
+        match (address, self.cartridge.as_mut()) {
+            (PATTERN_TABLE_START..=PATTERN_TABLE_END, Some(cartridge)) => {
+                cartridge.ppu_read(address)
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn ppu_write(&mut self, address: u16, data: u8) {
+        if let (PATTERN_TABLE_START..=PATTERN_TABLE_END, Some(cartridge)) =
+            (address, self.cartridge.as_mut())
+        {
+            cartridge.ppu_write(address, data);
+        }
     }
 }
 
@@ -72,56 +166,53 @@ impl MemoryBus for Bus {
                 self.cpu_memory[addr]
             }
             PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
-                let _addr = (address & PPU_REGISTERS_MASK) as usize;
-                todo!("PPU read");
+                self.ppu.read(address & PPU_REGISTERS_MASK)
             }
+            APU_STATUS => self.apu.read(address),
+            // There are no controllers yet, so the rest read as nothing.
+            APU_REGISTERS_START..=APU_REGISTERS_END => 0,
             CARTRIDGE_START..=CARTRIDGE_END => {
-                // PRG ROM size can be 16KB or 32KB.
-                // As there's a 32KB addressible space here, a 16KB ROM basically
-                // means that it's mirrored (i.e. mapped to the lower 16KB.)
-                // If we've got a 16KB PRG ROM, and the address is over 16KB, map it down.
-                //
-                if let Some(rom) = self.rom.as_ref() {
-                    let mut addr = address as usize;
-
-                    if rom.program_rom().len() == SIXTEEN_KILOBYTES && addr >= SIXTEEN_KILOBYTES {
-                        addr %= SIXTEEN_KILOBYTES;
-                    }
-
-                    rom.program_rom()[addr]
+                if let Some(cartridge) = self.cartridge.as_ref() {
+                    cartridge.cpu_read(address)
                 } else {
                     panic!("Can't access the cartridge rom!");
                 }
             }
-            _ => {
-                println!(
-                    "Can't access address `0x{:x}`. This needs to be an error returned!",
-                    address
-                );
-                0
+        }
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        match address {
+            PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
+                self.ppu.peek(address & PPU_REGISTERS_MASK)
             }
+            APU_STATUS => self.apu.peek(address),
+            // Without a cartridge there's nothing to see, which isn't worth a panic here.
+            CARTRIDGE_START..=CARTRIDGE_END => self
+                .cartridge
+                .as_ref()
+                .map_or(0, |cartridge| cartridge.cpu_peek(address)),
+            _ => self.read(address),
         }
     }
 
     fn write(&mut self, address: u16, data: u8) {
-        if address == 0x4015 {
-            println!("ARSE: {:02X}", data);
-        }
         match address {
             CPU_RAM_START..=CPU_RAM_MIRROR_RANGE_END => {
                 let addr = (address & CPU_RAM_ADDRESS_MASK) as usize;
                 self.cpu_memory[addr] = data;
             }
             PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
-                let _addr = (address & PPU_REGISTERS_MASK) as usize;
-                todo!("PPU write")
+                self.ppu.write(address & PPU_REGISTERS_MASK, data)
             }
-            _ => {
-                println!(
-                    "Can't access address `0x{:x}`. This needs to be an error returned!",
+            APU_REGISTERS_START..=APU_REGISTERS_END => self.apu.write(address, data),
+            CARTRIDGE_START..=CARTRIDGE_END => match self.cartridge.as_mut() {
+                Some(cartridge) => cartridge.cpu_write(address, data),
+                None => println!(
+                    "Can't write `0x{:x}` without a cartridge inserted.",
                     address
-                );
-            }
+                ),
+            },
         }
     }
 
@@ -133,8 +224,9 @@ impl MemoryBus for Bus {
                 self.cpu_memory[addr..(addr + data.len())].copy_from_slice(data);
             }
             PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
-                let _addr = (start_address & PPU_REGISTERS_MASK) as usize;
-                todo!("PPU write_slice")
+                for (offset, &byte) in data.iter().enumerate() {
+                    self.write(start_address + offset as u16, byte);
+                }
             }
             _ => {
                 println!(
@@ -154,11 +246,8 @@ impl MemoryBus for Bus {
                 let hi_byte = self.read(addr_u16 + 1) as u16;
                 (hi_byte << 8) | lo_byte
             }
-            PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
-                let _addr = (address & PPU_REGISTERS_MASK) as usize;
-                todo!("PPU read_u16");
-            }
-            CARTRIDGE_START..=CARTRIDGE_END => {
+            PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END
+            | CARTRIDGE_START..=CARTRIDGE_END => {
                 let lo_byte = self.read(address) as u16;
                 let hi_byte = self.read(address + 1) as u16;
                 (hi_byte << 8) | lo_byte
@@ -184,8 +273,8 @@ impl MemoryBus for Bus {
                 self.write(addr_u16 + 1, hi_byte);
             }
             PPU_REGISTERS_START..=PPU_REGISTERS_MIRROR_RANGE_END => {
-                let _addr = (address & PPU_REGISTERS_MASK) as usize;
-                todo!("PPU read_u16");
+                self.write(address, data as u8);
+                self.write(address + 1, (data >> 8) as u8);
             }
             _ => {
                 println!(
@@ -197,7 +286,65 @@ impl MemoryBus for Bus {
     }
 
     fn insert_rom(&mut self, rom: ROM) {
-        self.rom = Some(rom);
+        self.apu = Apu::new(rom.metadata().region());
+        self.cartridge = Some(mappers::create(rom));
+    }
+
+    fn insert_cartridge(&mut self, cartridge: Box<dyn Mapper>) {
+        self.cartridge = Some(cartridge);
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        for _ in 0..cycles {
+            self.ppu.clock();
+            self.apu.clock();
+
+            // Samples always live in cartridge space, from $8000 up.
+            if let Some(address) = self.apu.pending_dmc_fetch() {
+                let data = self
+                    .cartridge
+                    .as_ref()
+                    .map_or(0, |cartridge| cartridge.cpu_read(address));
+                self.apu.fill_dmc_sample(data);
+            }
+
+            if let Some(cartridge) = self.cartridge.as_mut() {
+                cartridge.clock();
+            }
+        }
+    }
+
+    fn take_nmi(&mut self) -> bool {
+        self.ppu.take_nmi()
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.apu.irq_pending()
+            || self
+                .cartridge
+                .as_ref()
+                .is_some_and(|cartridge| cartridge.irq_pending())
+    }
+
+    // Expansion audio is mixed in at the same level as the APU.
+    fn audio_output(&self) -> f32 {
+        self.apu.output()
+            + self
+                .cartridge
+                .as_ref()
+                .map_or(0.0, |cartridge| cartridge.audio_output())
+    }
+
+    fn switch_disk_side(&mut self) {
+        if let Some(cartridge) = self.cartridge.as_mut() {
+            cartridge.switch_disk_side();
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        self.cartridge
+            .as_ref()
+            .and_then(|cartridge| cartridge.save_data())
     }
 }
 
@@ -247,6 +394,52 @@ mod test {
         assert_eq_hex!(expected_value, bus.read_u16(address));
     }
 
+    #[test]
+    fn test_apu_status_reads_back_through_bus() {
+        let mut bus = Bus::default();
+
+        bus.write(0x4015, 0b0001);
+        bus.write(0x4003, 0b0000_1000);
+
+        assert_eq_hex!(0b0001, bus.read(0x4015));
+    }
+
+    #[test]
+    fn test_irq_pending_given_apu_frame_interrupt() {
+        let mut bus = Bus::default();
+
+        // The four step sequence ends 29829 cycles after power on.
+        for _ in 0..29828 {
+            bus.tick(1);
+        }
+        assert!(!bus.irq_pending());
+
+        bus.tick(1);
+        assert!(bus.irq_pending());
+    }
+
+    #[test]
+    fn test_peek_leaves_apu_frame_interrupt_pending() {
+        let mut bus = Bus::default();
+
+        for _ in 0..29829 {
+            bus.tick(1);
+        }
+
+        assert_eq_hex!(0x40, bus.peek(0x4015) & 0x40);
+        assert!(bus.irq_pending());
+
+        assert_eq_hex!(0x40, bus.read(0x4015) & 0x40);
+        assert!(!bus.irq_pending());
+    }
+
+    #[test]
+    fn test_peek_without_a_cartridge_reads_nothing() {
+        let bus = Bus::default();
+
+        assert_eq_hex!(0x00, bus.peek(0x8000));
+    }
+
     #[test]
     fn test_write_u16_set_correct_value() {
         let memory = setup_memory(vec![]);
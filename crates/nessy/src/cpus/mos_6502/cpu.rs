@@ -1,14 +1,20 @@
 use crate::{
-    cpus::mos_6502::{
-        bus::{Bus, MemoryBus},
-        opcode::OPCODES,
-        status::Flags,
+    cpus::{
+        Cpu, RegisterSnapshot,
+        mos_6502::{
+            bus::{Bus, MemoryBus},
+            cycle::InstructionInProgress,
+            instruction_set::stack::StackWrap,
+            interrupt::Interrupts,
+            status::Flags,
+            variant::Variant,
+        },
     },
     interpret_result::{InstructionResult, ProgramResult},
-    roms::ROM,
+    roms::{ROM, mappers::Mapper, program::Program},
 };
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Registers {
     pub a: u8,
     pub x: u8,
@@ -23,12 +29,34 @@ pub const RESET_VECTOR: u16 = 0xFFFC;
 // This is set to 0xFD to account for the reset cycle.
 pub const STACK_POINTER_RESET: u8 = 0xFD;
 
+// What the 65C02's WAI or STP has left the CPU waiting for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Halt {
+    Interrupt,
+    Reset,
+}
+
 pub struct Mos6502 {
     pub registers: Registers,
     pub status: Flags,
     pub program_counter: u16,
     pub stack_pointer: u8,
     pub bus: Box<dyn MemoryBus>,
+    // Total cycles spent executing instructions, one for each access on the bus.
+    pub cycles: u64,
+
+    // The instruction `tick` is part way through, if any.
+    pub(crate) in_progress: Option<InstructionInProgress>,
+
+    pub(crate) interrupts: Interrupts,
+
+    // Only kept once asked for, with `log_stack_wraps`.
+    pub(crate) stack_wraps: Option<Vec<StackWrap>>,
+
+    // Which chip's opcodes to run. The NES's 2A03 unless told otherwise.
+    pub(crate) variant: Variant,
+
+    pub(crate) halt: Option<Halt>,
 }
 
 impl Default for Mos6502 {
@@ -38,6 +66,8 @@ impl Default for Mos6502 {
 }
 
 impl Mos6502 {
+    // The bus is what the CPU is plugged into: `Bus` for the NES, or `FlatBus` for plain
+    // 6502 programs.
     pub fn new(bus: Box<dyn MemoryBus>) -> Self {
         Self {
             registers: Registers::default(),
@@ -45,9 +75,24 @@ impl Mos6502 {
             program_counter: 0,
             stack_pointer: STACK_POINTER_RESET,
             bus,
+            cycles: 0,
+            in_progress: None,
+            interrupts: Interrupts::default(),
+            stack_wraps: None,
+            variant: Variant::default(),
+            halt: None,
         }
     }
 
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
     // TODO: Implement the full ASM clone of the start cycle.
     // see: docs/illustration-of-start-cycle.txt
     pub fn reset(&mut self) {
@@ -55,6 +100,19 @@ impl Mos6502 {
         self.status = Flags::from_bits_truncate(DEFAULT_FLAGS);
         self.stack_pointer = STACK_POINTER_RESET;
         self.program_counter = 0;
+        self.halt = None;
+        self.reset_interrupts();
+    }
+
+    // Starts (or stops) logging every time the stack wraps around page $01, rather than just
+    // letting it happen the way the hardware does. Handy for tracking down whatever caused it.
+    pub fn log_stack_wraps(&mut self, enabled: bool) {
+        self.stack_wraps = enabled.then(Vec::new);
+    }
+
+    // The stack wraps logged so far, oldest first.
+    pub fn stack_wraps(&self) -> &[StackWrap] {
+        self.stack_wraps.as_deref().unwrap_or_default()
     }
 
     pub fn load_program(&mut self, rom: ROM) {
@@ -64,6 +122,40 @@ impl Mos6502 {
         self.program_counter = self.bus.read_u16(RESET_VECTOR);
     }
 
+    pub fn load_cartridge(&mut self, cartridge: Box<dyn Mapper>) {
+        self.bus.insert_cartridge(cartridge);
+        self.reset();
+
+        self.program_counter = self.bus.read_u16(RESET_VECTOR);
+    }
+
+    // Copies a plain program into memory and starts it running, for buses with RAM where it's
+    // being loaded.
+    pub fn load_program_image(&mut self, program: &Program) {
+        for segment in program.segments() {
+            self.bus.write_slice(segment.address, &segment.data);
+        }
+
+        self.reset();
+
+        self.program_counter = program.start_address();
+    }
+
+    // Read-modify-write instructions write the value they read straight back while they work
+    // out the new one, then write that. Returns the new value.
+    pub fn read_modify_write<F>(&mut self, address: u16, operation: F) -> u8
+    where
+        F: FnOnce(&mut Mos6502, u8) -> u8,
+    {
+        let value = self.bus.read(address);
+        self.bus.write(address, value);
+
+        let result = operation(self, value);
+        self.bus.write(address, result);
+
+        result
+    }
+
     pub fn run(&mut self) -> ProgramResult {
         self.run_with_callback(|_| {})
     }
@@ -75,43 +167,83 @@ impl Mos6502 {
         loop {
             callback(self);
 
-            let opcode_byte = self.bus.read(self.program_counter);
-            self.program_counter += 1;
-
-            match OPCODES.get(&opcode_byte) {
-                Some(opcode) => {
-                    // println!(
-                    //     "Exec: {} (0x{:x}) | Bytes: {} | PC: 0x{:x}",
-                    //     opcode.mnemonic,
-                    //     opcode.opcode,
-                    //     opcode.bytes,
-                    //     self.program_counter - 1
-                    // );
-                    //
-                    match (opcode.execute)(opcode, self) {
-                        InstructionResult::Ok => (),
-                        InstructionResult::IllegalInstruction => {
-                            panic!("Illegal instruction! Opcode: {:?}.", opcode);
-                        }
-                        InstructionResult::StackOverflow => {
-                            panic!("Stack overflow occurred! Opcode: {:?}.", opcode);
-                        }
-                        InstructionResult::StackUnderflow => {
-                            panic!("Stack underflow occurred! Opcode: {:?}.", opcode);
-                        }
-                        InstructionResult::EndProgram => return ProgramResult::Ok,
-                    }
+            match self.step() {
+                InstructionResult::Ok => {}
+                InstructionResult::IllegalInstruction => {
+                    panic!("Illegal instruction at 0x{:04X}.", self.program_counter);
                 }
-                None => panic!("Opcode not implemented: 0x{:x}.", opcode_byte),
+                InstructionResult::EndProgram => return ProgramResult::Ok,
+            }
+        }
+    }
+
+    // Executes the instruction at the program counter, or finishes off the one `tick` is part
+    // way through, for when the caller wants to be in charge of the loop. It's run a cycle at a
+    // time, so the cycles counted are the ones it spends, page crossings and taken branches
+    // included.
+    pub fn step(&mut self) -> InstructionResult {
+        loop {
+            if let Some(result) = self.tick() {
+                return result;
             }
         }
     }
 }
 
+impl Cpu for Mos6502 {
+    fn step(&mut self) -> InstructionResult {
+        Mos6502::step(self)
+    }
+
+    fn reset(&mut self) {
+        Mos6502::reset(self)
+    }
+
+    fn trigger_nmi(&mut self) {
+        Mos6502::trigger_nmi(self)
+    }
+
+    fn set_irq_line(&mut self, asserted: bool) {
+        Mos6502::set_irq_line(self, asserted)
+    }
+
+    fn snapshot(&self) -> RegisterSnapshot {
+        RegisterSnapshot {
+            a: self.registers.a,
+            x: self.registers.x,
+            y: self.registers.y,
+            status: self.status.bits(),
+            stack_pointer: self.stack_pointer,
+            program_counter: self.program_counter,
+        }
+    }
+
+    fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    fn bus(&self) -> &dyn MemoryBus {
+        self.bus.as_ref()
+    }
+
+    fn bus_mut(&mut self) -> &mut dyn MemoryBus {
+        self.bus.as_mut()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use sif::parameterized;
+
     use super::*;
-    use crate::cpus::mos_6502::status::Flags;
+    use crate::cpus::mos_6502::{
+        flat_bus::FlatBus,
+        instruction_set::helpers::{
+            BusAccess::{Read, Write},
+            RecordingBus,
+        },
+        status::Flags,
+    };
 
     #[test]
     fn reset_resets_everything() {
@@ -131,4 +263,131 @@ mod tests {
 
         assert_eq!(0, cpu.program_counter);
     }
+
+    #[test]
+    fn step_executes_one_instruction() {
+        let mut cpu = Mos6502::default();
+
+        // LDA #$42, LDX #$24
+        cpu.bus.write_slice(0x0000, &[0xA9, 0x42, 0xA2, 0x24]);
+
+        assert_eq!(InstructionResult::Ok, cpu.step());
+
+        assert_eq!(0x42, cpu.registers.a);
+        assert_eq!(0x00, cpu.registers.x);
+        assert_eq!(0x0002, cpu.program_counter);
+        assert_eq!(2, cpu.cycles);
+    }
+
+    #[parameterized]
+    // LDA $12F0,X without and with a page crossed
+    #[case(vec![0xBD, 0xF0, 0x12], 0x05, 4)]
+    #[case(vec![0xBD, 0xF0, 0x12], 0x10, 5)]
+    // BNE +$10, taken
+    #[case(vec![0xD0, 0x10], 0x00, 3)]
+    fn step_counts_every_cycle_spent(code: Vec<u8>, x: u8, expected_cycles: u64) {
+        let mut cpu = Mos6502::default();
+        cpu.bus.write_slice(0x0000, &code);
+        cpu.registers.x = x;
+
+        cpu.step();
+
+        assert_eq!(expected_cycles, cpu.cycles);
+    }
+
+    #[test]
+    fn can_be_driven_as_a_cpu() {
+        let mut cpu = Mos6502::default();
+
+        // LDA #$42, LDX #$24
+        cpu.bus.write_slice(0x0000, &[0xA9, 0x42, 0xA2, 0x24]);
+
+        let cpu: &mut dyn Cpu = &mut cpu;
+
+        cpu.step();
+        cpu.step();
+
+        assert_eq!(
+            RegisterSnapshot {
+                a: 0x42,
+                x: 0x24,
+                y: 0x00,
+                status: DEFAULT_FLAGS,
+                stack_pointer: STACK_POINTER_RESET,
+                program_counter: 0x0004,
+            },
+            cpu.snapshot()
+        );
+        assert_eq!(4, cpu.cycles());
+        assert_eq!(0xA9, cpu.bus().read(0x0000));
+    }
+
+    #[test]
+    fn read_modify_write_writes_value_back_first() {
+        // INC $1200,X
+        let (mut cpu, accesses) = RecordingBus::create_cpu(
+            0x0600,
+            vec![
+                (0x0600, 0xFE),
+                (0x0601, 0x00),
+                (0x0602, 0x12),
+                (0x1210, 0x41),
+            ],
+            Registers {
+                a: 0,
+                x: 0x10,
+                y: 0,
+            },
+        );
+
+        cpu.step();
+
+        assert_eq!(
+            vec![
+                Read(0x0600),
+                Read(0x0601),
+                Read(0x0602),
+                Read(0x1210),
+                Read(0x1210),
+                Write(0x1210, 0x41),
+                Write(0x1210, 0x42),
+            ],
+            *accesses.borrow()
+        );
+    }
+
+    #[test]
+    fn load_program_image_runs_from_start_address() {
+        let mut cpu = Mos6502::new(Box::new(FlatBus::default()));
+
+        // LDA #$01, STA $0200, BRK
+        let program = Program::from_binary(&[0xA9, 0x01, 0x8D, 0x00, 0x02, 0x00], 0x0600).unwrap();
+        cpu.load_program_image(&program);
+
+        assert_eq!(0x0600, cpu.program_counter);
+        assert_eq!(ProgramResult::Ok, cpu.run());
+        assert_eq!(0x01, cpu.bus.read(0x0200));
+    }
+
+    #[test]
+    fn flat_bus_prints_through_output_port() {
+        let bus = FlatBus::default()
+            .with_rom_range(0x0600..=0x06FF)
+            .with_output_port(0xF001);
+        let mut cpu = Mos6502::new(Box::new(bus));
+
+        // LDA #$4F, STA $F001, LDA #$4B, STA $F001, STA $0600, BRK
+        let program = Program::from_binary(
+            &[
+                0xA9, 0x4F, 0x8D, 0x01, 0xF0, 0xA9, 0x4B, 0x8D, 0x01, 0xF0, 0x8D, 0x00, 0x06, 0x00,
+            ],
+            0x0600,
+        )
+        .unwrap();
+        cpu.load_program_image(&program);
+        cpu.run();
+
+        assert_eq!(b"OK", cpu.bus.character_output());
+        assert_eq!(0xA9, cpu.bus.read(0x0600));
+    }
 }
@@ -0,0 +1,945 @@
+use crate::{
+    cpus::mos_6502::{
+        address_mode::AddressMode,
+        cpu::{Halt, Mos6502},
+        instruction_set::{
+            arithmetic::Arithmetic,
+            branch::Branch,
+            compare::Compare,
+            decrement::Decrement,
+            increment::Increment,
+            jump::{Jump, JumpType},
+            load::Load,
+            logical::Logical,
+            rotate::Rotate,
+            shift::Shift,
+            stack::Stack,
+            system::{BRK_OPCODE, LONG_NOP_EXTRA_CYCLES, LONG_NOP_OPCODE, System},
+        },
+        interrupt::Interrupt,
+        opcode::OpCode,
+        status::Flags,
+        variant::Variant,
+    },
+    interpret_result::InstructionResult,
+};
+
+// Every cycle of a 6502 instruction is exactly one read or write on the bus, and `tick` runs
+// them one at a time: first the address mode's cycles, which find the operand, then the
+// operation's, which use it. What each instruction does to the registers is shared with the
+// opcode table's execute fns, which make the same accesses all in one go.
+
+// What an instruction does once its address mode has found the operand.
+#[derive(Clone, Copy)]
+enum Operation {
+    // Works on the registers alone, with the opcode's execute fn, after reading and ignoring
+    // the byte after the opcode.
+    Implied,
+    // The 65C02's one cycle NOPs, which are done as soon as their opcode's been fetched.
+    OpcodeOnly,
+    Read(fn(&mut Mos6502, u8)),
+    Write(fn(&Mos6502) -> u8),
+    Modify(fn(&mut Mos6502, u8) -> u8),
+    Branch,
+    // The 65C02's BBR and BBS, which test a zero page byte before they get to the offset.
+    BranchOnBit,
+    Push(fn(&Mos6502) -> u8),
+    Pull(fn(&mut Mos6502, u8)),
+    Jump,
+    JumpIndirect(JumpType),
+    JumpToSubroutine,
+    ReturnFromSubroutine,
+    ReturnFromInterrupt,
+    Break,
+    // The 65C02's NOP $5C, which reads its operand and then carries on reading.
+    LongNop,
+    // The 65C02's WAI and STP, which leave the CPU halted with the opcode's execute fn.
+    Halt,
+}
+
+impl Operation {
+    #[rustfmt::skip]
+    fn of(opcode: &OpCode, variant: Variant) -> Self {
+        match (opcode.mnemonic, &opcode.address_mode) {
+            ("BRK", _) => Operation::Break,
+            ("RTI", _) => Operation::ReturnFromInterrupt,
+            ("RTS", _) => Operation::ReturnFromSubroutine,
+            ("JSR", _) => Operation::JumpToSubroutine,
+            ("JMP", AddressMode::Absolute) => Operation::Jump,
+            ("JMP", AddressMode::AbsoluteIndirectX) => Operation::JumpIndirect(JumpType::IndexedIndirect),
+            ("JMP", _) if variant == Variant::Wdc65C02 => Operation::JumpIndirect(JumpType::IndirectFixed),
+            ("JMP", _) => Operation::JumpIndirect(JumpType::Indirect),
+
+            ("PHA", _) => Operation::Push(|cpu| cpu.registers.a),
+            ("PHP", _) => Operation::Push(Stack::pushed_status),
+            ("PHX", _) => Operation::Push(|cpu| cpu.registers.x),
+            ("PHY", _) => Operation::Push(|cpu| cpu.registers.y),
+            ("PLA", _) => Operation::Pull(Load::load_accumulator),
+            ("PLP", _) => Operation::Pull(Stack::restore_status),
+            ("PLX", _) => Operation::Pull(Load::load_x),
+            ("PLY", _) => Operation::Pull(Load::load_y),
+
+            ("WAI" | "STP", _) => Operation::Halt,
+
+            ("NOP", AddressMode::None) => Operation::OpcodeOnly,
+            ("NOP", _) if variant == Variant::Wdc65C02 && opcode.opcode == LONG_NOP_OPCODE => Operation::LongNop,
+
+            (_, AddressMode::Implied | AddressMode::Accumulator) => Operation::Implied,
+            (_, AddressMode::Relative) => Operation::Branch,
+            (_, AddressMode::ZeroPageRelative) => Operation::BranchOnBit,
+
+            ("LDA", _) => Operation::Read(Load::load_accumulator),
+            ("LDX", _) => Operation::Read(Load::load_x),
+            ("LDY", _) => Operation::Read(Load::load_y),
+            ("LAX", _) => Operation::Read(Load::load_accumulator_and_x),
+            ("ADC", _) => Operation::Read(|cpu, value| {
+                let decimal_mode = cpu.variant.decimal_mode();
+                Arithmetic::add(cpu, value, decimal_mode)
+            }),
+            ("SBC", _) => Operation::Read(|cpu, value| {
+                let decimal_mode = cpu.variant.decimal_mode();
+                Arithmetic::subtract(cpu, value, decimal_mode)
+            }),
+            ("AND", _) => Operation::Read(Logical::and_accumulator),
+            ("EOR", _) => Operation::Read(Logical::eor_accumulator),
+            ("ORA", _) => Operation::Read(Logical::ora_accumulator),
+            ("BIT", AddressMode::Immediate) => Operation::Read(Logical::test_bits_immediate),
+            ("BIT", _) => Operation::Read(Logical::test_bits),
+            ("CMP", _) => Operation::Read(Compare::compare_accumulator),
+            ("CPX", _) => Operation::Read(Compare::compare_x),
+            ("CPY", _) => Operation::Read(Compare::compare_y),
+            ("NOP", _) => Operation::Read(|_, _| {}),
+
+            ("STA", _) => Operation::Write(|cpu| cpu.registers.a),
+            ("STX", _) => Operation::Write(|cpu| cpu.registers.x),
+            ("STY", _) => Operation::Write(|cpu| cpu.registers.y),
+            ("STZ", _) => Operation::Write(|_| 0),
+            ("SAX", _) => Operation::Write(|cpu| cpu.registers.a & cpu.registers.x),
+
+            ("ASL", _) => Operation::Modify(Shift::arithmetic_shift),
+            ("LSR", _) => Operation::Modify(Shift::logical_shift),
+            ("ROL", _) => Operation::Modify(Rotate::rotate_left),
+            ("ROR", _) => Operation::Modify(Rotate::rotate_right),
+            ("INC", _) => Operation::Modify(Increment::increment),
+            ("DEC", _) => Operation::Modify(Decrement::decrement),
+            ("TRB", _) => Operation::Modify(Logical::reset_bits),
+            ("TSB", _) => Operation::Modify(Logical::set_bits),
+            (mnemonic, _) if mnemonic.starts_with("RMB") => Operation::Modify(Logical::RESET_BIT[opcode.bit_index()]),
+            (mnemonic, _) if mnemonic.starts_with("SMB") => Operation::Modify(Logical::SET_BIT[opcode.bit_index()]),
+            ("DCP", _) => Operation::Modify(Decrement::decrement_then_compare),
+            ("SLO", _) => Operation::Modify(Logical::shift_left_then_ora),
+            ("SRE", _) => Operation::Modify(Logical::shift_right_then_eor),
+            ("RLA", _) => Operation::Modify(Logical::rotate_left_then_and),
+            ("ISB", _) => Operation::Modify(|cpu, value| {
+                let decimal_mode = cpu.variant.decimal_mode();
+                Arithmetic::increment_then_subtract(cpu, value, decimal_mode)
+            }),
+            ("RRA", _) => Operation::Modify(|cpu, value| {
+                let decimal_mode = cpu.variant.decimal_mode();
+                Arithmetic::rotate_right_then_add(cpu, value, decimal_mode)
+            }),
+
+            (mnemonic, address_mode) => {
+                unreachable!("No microcode for {} with {:?}.", mnemonic, address_mode)
+            }
+        }
+    }
+
+    // Whether the address mode has to find the operand before the operation can start.
+    fn has_operand(self) -> bool {
+        matches!(
+            self,
+            Operation::Read(_)
+                | Operation::Write(_)
+                | Operation::Modify(_)
+                | Operation::BranchOnBit
+                | Operation::LongNop
+        )
+    }
+}
+
+// The part of the instruction the next cycle is for, and how many cycles into it that is.
+#[derive(Clone, Copy)]
+enum Stage {
+    Addressing(u8),
+    Operating(u8),
+}
+
+// An instruction which has been started by `Mos6502::tick` but hasn't finished yet.
+pub struct InstructionInProgress {
+    opcode: &'static OpCode,
+    operation: Operation,
+    // The interrupt being run in place of the opcode that was fetched, if any.
+    interrupt: Option<Interrupt>,
+    status_before: Flags,
+    stage: Stage,
+    // The operand's address, or as much of it as has been read so far. Jumps build their
+    // target up here.
+    address: u16,
+    // An indexed address without the carry into its high byte, which gets read first when
+    // there is one.
+    uncarried_address: u16,
+    // A zero page pointer on its way to becoming the address.
+    pointer: u8,
+    // A byte kept between cycles, like the operand while it's modified.
+    value: u8,
+    result: Option<InstructionResult>,
+}
+
+impl InstructionInProgress {
+    // Adds an index to a base address. A read which stays on the same page can go straight to
+    // the operand, so that's returned as whether the address is ready.
+    fn index(&mut self, base_address: u16, index: u8) -> bool {
+        self.address = base_address.wrapping_add(index as u16);
+        self.uncarried_address = (base_address & 0xFF00) | (self.address & 0x00FF);
+
+        matches!(self.operation, Operation::Read(_)) && self.uncarried_address == self.address
+    }
+}
+
+impl Mos6502 {
+    // Advances the CPU by a single bus cycle, starting the next instruction if the last one has
+    // finished. Returns the instruction's result on its final cycle. Each cycle makes the read
+    // or write the 6502 makes on it, and the registers change when they do on the chip, so the
+    // program counter moves on as each byte of the instruction is fetched.
+    // While WAI or STP has the CPU halted, each cycle is over on its own, without an access.
+    pub fn tick(&mut self) -> Option<InstructionResult> {
+        if self.in_progress.is_none() && self.is_halted() {
+            return Some(self.halted_cycle());
+        }
+
+        let in_progress = match self.in_progress.take() {
+            Some(mut in_progress) => {
+                self.run_cycle(&mut in_progress);
+                in_progress
+            }
+            None => self.fetch_opcode(),
+        };
+
+        self.cycles += 1;
+        self.tick_bus(1);
+
+        let result = match in_progress.result {
+            Some(result) => {
+                let [polled, polled_early] = self.interrupts.recent_lines;
+                self.poll_interrupts(
+                    in_progress.opcode,
+                    in_progress.status_before,
+                    polled,
+                    polled_early,
+                );
+
+                Some(result)
+            }
+            None => {
+                self.in_progress = Some(in_progress);
+                None
+            }
+        };
+
+        self.interrupts.recent_lines = [self.interrupt_lines(), self.interrupts.recent_lines[0]];
+
+        result
+    }
+
+    // Whether the CPU is still halted by WAI or STP, waking it from WAI if an interrupt line has
+    // been asserted.
+    fn is_halted(&mut self) -> bool {
+        if self.halt == Some(Halt::Interrupt) && self.wakes_from_wait() {
+            self.halt = None;
+        }
+
+        self.halt.is_some()
+    }
+
+    // A cycle spent halted, which only lets the rest of the system catch up. STP's cycles keep
+    // on ending the program.
+    fn halted_cycle(&mut self) -> InstructionResult {
+        self.cycles += 1;
+        self.tick_bus(1);
+        self.interrupts.recent_lines = [self.interrupt_lines(), self.interrupts.recent_lines[0]];
+
+        match self.halt {
+            Some(Halt::Reset) => InstructionResult::EndProgram,
+            _ => InstructionResult::Ok,
+        }
+    }
+
+    // Whether `tick` is part way through an instruction.
+    pub fn is_mid_instruction(&self) -> bool {
+        self.in_progress.is_some()
+    }
+
+    // The first cycle of every instruction. A pending interrupt is run instead, the way the
+    // 6502 does it: the opcode it fetches is thrown away in favour of BRK's, and the program
+    // counter stays put.
+    fn fetch_opcode(&mut self) -> InstructionInProgress {
+        self.interrupts.instruction_start = self.cycles;
+
+        let fetched = self.bus.read(self.program_counter);
+        let interrupt = self.interrupts.pending.take();
+
+        let opcode_byte = match interrupt {
+            Some(_) => BRK_OPCODE,
+            None => {
+                self.program_counter = self.program_counter.wrapping_add(1);
+                fetched
+            }
+        };
+
+        let Some(opcode) = self.variant.opcodes().get(&opcode_byte) else {
+            panic!("Opcode not implemented: 0x{:x}.", opcode_byte);
+        };
+
+        let operation = Operation::of(opcode, self.variant);
+
+        let mut in_progress = InstructionInProgress {
+            opcode,
+            operation,
+            interrupt,
+            status_before: self.status,
+            stage: Stage::Addressing(0),
+            address: 0,
+            uncarried_address: 0,
+            pointer: 0,
+            value: 0,
+            result: None,
+        };
+
+        if let Operation::OpcodeOnly = operation {
+            in_progress.result = Some(InstructionResult::Ok);
+        } else if !operation.has_operand() {
+            in_progress.stage = Stage::Operating(0);
+        } else if opcode.address_mode == AddressMode::Immediate {
+            // The operand's the next byte, so there's nothing to find.
+            in_progress.address = self.program_counter;
+            self.program_counter = self.program_counter.wrapping_add(1);
+            in_progress.stage = Stage::Operating(0);
+        }
+
+        in_progress
+    }
+
+    fn run_cycle(&mut self, in_progress: &mut InstructionInProgress) {
+        match in_progress.stage {
+            Stage::Addressing(cycle) => {
+                in_progress.stage = if self.addressing_cycle(in_progress, cycle) {
+                    Stage::Operating(0)
+                } else {
+                    Stage::Addressing(cycle + 1)
+                };
+            }
+            Stage::Operating(cycle) => {
+                in_progress.stage = Stage::Operating(cycle + 1);
+                in_progress.result = self.operating_cycle(in_progress, cycle);
+            }
+        }
+    }
+
+    // Reads the next byte of the instruction.
+    fn fetch_operand(&mut self) -> u8 {
+        let value = self.bus.read(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(1);
+        value
+    }
+
+    // A cycle of the address mode finding the operand. Returns whether it has.
+    fn addressing_cycle(&mut self, in_progress: &mut InstructionInProgress, cycle: u8) -> bool {
+        let (x, y) = (self.registers.x, self.registers.y);
+
+        match (&in_progress.opcode.address_mode, cycle) {
+            (AddressMode::ZeroPage | AddressMode::ZeroPageRelative, 0) => {
+                in_progress.address = self.fetch_operand() as u16;
+                true
+            }
+            (
+                AddressMode::ZeroPageX
+                | AddressMode::ZeroPageY
+                | AddressMode::IndirectX
+                | AddressMode::IndirectY
+                | AddressMode::ZeroPageIndirect,
+                0,
+            ) => {
+                in_progress.pointer = self.fetch_operand();
+                false
+            }
+            // Zero page indexing never leaves the zero page, but the 6502 reads the unindexed
+            // address while it adds the index.
+            (AddressMode::ZeroPageX, 1) => {
+                self.bus.read(in_progress.pointer as u16);
+                in_progress.address = in_progress.pointer.wrapping_add(x) as u16;
+                true
+            }
+            (AddressMode::ZeroPageY, 1) => {
+                self.bus.read(in_progress.pointer as u16);
+                in_progress.address = in_progress.pointer.wrapping_add(y) as u16;
+                true
+            }
+            (AddressMode::Absolute | AddressMode::AbsoluteX | AddressMode::AbsoluteY, 0) => {
+                in_progress.address = self.fetch_operand() as u16;
+                false
+            }
+            (AddressMode::Absolute, 1) => {
+                in_progress.address |= (self.fetch_operand() as u16) << 8;
+                true
+            }
+            (AddressMode::AbsoluteX, 1) => {
+                let base_address = in_progress.address | (self.fetch_operand() as u16) << 8;
+                in_progress.index(base_address, x)
+            }
+            (AddressMode::AbsoluteY, 1) => {
+                let base_address = in_progress.address | (self.fetch_operand() as u16) << 8;
+                in_progress.index(base_address, y)
+            }
+            // The pointer is read before X gets added to it.
+            (AddressMode::IndirectX, 1) => {
+                self.bus.read(in_progress.pointer as u16);
+                in_progress.pointer = in_progress.pointer.wrapping_add(x);
+                false
+            }
+            (AddressMode::IndirectX, 2)
+            | (AddressMode::IndirectY | AddressMode::ZeroPageIndirect, 1) => {
+                in_progress.address = self.bus.read(in_progress.pointer as u16) as u16;
+                false
+            }
+            (AddressMode::IndirectX, 3) | (AddressMode::ZeroPageIndirect, 2) => {
+                let hi = self.bus.read(in_progress.pointer.wrapping_add(1) as u16);
+                in_progress.address |= (hi as u16) << 8;
+                true
+            }
+            (AddressMode::IndirectY, 2) => {
+                let hi = self.bus.read(in_progress.pointer.wrapping_add(1) as u16);
+                in_progress.index(in_progress.address | (hi as u16) << 8, y)
+            }
+            // Indexing across a page costs a cycle, spent reading from the address before the
+            // carry was added. Stores and read-modify-writes always spend it.
+            (AddressMode::AbsoluteX | AddressMode::AbsoluteY | AddressMode::IndirectY, _) => {
+                self.bus.read(in_progress.uncarried_address);
+                true
+            }
+            (address_mode, _) => {
+                unreachable!("No cycle {} for {:?}.", cycle, address_mode)
+            }
+        }
+    }
+
+    // A cycle of the instruction's operation. Returns the instruction's result on its last one.
+    fn operating_cycle(
+        &mut self,
+        in_progress: &mut InstructionInProgress,
+        cycle: u8,
+    ) -> Option<InstructionResult> {
+        let done = Some(InstructionResult::Ok);
+        let address = in_progress.address;
+
+        match (in_progress.operation, cycle) {
+            (Operation::Implied, _) => {
+                self.bus.read(self.program_counter);
+                Some((in_progress.opcode.execute)(in_progress.opcode, self))
+            }
+            (Operation::OpcodeOnly, _) => {
+                unreachable!("One cycle NOPs are done once they're fetched.")
+            }
+
+            (Operation::Read(operate), 0) => {
+                let value = self.bus.read(address);
+                operate(self, value);
+
+                let fix_up = matches!(in_progress.opcode.mnemonic, "ADC" | "SBC")
+                    && Arithmetic::needs_decimal_fix_up(self, self.variant.decimal_mode());
+                if fix_up { None } else { done }
+            }
+            (Operation::Read(_), _) => {
+                self.bus.read(address);
+                done
+            }
+
+            (Operation::LongNop, LONG_NOP_EXTRA_CYCLES) => {
+                self.bus.read(address);
+                done
+            }
+            (Operation::LongNop, _) => {
+                self.bus.read(address);
+                None
+            }
+
+            (Operation::Write(value), _) => {
+                let value = value(self);
+                self.bus.write(address, value);
+                done
+            }
+
+            (Operation::Modify(_), 0) => {
+                in_progress.value = self.bus.read(address);
+                None
+            }
+            // The value read is written straight back while the new one is worked out.
+            (Operation::Modify(operate), 1) => {
+                self.bus.write(address, in_progress.value);
+                in_progress.value = operate(self, in_progress.value);
+                None
+            }
+            (Operation::Modify(_), _) => {
+                self.bus.write(address, in_progress.value);
+                done
+            }
+
+            (Operation::Branch, 0) => {
+                in_progress.value = self.fetch_operand();
+
+                if Branch::is_taken(in_progress.opcode.mnemonic, self.status) {
+                    None
+                } else {
+                    done
+                }
+            }
+            // A taken branch reads the next opcode while it adds the offset.
+            (Operation::Branch, 1) => {
+                self.bus.read(self.program_counter);
+
+                let offset = in_progress.value as i8;
+                in_progress.address = self.program_counter.wrapping_add(offset as i16 as u16);
+                in_progress.uncarried_address =
+                    (self.program_counter & 0xFF00) | (in_progress.address & 0x00FF);
+
+                if in_progress.uncarried_address != in_progress.address {
+                    return None;
+                }
+
+                // Which also means it doesn't get to poll for interrupts on its last cycle.
+                self.interrupts.skip_last_poll = true;
+                self.program_counter = in_progress.address;
+                done
+            }
+            // Then again from the wrong page if the carry into the high byte has to be fixed up.
+            (Operation::Branch, _) => {
+                self.bus.read(in_progress.uncarried_address);
+                self.program_counter = address;
+                done
+            }
+
+            (Operation::BranchOnBit, 0) => {
+                in_progress.value = self.bus.read(address);
+                None
+            }
+            // The byte is read again while its bit is tested.
+            (Operation::BranchOnBit, 1) => {
+                self.bus.read(address);
+                None
+            }
+            (Operation::BranchOnBit, _) => {
+                let is_taken = Branch::is_bit_taken(in_progress.opcode, in_progress.value);
+                in_progress.value = self.fetch_operand();
+
+                if !is_taken {
+                    return done;
+                }
+
+                // From here on it's the same as any other taken branch.
+                in_progress.operation = Operation::Branch;
+                in_progress.stage = Stage::Operating(1);
+                None
+            }
+
+            (Operation::Halt, 0) => {
+                self.bus.read(self.program_counter);
+                None
+            }
+            (Operation::Halt, _) => Some((in_progress.opcode.execute)(in_progress.opcode, self)),
+
+            (
+                Operation::Push(_)
+                | Operation::Pull(_)
+                | Operation::ReturnFromSubroutine
+                | Operation::ReturnFromInterrupt,
+                0,
+            ) => {
+                self.bus.read(self.program_counter);
+                None
+            }
+            (Operation::Push(value), _) => {
+                Stack::push(self, value(self));
+                done
+            }
+            (
+                Operation::Pull(_)
+                | Operation::ReturnFromSubroutine
+                | Operation::ReturnFromInterrupt,
+                1,
+            ) => {
+                Stack::read_before_pop(self);
+                None
+            }
+            (Operation::Pull(operate), _) => {
+                let value = Stack::pop(self);
+                operate(self, value);
+                done
+            }
+
+            (Operation::Jump | Operation::JumpIndirect(_), 0) => {
+                in_progress.address = self.fetch_operand() as u16;
+                None
+            }
+            (Operation::Jump, _) => {
+                self.program_counter = address | (self.fetch_operand() as u16) << 8;
+                done
+            }
+            (Operation::JumpIndirect(_), 1) => {
+                in_progress.address |= (self.fetch_operand() as u16) << 8;
+                None
+            }
+            (Operation::JumpIndirect(JumpType::IndirectFixed), 2) => {
+                Jump::fix_up_pointer(self);
+                None
+            }
+            (Operation::JumpIndirect(JumpType::IndexedIndirect), 2) => {
+                Jump::fix_up_pointer(self);
+                in_progress.address = address.wrapping_add(self.registers.x as u16);
+                None
+            }
+            (Operation::JumpIndirect(JumpType::IndirectFixed | JumpType::IndexedIndirect), 3)
+            | (Operation::JumpIndirect(_), 2) => {
+                in_progress.value = self.bus.read(address);
+                None
+            }
+            (Operation::JumpIndirect(jump_type), _) => {
+                let hi = self
+                    .bus
+                    .read(Jump::pointer_high_address(address, jump_type));
+                self.program_counter = u16::from(in_progress.value) | (u16::from(hi) << 8);
+                done
+            }
+
+            // The 6502 only has room to hold the low byte of the target while it pushes the
+            // return address, so it reads the high byte last.
+            (Operation::JumpToSubroutine, 0) => {
+                in_progress.value = self.fetch_operand();
+                None
+            }
+            (Operation::JumpToSubroutine, 1) => {
+                Stack::read_before_pop(self);
+                None
+            }
+            (Operation::JumpToSubroutine, 2) => {
+                Stack::push(self, (self.program_counter >> 8) as u8);
+                None
+            }
+            (Operation::JumpToSubroutine, 3) => {
+                Stack::push(self, self.program_counter as u8);
+                None
+            }
+            (Operation::JumpToSubroutine, _) => {
+                let hi = self.bus.read(self.program_counter);
+                self.program_counter = u16::from(in_progress.value) | (u16::from(hi) << 8);
+                done
+            }
+
+            (Operation::ReturnFromSubroutine, 2) => {
+                in_progress.value = Stack::pop(self);
+                None
+            }
+            (Operation::ReturnFromSubroutine, 3) => {
+                let hi = Stack::pop(self);
+                in_progress.address = u16::from(in_progress.value) | (u16::from(hi) << 8);
+                None
+            }
+            // The pulled address is read once more while it's incremented past the JSR.
+            (Operation::ReturnFromSubroutine, _) => {
+                self.bus.read(address);
+                self.program_counter = address.wrapping_add(1);
+                done
+            }
+
+            (Operation::ReturnFromInterrupt, 2) => {
+                let status = Stack::pop(self);
+                System::restore_status(self, status);
+                None
+            }
+            (Operation::ReturnFromInterrupt, 3) => {
+                in_progress.value = Stack::pop(self);
+                None
+            }
+            (Operation::ReturnFromInterrupt, _) => {
+                let hi = Stack::pop(self);
+                self.program_counter = u16::from(in_progress.value) | (u16::from(hi) << 8);
+                done
+            }
+
+            // BRK skips the byte after it, an interrupt leaves the program counter where it was.
+            (Operation::Break, 0) => {
+                self.bus.read(self.program_counter);
+
+                if in_progress.interrupt.is_none() {
+                    self.program_counter = self.program_counter.wrapping_add(1);
+                }
+                None
+            }
+            (Operation::Break, 1) => {
+                Stack::push(self, (self.program_counter >> 8) as u8);
+                None
+            }
+            (Operation::Break, 2) => {
+                Stack::push(self, self.program_counter as u8);
+                None
+            }
+            (Operation::Break, 3) => {
+                Stack::push(
+                    self,
+                    System::interrupted_status(self, in_progress.interrupt),
+                );
+                System::start_handler(self);
+                None
+            }
+            (Operation::Break, 4) => {
+                in_progress.address = System::interrupt_vector(self, in_progress.interrupt);
+                in_progress.value = self.bus.read(in_progress.address);
+                None
+            }
+            (Operation::Break, _) => {
+                let hi = self.bus.read(address + 1);
+                self.program_counter = u16::from(in_progress.value) | (u16::from(hi) << 8);
+                Some(System::break_result(self, in_progress.interrupt))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sif::parameterized;
+
+    use super::*;
+    use crate::cpus::mos_6502::{
+        cpu::Registers,
+        instruction_set::helpers::{
+            BusAccess::{self, Read, Write},
+            RecordingBus,
+        },
+    };
+
+    fn tick_instruction(cpu: &mut Mos6502) -> (u8, InstructionResult) {
+        let mut ticks = 1;
+
+        loop {
+            if let Some(result) = cpu.tick() {
+                return (ticks, result);
+            }
+
+            ticks += 1;
+        }
+    }
+
+    #[test]
+    fn tick_makes_one_access_per_cycle() {
+        // STA $1200,X
+        let (mut cpu, accesses) = RecordingBus::create_cpu(
+            0x0600,
+            vec![(0x0600, 0x9D), (0x0601, 0x00), (0x0602, 0x12)],
+            Registers {
+                a: 0x42,
+                x: 0x10,
+                y: 0,
+            },
+        );
+
+        let expected = [
+            Read(0x0600),
+            Read(0x0601),
+            Read(0x0602),
+            Read(0x1210),
+            Write(0x1210, 0x42),
+        ];
+
+        // The program counter moves on as each byte of the instruction is fetched.
+        let program_counters = [0x0601, 0x0602, 0x0603, 0x0603, 0x0603];
+
+        for cycle in 0..expected.len() {
+            let result = cpu.tick();
+
+            assert_eq!(expected[..=cycle], accesses.borrow()[..]);
+            assert_eq!(program_counters[cycle], cpu.program_counter);
+
+            if cycle < expected.len() - 1 {
+                assert_eq!(None, result);
+                assert!(cpu.is_mid_instruction());
+            } else {
+                assert_eq!(Some(InstructionResult::Ok), result);
+                assert!(!cpu.is_mid_instruction());
+            }
+        }
+
+        assert_eq!(5, cpu.cycles);
+    }
+
+    #[test]
+    fn tick_reads_side_effecting_addresses_once() {
+        // INC $10
+        let (mut cpu, accesses) = RecordingBus::create_cpu(
+            0x0600,
+            vec![(0x0600, 0xE6), (0x0601, 0x10), (0x0010, 0x7F)],
+            Registers::default(),
+        );
+
+        assert_eq!((5, InstructionResult::Ok), tick_instruction(&mut cpu));
+
+        assert_eq!(
+            vec![
+                Read(0x0600),
+                Read(0x0601),
+                Read(0x0010),
+                Write(0x0010, 0x7F),
+                Write(0x0010, 0x80),
+            ],
+            *accesses.borrow()
+        );
+        assert_eq!(0x80, cpu.bus.read(0x0010));
+        assert!(cpu.status.contains(Flags::NEGATIVE));
+    }
+
+    #[test]
+    fn tick_logs_a_stack_wrap_once() {
+        // PHA
+        let (mut cpu, _) =
+            RecordingBus::create_cpu(0x0600, vec![(0x0600, 0x48)], Registers::default());
+        cpu.stack_pointer = 0x00;
+        cpu.log_stack_wraps(true);
+
+        tick_instruction(&mut cpu);
+
+        assert_eq!(1, cpu.stack_wraps().len());
+        assert_eq!(0xFF, cpu.stack_pointer);
+    }
+
+    #[parameterized]
+    // BNE not taken
+    #[case(vec![(0x0600, 0xD0), (0x0601, 0x10)], Flags::ZERO, 2, 0x0602)]
+    // BNE taken
+    #[case(vec![(0x0600, 0xD0), (0x0601, 0x10)], Flags::empty(), 3, 0x0612)]
+    // BNE taken across a page
+    #[case(vec![(0x06F0, 0xD0), (0x06F1, 0x10)], Flags::empty(), 4, 0x0702)]
+    fn tick_counts_branch_cycles(
+        memory_values: Vec<(u16, u8)>,
+        status: Flags,
+        expected_cycles: u8,
+        expected_pc: u16,
+    ) {
+        let (mut cpu, _) =
+            RecordingBus::create_cpu(memory_values[0].0, memory_values, Registers::default());
+        cpu.status = status;
+
+        assert_eq!(
+            (expected_cycles, InstructionResult::Ok),
+            tick_instruction(&mut cpu)
+        );
+        assert_eq!(expected_pc, cpu.program_counter);
+    }
+
+    #[parameterized]
+    // No page crossed, so straight to the operand.
+    #[case(0x05, vec![Read(0x0600), Read(0x0601), Read(0x0602), Read(0x12F5)])]
+    #[case(0x10, vec![Read(0x0600), Read(0x0601), Read(0x0602), Read(0x1200), Read(0x1300)])]
+    fn tick_takes_an_extra_cycle_reading_across_a_page(
+        index: u8,
+        expected_accesses: Vec<BusAccess>,
+    ) {
+        // LDA $12F0,X
+        let (mut cpu, accesses) = RecordingBus::create_cpu(
+            0x0600,
+            vec![
+                (0x0600, 0xBD),
+                (0x0601, 0xF0),
+                (0x0602, 0x12),
+                (0x1300, 0x42),
+            ],
+            Registers {
+                a: 0,
+                x: index,
+                y: 0,
+            },
+        );
+
+        let (ticks, _) = tick_instruction(&mut cpu);
+
+        assert_eq!(expected_accesses.len() as u8, ticks);
+        assert_eq!(expected_accesses, *accesses.borrow());
+    }
+
+    // Runs the instruction at the program counter with its execute fn from the opcode table,
+    // which makes all of its accesses in one go.
+    fn execute(cpu: &mut Mos6502) -> InstructionResult {
+        let opcode = &cpu.variant.opcodes()[&cpu.bus.read(cpu.program_counter)];
+        cpu.program_counter += 1;
+
+        // Instructions without an operand still read the byte after the opcode, and ignore it.
+        if matches!(
+            opcode.address_mode,
+            AddressMode::Implied | AddressMode::Accumulator
+        ) {
+            cpu.bus.read(cpu.program_counter);
+        }
+
+        (opcode.execute)(opcode, cpu)
+    }
+
+    #[parameterized]
+    #[case(Variant::Ricoh2A03)]
+    #[case(Variant::Nmos6502)]
+    #[case(Variant::Wdc65C02)]
+    fn tick_matches_the_opcode_table_for_every_opcode(variant: Variant) {
+        for opcode in variant.opcodes().values() {
+            let memory_values = vec![
+                (0x0600, opcode.opcode),
+                (0x0601, 0x40),
+                (0x0602, 0x02),
+                (0x0040, 0x00),
+                (0x0041, 0x03),
+                (0x0242, 0x5A),
+            ];
+            let create_cpu = || {
+                let (cpu, accesses) = RecordingBus::create_cpu(
+                    0x0600,
+                    memory_values.clone(),
+                    Registers {
+                        a: 0x81,
+                        x: 0x01,
+                        y: 0x02,
+                    },
+                );
+                let mut cpu = cpu.with_variant(variant);
+                cpu.stack_pointer = 0xF0;
+                (cpu, accesses)
+            };
+
+            let (mut executed, executed_accesses) = create_cpu();
+            let execute_result = execute(&mut executed);
+
+            let (mut ticked, ticked_accesses) = create_cpu();
+            let (ticks, tick_result) = tick_instruction(&mut ticked);
+
+            let name = format!("{} (0x{:02X})", opcode.mnemonic, opcode.opcode);
+            assert_eq!(execute_result, tick_result, "{name}");
+            assert_eq!(
+                *executed_accesses.borrow(),
+                *ticked_accesses.borrow(),
+                "{name}"
+            );
+            assert_eq!(executed.registers, ticked.registers, "{name}");
+            assert_eq!(executed.status, ticked.status, "{name}");
+            assert_eq!(executed.program_counter, ticked.program_counter, "{name}");
+            assert_eq!(executed.stack_pointer, ticked.stack_pointer, "{name}");
+
+            // Each cycle is one access.
+            assert_eq!(ticks as usize, ticked_accesses.borrow().len(), "{name}");
+
+            // Nothing here crosses a page, so apart from taken branches, that's the number of
+            // cycles in the table.
+            let branch_taken = matches!(
+                opcode.address_mode,
+                AddressMode::Relative | AddressMode::ZeroPageRelative
+            ) && ticked.program_counter != 0x0601 + opcode.bytes as u16;
+            if !branch_taken {
+                assert_eq!(opcode.cycles, ticks, "{name}");
+            }
+        }
+    }
+}
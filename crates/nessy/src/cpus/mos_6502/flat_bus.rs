@@ -0,0 +1,175 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    cpus::mos_6502::bus::MemoryBus,
+    roms::{ROM, mappers::Mapper},
+};
+
+// The whole 16-bit address space, as one block of RAM.
+pub const FLAT_MEMORY_SIZE: usize = 0x10000;
+
+// Where an inserted ROM's program is copied to, as if it were an NROM cartridge.
+const PROGRAM_ROM_START: usize = 0x8000;
+
+// A bus with nothing on it but 64KB of RAM, for running plain 6502 programs which don't
+// expect any NES hardware. Parts of it can be made read-only, and one address can be set
+// aside as a port which collects whatever characters are written to it.
+pub struct FlatBus {
+    memory: Box<[u8; FLAT_MEMORY_SIZE]>,
+    rom_ranges: Vec<RangeInclusive<u16>>,
+    output_port: Option<u16>,
+    output: Vec<u8>,
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        Self {
+            memory: Box::new([0; FLAT_MEMORY_SIZE]),
+            rom_ranges: vec![],
+            output_port: None,
+            output: vec![],
+        }
+    }
+}
+
+impl FlatBus {
+    // Ignores writes made by the program to `range`. Loading into it still works.
+    pub fn with_rom_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.rom_ranges.push(range);
+        self
+    }
+
+    // Collects bytes written to `address` as output, rather than storing them.
+    pub fn with_output_port(mut self, address: u16) -> Self {
+        self.output_port = Some(address);
+        self
+    }
+
+    fn is_rom(&self, address: u16) -> bool {
+        self.rom_ranges.iter().any(|range| range.contains(&address))
+    }
+}
+
+impl MemoryBus for FlatBus {
+    fn read(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        if self.output_port == Some(address) {
+            self.output.push(data);
+        } else if !self.is_rom(address) {
+            self.memory[address as usize] = data;
+        }
+    }
+
+    // This is how programs get loaded, so it writes straight to memory, ROM or not. Anything
+    // past the top of memory wraps around to the bottom.
+    fn write_slice(&mut self, start_address: u16, data: &[u8]) {
+        for (offset, &byte) in data.iter().enumerate() {
+            self.memory[start_address.wrapping_add(offset as u16) as usize] = byte;
+        }
+    }
+
+    fn read_u16(&self, address: u16) -> u16 {
+        let lo_byte = self.read(address) as u16;
+        let hi_byte = self.read(address.wrapping_add(1)) as u16;
+        (hi_byte << 8) | lo_byte
+    }
+
+    fn write_u16(&mut self, address: u16, data: u16) {
+        self.write(address, (data & 0xFF) as u8);
+        self.write(address.wrapping_add(1), (data >> 8) as u8);
+    }
+
+    // Copies the program in at $8000, mirrored up to $C000 when it's only 16KB.
+    fn insert_rom(&mut self, rom: ROM) {
+        let program = rom.program_rom();
+
+        for offset in (0..FLAT_MEMORY_SIZE - PROGRAM_ROM_START).step_by(program.len().max(1)) {
+            let start = PROGRAM_ROM_START + offset;
+            let length = program.len().min(FLAT_MEMORY_SIZE - start);
+            self.memory[start..start + length].copy_from_slice(&program[..length]);
+        }
+    }
+
+    fn insert_cartridge(&mut self, _cartridge: Box<dyn Mapper>) {
+        panic!("The flat bus has no cartridge slot.");
+    }
+
+    fn character_output(&self) -> &[u8] {
+        &self.output
+    }
+
+    // Plain programs finish with a BRK.
+    fn ends_on_brk(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+
+    use super::*;
+
+    #[test]
+    fn test_read_and_write_cover_all_memory() {
+        let mut bus = FlatBus::default();
+
+        bus.write(0x0000, 0x11);
+        bus.write(0x2000, 0x22);
+        bus.write(0xFFFF, 0x33);
+
+        assert_eq_hex!(0x11, bus.read(0x0000));
+        assert_eq_hex!(0x00, bus.read(0x0800));
+        assert_eq_hex!(0x22, bus.read(0x2000));
+        assert_eq_hex!(0x33, bus.read(0xFFFF));
+    }
+
+    #[test]
+    fn test_u16_wraps_at_top_of_memory() {
+        let mut bus = FlatBus::default();
+
+        bus.write_u16(0xFFFF, 0xBEEF);
+
+        assert_eq_hex!(0xEF, bus.read(0xFFFF));
+        assert_eq_hex!(0xBE, bus.read(0x0000));
+        assert_eq_hex!(0xBEEF, bus.read_u16(0xFFFF));
+    }
+
+    #[test]
+    fn test_write_ignores_rom_ranges() {
+        let mut bus = FlatBus::default().with_rom_range(0xE000..=0xFFFF);
+
+        bus.write_slice(0xFFFC, &[0x00, 0x04]);
+        bus.write(0xFFFC, 0x11);
+        bus.write(0xDFFF, 0x22);
+
+        assert_eq_hex!(0x0400, bus.read_u16(0xFFFC));
+        assert_eq_hex!(0x22, bus.read(0xDFFF));
+    }
+
+    #[test]
+    fn test_write_collects_output_port() {
+        let mut bus = FlatBus::default().with_output_port(0xF001);
+
+        for &character in b"Hi!" {
+            bus.write(0xF001, character);
+        }
+
+        assert_eq!(b"Hi!", bus.character_output());
+        assert_eq_hex!(0x00, bus.read(0xF001));
+    }
+
+    #[test]
+    fn test_write_slice_wraps_at_top_of_memory() {
+        let mut bus = FlatBus::default();
+
+        bus.write_slice(0xFFFE, &[0x01, 0x02, 0x03]);
+
+        assert_eq_hex!(0x01, bus.read(0xFFFE));
+        assert_eq_hex!(0x02, bus.read(0xFFFF));
+        assert_eq_hex!(0x03, bus.read(0x0000));
+    }
+}
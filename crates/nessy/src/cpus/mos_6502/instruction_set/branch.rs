@@ -1,20 +1,70 @@
 use crate::{
-    cpus::mos_6502::{cpu::Mos6502, status::Flags},
+    cpus::mos_6502::{cpu::Mos6502, opcode::OpCode, status::Flags},
     interpret_result::InstructionResult,
 };
 
 pub struct Branch {}
 
 impl Branch {
-    fn branch_rule<F>(cpu: &mut Mos6502, predicate: F) -> InstructionResult
-    where
-        F: Fn(&mut Mos6502) -> bool,
-    {
+    // Whether the branch with this mnemonic is taken with the flags as they are.
+    pub fn is_taken(mnemonic: &str, status: Flags) -> bool {
+        match mnemonic {
+            "BCC" => !status.contains(Flags::CARRY),
+            "BCS" => status.contains(Flags::CARRY),
+            "BEQ" => status.contains(Flags::ZERO),
+            "BNE" => !status.contains(Flags::ZERO),
+            "BMI" => status.contains(Flags::NEGATIVE),
+            "BPL" => !status.contains(Flags::NEGATIVE),
+            "BVC" => !status.contains(Flags::OVERFLOW),
+            "BVS" => status.contains(Flags::OVERFLOW),
+            "BRA" => true,
+            _ => panic!("Not a branch: {}.", mnemonic),
+        }
+    }
+
+    // Whether BBR or BBS is taken, given the zero page byte it tested.
+    pub fn is_bit_taken(opcode: &OpCode, value: u8) -> bool {
+        let is_set = value & (1 << opcode.bit_index()) != 0;
+
+        opcode.mnemonic.starts_with("BBS") == is_set
+    }
+
+    fn branch_rule(cpu: &mut Mos6502, mnemonic: &str) -> InstructionResult {
+        let is_taken = Branch::is_taken(mnemonic, cpu.status);
+        Branch::branch_if(cpu, is_taken)
+    }
+
+    // BBR and BBS read the zero page byte they test twice, before the offset.
+    fn branch_on_bit_rule(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        let address = cpu.bus.read(cpu.program_counter) as u16;
+        cpu.program_counter += 1;
+
+        let value = cpu.bus.read(address);
+        cpu.bus.read(address);
+
+        Branch::branch_if(cpu, Branch::is_bit_taken(opcode, value))
+    }
+
+    fn branch_if(cpu: &mut Mos6502, is_taken: bool) -> InstructionResult {
         let offset = cpu.bus.read(cpu.program_counter) as i8;
         cpu.program_counter += 1;
 
-        if predicate(cpu) {
-            cpu.program_counter = cpu.program_counter.wrapping_add(offset as i16 as u16);
+        if is_taken {
+            // A taken branch reads the next opcode while it adds the offset, and again from the
+            // wrong page if the carry into the high byte still has to be fixed up.
+            cpu.bus.read(cpu.program_counter);
+
+            let target = cpu.program_counter.wrapping_add(offset as i16 as u16);
+            let uncarried_target = (cpu.program_counter & 0xFF00) | (target & 0x00FF);
+
+            if uncarried_target != target {
+                cpu.bus.read(uncarried_target);
+            } else {
+                // Which also means it doesn't get to poll for interrupts on its last cycle.
+                cpu.interrupts.skip_last_poll = true;
+            }
+
+            cpu.program_counter = target;
         }
 
         InstructionResult::Ok
@@ -22,42 +72,57 @@ impl Branch {
 
     // BCC - Branch if Carry Clear
     pub fn bcc(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| !cpu.status.contains(Flags::CARRY))
+        Branch::branch_rule(cpu, "BCC")
     }
 
     // BCS - Branch if Carry Set
     pub fn bcs(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| cpu.status.contains(Flags::CARRY))
+        Branch::branch_rule(cpu, "BCS")
     }
 
     // BEQ - Branch if Equal
     pub fn beq(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| cpu.status.contains(Flags::ZERO))
+        Branch::branch_rule(cpu, "BEQ")
     }
 
     // BNE - Branch if Not Equal
     pub fn bne(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| !cpu.status.contains(Flags::ZERO))
+        Branch::branch_rule(cpu, "BNE")
     }
 
     // BMI - Branch if Minus
     pub fn bmi(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| cpu.status.contains(Flags::NEGATIVE))
+        Branch::branch_rule(cpu, "BMI")
     }
 
     // BPL - Branch if Positive
     pub fn bpl(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| !cpu.status.contains(Flags::NEGATIVE))
+        Branch::branch_rule(cpu, "BPL")
     }
 
     // BVC - Branch if Overflow Clear
     pub fn bvc(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| !cpu.status.contains(Flags::OVERFLOW))
+        Branch::branch_rule(cpu, "BVC")
     }
 
     // BVS - Branch if Overflow Set
     pub fn bvs(cpu: &mut Mos6502) -> InstructionResult {
-        Branch::branch_rule(cpu, |cpu| cpu.status.contains(Flags::OVERFLOW))
+        Branch::branch_rule(cpu, "BVS")
+    }
+
+    // BRA - Branch Always [65C02]
+    pub fn bra(cpu: &mut Mos6502) -> InstructionResult {
+        Branch::branch_rule(cpu, "BRA")
+    }
+
+    // BBR0-7 - Branch on Bit Reset [65C02]
+    pub fn bbr(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Branch::branch_on_bit_rule(opcode, cpu)
+    }
+
+    // BBS0-7 - Branch on Bit Set [65C02]
+    pub fn bbs(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Branch::branch_on_bit_rule(opcode, cpu)
     }
 }
 
@@ -65,9 +130,11 @@ impl Branch {
 mod test {
     use assert_hex::assert_eq_hex;
 
+    use sif::parameterized;
+
     use super::*;
 
-    use crate::cpus::mos_6502::instruction_set::helpers::Helpers;
+    use crate::cpus::mos_6502::{instruction_set::helpers::Helpers, opcode::WDC_65C02_OPCODES};
 
     macro_rules! assert_branch_operation {
         ($flags:expr, $expected_pc:expr, $func:expr) => {
@@ -164,4 +231,26 @@ mod test {
     fn test_bvs_doesnt_branches_if_overflow_not_set() {
         assert_branch_operation!(Flags::empty(), 0xAB, Branch::bvs);
     }
+
+    #[parameterized]
+    #[case(0x0F, 0xFE, 0xAF)]
+    #[case(0x0F, 0x01, 0xAC)]
+    #[case(0x7F, 0x7F, 0xAF)]
+    #[case(0x8F, 0x01, 0xAF)]
+    #[case(0x8F, 0xFE, 0xAC)]
+    #[case(0xFF, 0x80, 0xAF)]
+    fn test_bbr_and_bbs_branch_on_zero_page_bit(opcode: u8, memory_value: u8, expected_pc: u16) {
+        let mut cpu = Helpers::create_cpu(
+            0xAA,
+            0x0,
+            Some(vec![(0xAA, 0x10), (0xAB, 0x03), (0x10, memory_value)]),
+            None,
+            None,
+        );
+
+        let opcode = &WDC_65C02_OPCODES[&opcode];
+        (opcode.execute)(opcode, &mut cpu);
+
+        assert_eq_hex!(expected_pc, cpu.program_counter);
+    }
 }
@@ -25,6 +25,18 @@ impl Compare {
             .set_status_flag(Flags::NEGATIVE, result & MSB_MASK != 0);
     }
 
+    pub fn compare_accumulator(cpu: &mut Mos6502, value: u8) {
+        Compare::compare_set_flags(cpu, cpu.registers.a, value);
+    }
+
+    pub fn compare_x(cpu: &mut Mos6502, value: u8) {
+        Compare::compare_set_flags(cpu, cpu.registers.x, value);
+    }
+
+    pub fn compare_y(cpu: &mut Mos6502, value: u8) {
+        Compare::compare_set_flags(cpu, cpu.registers.y, value);
+    }
+
     // CMP - Compare
     pub fn cmp(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
         let address = cpu.get_address(&opcode.address_mode);
@@ -32,7 +44,7 @@ impl Compare {
 
         cpu.program_counter += opcode.bytes as u16;
 
-        Compare::compare_set_flags(cpu, cpu.registers.a, memory_value);
+        Compare::compare_accumulator(cpu, memory_value);
 
         InstructionResult::Ok
     }
@@ -54,7 +66,7 @@ impl Compare {
 
         cpu.program_counter += opcode.bytes as u16;
 
-        Compare::compare_set_flags(cpu, cpu.registers.x, memory_value);
+        Compare::compare_x(cpu, memory_value);
 
         InstructionResult::Ok
     }
@@ -76,7 +88,7 @@ impl Compare {
 
         cpu.program_counter += opcode.bytes as u16;
 
-        Compare::compare_set_flags(cpu, cpu.registers.y, memory_value);
+        Compare::compare_y(cpu, memory_value);
 
         InstructionResult::Ok
     }
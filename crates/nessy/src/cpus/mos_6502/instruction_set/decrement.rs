@@ -12,7 +12,7 @@ use crate::{
 pub struct Decrement {}
 
 impl Decrement {
-    fn decrement(cpu: &mut Mos6502, operand: u8) -> u8 {
+    pub fn decrement(cpu: &mut Mos6502, operand: u8) -> u8 {
         let result = operand.wrapping_sub(1);
 
         cpu.status.set_status_flag(Flags::ZERO, result == 0);
@@ -36,14 +36,10 @@ impl Decrement {
             return InstructionResult::IllegalInstruction;
         }
 
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let memory_value = cpu.bus.read(address);
-
-        let decrement_result = Decrement::decrement(cpu, memory_value);
-
-        cpu.bus.write(address, decrement_result);
+        cpu.read_modify_write(address, Decrement::decrement);
 
         InstructionResult::Ok
     }
@@ -74,17 +70,26 @@ impl Decrement {
         InstructionResult::Ok
     }
 
+    // DEC A - Decrement Accumulator [65C02]
+    pub fn dec_accumulator(cpu: &mut Mos6502) -> InstructionResult {
+        cpu.registers.a = Decrement::decrement(cpu, cpu.registers.a);
+
+        InstructionResult::Ok
+    }
+
+    // DCP's read-modify-write operation. Returns the decremented value.
+    pub fn decrement_then_compare(cpu: &mut Mos6502, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        Compare::compare_accumulator(cpu, result);
+        result
+    }
+
     // DCP - Subtract 1 from memory (without borrow) then compare it against A. [undocumented]
     pub fn dcp(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let memory_value = cpu.bus.read(address);
-        let result = memory_value.wrapping_sub(1);
-
-        cpu.bus.write(address, result);
-
-        Compare::compare_set_flags(cpu, cpu.registers.a, result);
+        cpu.read_modify_write(address, Decrement::decrement_then_compare);
 
         InstructionResult::Ok
     }
@@ -168,6 +173,27 @@ mod test {
         assert_eq!(Flags::NEGATIVE, cpu.status);
     }
 
+    #[test]
+    fn test_dec_accumulator_decrements_a_value_and_sets_zero_flag() {
+        let mut cpu = Mos6502 {
+            registers: Registers {
+                a: 0x01,
+                x: 0,
+                y: 0,
+            },
+            status: Flags::empty(),
+            ..Default::default()
+        };
+
+        let result = Decrement::dec_accumulator(&mut cpu);
+
+        assert_eq!(InstructionResult::Ok, result);
+
+        assert_eq_hex!(0x00, cpu.registers.a);
+
+        assert_eq!(Flags::ZERO, cpu.status);
+    }
+
     #[parameterized]
     #[case(0x07, 0x04, 0x03, Flags::CARRY)]
     #[case(0x00, 0x00, 0xFF, Flags::empty())]
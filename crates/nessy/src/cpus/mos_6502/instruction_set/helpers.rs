@@ -1,9 +1,16 @@
 #[cfg(test)]
-use crate::cpus::mos_6502::{
-    address_mode::AddressMode,
-    cpu::{Mos6502, Registers},
-    opcode::OpCode,
-    status::Flags,
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(test)]
+use crate::{
+    cpus::mos_6502::{
+        address_mode::AddressMode,
+        bus::MemoryBus,
+        cpu::{Mos6502, Registers},
+        opcode::OpCode,
+        status::Flags,
+    },
+    roms::{ROM, mappers::Mapper},
 };
 
 #[macro_export]
@@ -65,3 +72,81 @@ impl Helpers {
         cpu
     }
 }
+
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+pub enum BusAccess {
+    Read(u16),
+    Write(u16, u8),
+}
+
+// 64KB of RAM which logs every access, for checking the exact reads and writes an
+// instruction makes.
+#[cfg(test)]
+pub struct RecordingBus {
+    memory: Vec<u8>,
+    pub accesses: Rc<RefCell<Vec<BusAccess>>>,
+}
+
+#[cfg(test)]
+impl RecordingBus {
+    // Returns the CPU, along with the log its bus writes to.
+    pub fn create_cpu(
+        program_counter: u16,
+        memory_values: Vec<(u16, u8)>,
+        registers: Registers,
+    ) -> (Mos6502, Rc<RefCell<Vec<BusAccess>>>) {
+        let mut bus = RecordingBus {
+            memory: vec![0; 0x10000],
+            accesses: Rc::default(),
+        };
+
+        for (address, value) in memory_values {
+            bus.memory[address as usize] = value;
+        }
+
+        let accesses = bus.accesses.clone();
+
+        let mut cpu = Mos6502::new(Box::new(bus));
+        cpu.program_counter = program_counter;
+        cpu.registers = registers;
+
+        (cpu, accesses)
+    }
+}
+
+#[cfg(test)]
+impl MemoryBus for RecordingBus {
+    fn read(&self, address: u16) -> u8 {
+        self.accesses.borrow_mut().push(BusAccess::Read(address));
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.accesses
+            .borrow_mut()
+            .push(BusAccess::Write(address, data));
+        self.memory[address as usize] = data;
+    }
+
+    fn write_slice(&mut self, start_address: u16, data: &[u8]) {
+        for (offset, &byte) in data.iter().enumerate() {
+            self.write(start_address.wrapping_add(offset as u16), byte);
+        }
+    }
+
+    fn read_u16(&self, address: u16) -> u16 {
+        let lo_byte = self.read(address) as u16;
+        let hi_byte = self.read(address.wrapping_add(1)) as u16;
+        (hi_byte << 8) | lo_byte
+    }
+
+    fn write_u16(&mut self, address: u16, data: u16) {
+        self.write(address, (data & 0xFF) as u8);
+        self.write(address.wrapping_add(1), (data >> 8) as u8);
+    }
+
+    fn insert_rom(&mut self, _rom: ROM) {}
+
+    fn insert_cartridge(&mut self, _cartridge: Box<dyn Mapper>) {}
+}
@@ -12,7 +12,7 @@ use crate::{
 pub struct Increment {}
 
 impl Increment {
-    fn increment(cpu: &mut Mos6502, operand: u8) -> u8 {
+    pub fn increment(cpu: &mut Mos6502, operand: u8) -> u8 {
         let result = operand.wrapping_add(1);
 
         cpu.status.set_status_flag(Flags::ZERO, result == 0);
@@ -35,13 +35,10 @@ impl Increment {
             return InstructionResult::IllegalInstruction;
         }
 
-        let address = cpu.get_address(&opcode.address_mode);
-        let memory_value = cpu.bus.read(address);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let result = Increment::increment(cpu, memory_value);
-
-        cpu.bus.write(address, result);
+        cpu.read_modify_write(address, Increment::increment);
 
         InstructionResult::Ok
     }
@@ -59,6 +56,13 @@ impl Increment {
 
         InstructionResult::Ok
     }
+
+    // INC A - Increment Accumulator [65C02]
+    pub fn inc_accumulator(cpu: &mut Mos6502) -> InstructionResult {
+        cpu.registers.a = Increment::increment(cpu, cpu.registers.a);
+
+        InstructionResult::Ok
+    }
 }
 
 #[cfg(test)]
@@ -118,4 +122,18 @@ mod test {
         assert_eq_hex!(expected_y_reg, cpu.registers.y);
         assert_eq!(expected_flags, cpu.status);
     }
+
+    #[parameterized]
+    #[case(0xFF, 0, Flags::ZERO)]
+    #[case(0x7F, 0x80, Flags::NEGATIVE)]
+    fn test_inc_accumulator(a_reg: u8, expected_a_reg: u8, expected_flags: Flags) {
+        let mut cpu = Helpers::create_cpu(0x0, 0x0, None, None, None);
+
+        cpu.registers.a = a_reg;
+
+        Increment::inc_accumulator(&mut cpu);
+
+        assert_eq_hex!(expected_a_reg, cpu.registers.a);
+        assert_eq!(expected_flags, cpu.status);
+    }
 }
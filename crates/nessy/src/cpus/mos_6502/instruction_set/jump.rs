@@ -8,22 +8,42 @@ use crate::{
     interpret_result::InstructionResult,
 };
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JumpType {
     Absolute,
     Indirect,
+    // The 65C02 doesn't wrap the pointer within its page.
+    IndirectFixed,
+    // The 65C02's JMP (abs,X), which adds X to the pointer.
+    IndexedIndirect,
 }
 
 // The following instructions modify the program counter causing a break to normal sequential execution.
 pub struct Jump {}
 
 impl Jump {
+    // Where an indirect jump reads the high byte of its target from. The NMOS 6502 never
+    // carries into the pointer's high byte, so a pointer at the end of a page wraps around it.
+    pub fn pointer_high_address(pointer: u16, jump_type: JumpType) -> u16 {
+        match jump_type {
+            JumpType::IndirectFixed | JumpType::IndexedIndirect => pointer.wrapping_add(1),
+            _ => (pointer & 0xFF00) | (pointer.wrapping_add(1) & 0x00FF),
+        }
+    }
+
+    // The 65C02 spends the cycle it took to fix that, or to add X, reading the pointer's high
+    // byte again.
+    pub fn fix_up_pointer(cpu: &mut Mos6502) {
+        cpu.bus.read(cpu.program_counter.wrapping_sub(1));
+    }
+
     // JMP - Jump
     pub fn jmp(cpu: &mut Mos6502, jump_type: JumpType) -> InstructionResult {
         match jump_type {
             JumpType::Absolute => {
                 cpu.program_counter = cpu.get_address(&AddressMode::Absolute);
             }
-            JumpType::Indirect => {
+            JumpType::Indirect | JumpType::IndirectFixed | JumpType::IndexedIndirect => {
                 // First address
                 let ptr_lo = cpu.bus.read(cpu.program_counter);
                 cpu.program_counter = cpu.program_counter.wrapping_add(1);
@@ -31,14 +51,21 @@ impl Jump {
                 cpu.program_counter = cpu.program_counter.wrapping_add(1);
 
                 // Form 16-bit ptr address
-                let ptr = u16::from(ptr_lo) | (u16::from(ptr_hi) << 8);
+                let mut ptr = u16::from(ptr_lo) | (u16::from(ptr_hi) << 8);
+
+                if jump_type != JumpType::Indirect {
+                    Jump::fix_up_pointer(cpu);
+                }
+
+                if jump_type == JumpType::IndexedIndirect {
+                    ptr = ptr.wrapping_add(cpu.registers.x as u16);
+                }
 
                 // Get the indirect jump's lo byte of the address
                 let jump_target_lo = cpu.bus.read(ptr);
 
                 // Build up the high byte of the jump target
-                let hi_address = (ptr & 0xFF00) | ((ptr.wrapping_add(1)) & 0x00FF);
-                let jump_target_hi = cpu.bus.read(hi_address);
+                let jump_target_hi = cpu.bus.read(Jump::pointer_high_address(ptr, jump_type));
 
                 cpu.program_counter = u16::from(jump_target_lo) | (u16::from(jump_target_hi) << 8);
             }
@@ -55,7 +82,9 @@ impl Jump {
             return InstructionResult::IllegalInstruction;
         }
 
-        let subroutine_address = cpu.get_address(&opcode.address_mode);
+        // The 6502 only has room to hold the low byte of the target while it pushes the return
+        // address, so it reads the high byte last.
+        let target_lo = cpu.bus.read(cpu.program_counter);
 
         let return_address = cpu.program_counter + opcode.bytes as u16 - 1;
 
@@ -63,33 +92,30 @@ impl Jump {
         let hi_byte = (return_address >> 8) as u8;
         let lo_byte = (return_address & 0x00FF) as u8;
 
-        if let Err(instruction_result) = Stack::push(cpu, hi_byte) {
-            return instruction_result;
-        }
+        Stack::read_before_pop(cpu);
 
-        if let Err(instruction_result) = Stack::push(cpu, lo_byte) {
-            return instruction_result;
-        }
+        Stack::push(cpu, hi_byte);
+        Stack::push(cpu, lo_byte);
 
-        cpu.program_counter = subroutine_address;
+        let target_hi = cpu.bus.read(return_address);
+
+        cpu.program_counter = u16::from(target_lo) | (u16::from(target_hi) << 8);
 
         InstructionResult::Ok
     }
 
     // RTS - Return from Subroutine
     pub fn rts(cpu: &mut Mos6502) -> InstructionResult {
-        let lo = match Stack::pop(cpu) {
-            Ok(v) => v,
-            Err(err) => return err,
-        };
+        Stack::read_before_pop(cpu);
 
-        let hi = match Stack::pop(cpu) {
-            Ok(v) => v,
-            Err(err) => return err,
-        };
+        let lo = Stack::pop(cpu);
+        let hi = Stack::pop(cpu);
 
         let address = u16::from(lo) | (u16::from(hi) << 8);
-        cpu.program_counter = address + 1;
+
+        // The pulled address is read once more while it's incremented past the JSR.
+        cpu.bus.read(address);
+        cpu.program_counter = address.wrapping_add(1);
 
         InstructionResult::Ok
     }
@@ -102,7 +128,7 @@ mod test {
 
     use super::*;
     use crate::cpus::mos_6502::{
-        cpu::Mos6502,
+        cpu::{Mos6502, Registers},
         instruction_set::{helpers::Helpers, stack::STACK_BOTTOM},
     };
 
@@ -140,6 +166,29 @@ mod test {
         assert_eq_hex!(0x1234, cpu.program_counter);
     }
 
+    #[test]
+    fn test_jmp_with_indexed_indirect_adds_x_to_the_pointer() {
+        let mut cpu = Helpers::create_cpu(
+            0x0A,
+            0x0,
+            Some(vec![
+                (0x0A, 0xFE),
+                (0x0B, 0x01),
+                (0x01FF, 0x34),
+                (0x0200, 0x12),
+            ]),
+            Some(Registers { a: 0, x: 1, y: 0 }),
+            None,
+        );
+
+        assert_eq!(
+            InstructionResult::Ok,
+            Jump::jmp(&mut cpu, JumpType::IndexedIndirect)
+        );
+
+        assert_eq_hex!(0x1234, cpu.program_counter);
+    }
+
     #[test]
     fn test_jsr_works() {
         let mut cpu = Helpers::create_cpu(
@@ -193,15 +242,18 @@ mod test {
     }
 
     #[test]
-    fn test_rts_returns_stack_underflow_given_empty_stack() {
-        let mut cpu = Mos6502 {
-            stack_pointer: 0xFF,
-            program_counter: 0xAA,
-            ..Default::default()
-        };
+    fn test_rts_wraps_given_empty_stack() {
+        let mut cpu = Helpers::create_cpu(
+            0xAA,
+            0xFF,
+            Some(vec![(0x0100, 0x34), (0x0101, 0x12)]),
+            None,
+            None,
+        );
 
-        assert_eq!(InstructionResult::StackUnderflow, Jump::rts(&mut cpu));
+        assert_eq!(InstructionResult::Ok, Jump::rts(&mut cpu));
 
-        assert_eq_hex!(0xAA, cpu.program_counter);
+        assert_eq_hex!(0x1235, cpu.program_counter);
+        assert_eq_hex!(0x01, cpu.stack_pointer);
     }
 }
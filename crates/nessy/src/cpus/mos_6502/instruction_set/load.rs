@@ -11,54 +11,61 @@ impl Load {
         cpu.status.set_negative_flag(value);
     }
 
-    // LDA - Load Accumulator
-    pub fn lda(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+    // Where each of the loads (and pulls) leave the value they've read.
+    pub fn load_accumulator(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.a = value;
+        Load::set_flags(cpu, value);
+    }
 
-        cpu.registers.a = cpu.bus.read(address);
-        Load::set_flags(cpu, cpu.registers.a);
+    pub fn load_x(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.x = value;
+        Load::set_flags(cpu, value);
+    }
 
-        cpu.program_counter += opcode.bytes as u16;
+    pub fn load_y(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.y = value;
+        Load::set_flags(cpu, value);
+    }
 
-        InstructionResult::Ok
+    pub fn load_accumulator_and_x(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.a = value;
+        cpu.registers.x = value;
+        Load::set_flags(cpu, value);
     }
 
-    // LDX - Load X Register
-    pub fn ldx(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+    fn load(
+        opcode: &OpCode,
+        cpu: &mut Mos6502,
+        operation: fn(&mut Mos6502, u8),
+    ) -> InstructionResult {
         let address = cpu.get_address(&opcode.address_mode);
 
-        cpu.registers.x = cpu.bus.read(address);
-        Load::set_flags(cpu, cpu.registers.x);
+        let value = cpu.bus.read(address);
+        operation(cpu, value);
 
         cpu.program_counter += opcode.bytes as u16;
 
         InstructionResult::Ok
     }
 
-    // LDY - Load Y Register
-    pub fn ldy(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-
-        cpu.registers.y = cpu.bus.read(address);
-        Load::set_flags(cpu, cpu.registers.y);
+    // LDA - Load Accumulator
+    pub fn lda(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Load::load(opcode, cpu, Load::load_accumulator)
+    }
 
-        cpu.program_counter += opcode.bytes as u16;
+    // LDX - Load X Register
+    pub fn ldx(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Load::load(opcode, cpu, Load::load_x)
+    }
 
-        InstructionResult::Ok
+    // LDY - Load Y Register
+    pub fn ldy(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Load::load(opcode, cpu, Load::load_y)
     }
 
     // LAX - Load accumulator and X register with memory. [undocumented]
     pub fn lax(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-
-        let value = cpu.bus.read(address);
-        cpu.registers.a = value;
-        cpu.registers.x = value;
-
-        Load::set_flags(cpu, value);
-        cpu.program_counter += opcode.bytes as u16;
-
-        InstructionResult::Ok
+        Load::load(opcode, cpu, Load::load_accumulator_and_x)
     }
 }
 
@@ -2,7 +2,7 @@ use crate::{
     cpus::mos_6502::{
         address_mode::{AddressMode, MemoryAddressing},
         cpu::Mos6502,
-        instruction_set::{helpers::MSB_MASK, rotate::Rotate, shift::Shift},
+        instruction_set::{rotate::Rotate, shift::Shift},
         opcode::OpCode,
         status::Flags,
     },
@@ -14,126 +14,195 @@ use crate::{
 pub struct Logical {}
 
 impl Logical {
+    fn set_accumulator_flags(cpu: &mut Mos6502) {
+        cpu.status.set_zero_flag(cpu.registers.a);
+        cpu.status.set_negative_flag(cpu.registers.a);
+    }
+
     fn accumulator_rule(
         opcode: &OpCode,
         cpu: &mut Mos6502,
-        operation: fn(cpu: &mut Mos6502, address: u16),
+        operation: fn(cpu: &mut Mos6502, value: u8),
     ) -> InstructionResult {
         let address = cpu.get_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        operation(cpu, address);
-
-        cpu.status.set_zero_flag(cpu.registers.a);
-        cpu.status.set_negative_flag(cpu.registers.a);
+        let value = cpu.bus.read(address);
+        operation(cpu, value);
 
         InstructionResult::Ok
     }
 
-    // AND - Logical AND
-    pub fn and(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        Logical::accumulator_rule(opcode, cpu, |cpu, address| {
-            cpu.registers.a &= cpu.bus.read(address);
-        })
+    pub fn and_accumulator(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.a &= value;
+        Logical::set_accumulator_flags(cpu);
     }
 
-    // EOR - Exclusive OR
-    pub fn eor(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        Logical::accumulator_rule(opcode, cpu, |cpu, address| {
-            cpu.registers.a ^= cpu.bus.read(address);
-        })
+    pub fn eor_accumulator(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.a ^= value;
+        Logical::set_accumulator_flags(cpu);
     }
 
-    // ORA - Logical Inclusive OR
-    pub fn ora(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        Logical::accumulator_rule(opcode, cpu, |cpu, address| {
-            cpu.registers.a |= cpu.bus.read(address);
-        })
+    pub fn ora_accumulator(cpu: &mut Mos6502, value: u8) {
+        cpu.registers.a |= value;
+        Logical::set_accumulator_flags(cpu);
     }
 
-    // BIT - Bit Test
-    pub fn bit(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        if ![AddressMode::ZeroPage, AddressMode::Absolute].contains(&opcode.address_mode) {
-            return InstructionResult::IllegalInstruction;
-        }
-
-        let address = cpu.get_address(&opcode.address_mode);
-        cpu.program_counter += opcode.bytes as u16;
-
-        let memory_value = cpu.bus.read(address);
-
-        let acca_and_mem = cpu.registers.a & memory_value;
-
-        cpu.status.set_zero_flag(acca_and_mem);
-        cpu.status.set_negative_flag(memory_value);
+    // BIT's flags: Z from the accumulator ANDed with memory, N and V straight from memory.
+    pub fn test_bits(cpu: &mut Mos6502, value: u8) {
+        cpu.status.set_zero_flag(cpu.registers.a & value);
+        cpu.status.set_negative_flag(value);
         cpu.status
-            .set_status_flag(Flags::OVERFLOW, memory_value & 0b0100_0000 != 0);
+            .set_status_flag(Flags::OVERFLOW, value & 0b0100_0000 != 0);
+    }
 
-        InstructionResult::Ok
+    // BIT # on the 65C02. There's no memory to take N and V from, so only Z is set.
+    pub fn test_bits_immediate(cpu: &mut Mos6502, value: u8) {
+        cpu.status.set_zero_flag(cpu.registers.a & value);
     }
 
-    // SRE - Shift right one bit in memory, then EOR accumulator with memory. [undocumented]
-    pub fn sre(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        cpu.program_counter += opcode.bytes as u16;
+    // TRB's read-modify-write operation.
+    pub fn reset_bits(cpu: &mut Mos6502, value: u8) -> u8 {
+        cpu.status.set_zero_flag(cpu.registers.a & value);
+        value & !cpu.registers.a
+    }
 
-        let memory_value = cpu.bus.read(address);
+    // TSB's read-modify-write operation.
+    pub fn set_bits(cpu: &mut Mos6502, value: u8) -> u8 {
+        cpu.status.set_zero_flag(cpu.registers.a & value);
+        value | cpu.registers.a
+    }
 
-        let result = Shift::logical_shift(cpu, memory_value);
+    // RMB and SMB's read-modify-write operations, for each bit.
+    pub const RESET_BIT: [fn(&mut Mos6502, u8) -> u8; 8] = [
+        Logical::reset_bit::<0>,
+        Logical::reset_bit::<1>,
+        Logical::reset_bit::<2>,
+        Logical::reset_bit::<3>,
+        Logical::reset_bit::<4>,
+        Logical::reset_bit::<5>,
+        Logical::reset_bit::<6>,
+        Logical::reset_bit::<7>,
+    ];
+    pub const SET_BIT: [fn(&mut Mos6502, u8) -> u8; 8] = [
+        Logical::set_bit::<0>,
+        Logical::set_bit::<1>,
+        Logical::set_bit::<2>,
+        Logical::set_bit::<3>,
+        Logical::set_bit::<4>,
+        Logical::set_bit::<5>,
+        Logical::set_bit::<6>,
+        Logical::set_bit::<7>,
+    ];
+
+    fn reset_bit<const BIT: u8>(_: &mut Mos6502, value: u8) -> u8 {
+        value & !(1 << BIT)
+    }
 
-        cpu.bus.write(address, result);
+    fn set_bit<const BIT: u8>(_: &mut Mos6502, value: u8) -> u8 {
+        value | 1 << BIT
+    }
 
-        // Then perform the EOR operation on the result & Acca
-        cpu.registers.a ^= result;
+    // SRE's read-modify-write operation. The carry comes from the shift.
+    pub fn shift_right_then_eor(cpu: &mut Mos6502, value: u8) -> u8 {
+        let result = Shift::logical_shift(cpu, value);
+        Logical::eor_accumulator(cpu, result);
+        result
+    }
 
-        // CARRY inherited from the logical_shift call
-        cpu.status.set_zero_flag(cpu.registers.a);
-        cpu.status.set_negative_flag(cpu.registers.a);
+    // SLO's read-modify-write operation. The carry comes from the shift.
+    pub fn shift_left_then_ora(cpu: &mut Mos6502, value: u8) -> u8 {
+        let result = Shift::arithmetic_shift(cpu, value);
+        Logical::ora_accumulator(cpu, result);
+        result
+    }
 
-        InstructionResult::Ok
+    // RLA's read-modify-write operation.
+    pub fn rotate_left_then_and(cpu: &mut Mos6502, value: u8) -> u8 {
+        let result = Rotate::rotate_left(cpu, value);
+        Logical::and_accumulator(cpu, result);
+        result
     }
 
-    pub fn slo(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        cpu.program_counter += opcode.bytes as u16;
+    // AND - Logical AND
+    pub fn and(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::accumulator_rule(opcode, cpu, Logical::and_accumulator)
+    }
 
-        // Shift left and write value back to memory
-        let memory_value = cpu.bus.read(address);
+    // EOR - Exclusive OR
+    pub fn eor(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::accumulator_rule(opcode, cpu, Logical::eor_accumulator)
+    }
 
-        let result = Shift::arithmetic_shift(cpu, memory_value);
+    // ORA - Logical Inclusive OR
+    pub fn ora(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::accumulator_rule(opcode, cpu, Logical::ora_accumulator)
+    }
 
-        cpu.bus.write(address, result);
+    // BIT - Bit Test
+    // The 65C02 adds the immediate and indexed modes.
+    pub fn bit(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        match opcode.address_mode {
+            AddressMode::Immediate => {
+                Logical::accumulator_rule(opcode, cpu, Logical::test_bits_immediate)
+            }
+            AddressMode::ZeroPage
+            | AddressMode::ZeroPageX
+            | AddressMode::Absolute
+            | AddressMode::AbsoluteX => Logical::accumulator_rule(opcode, cpu, Logical::test_bits),
+            _ => InstructionResult::IllegalInstruction,
+        }
+    }
 
-        // Then perform the EOR operation on the result & Acca
-        cpu.registers.a |= result;
+    fn read_modify_write_rule(
+        opcode: &OpCode,
+        cpu: &mut Mos6502,
+        operation: fn(cpu: &mut Mos6502, value: u8) -> u8,
+    ) -> InstructionResult {
+        let address = cpu.get_write_address(&opcode.address_mode);
+        cpu.program_counter += opcode.bytes as u16;
 
-        cpu.status
-            .set_status_flag(Flags::CARRY, memory_value & MSB_MASK != 0);
-        cpu.status.set_zero_flag(cpu.registers.a);
-        cpu.status.set_negative_flag(cpu.registers.a);
+        cpu.read_modify_write(address, operation);
 
         InstructionResult::Ok
     }
 
-    // RLA - Rotate one bit left in memory, then AND accumulator with memory. [undocumented]
-    pub fn rla(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        cpu.program_counter += opcode.bytes as u16;
+    // TRB - Test and Reset Bits [65C02]
+    // Clears the accumulator's bits in memory. Z is set the same way as BIT.
+    pub fn trb(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::reset_bits)
+    }
 
-        // Shift left and write value back to memory
-        let memory_value = cpu.bus.read(address);
+    // TSB - Test and Set Bits [65C02]
+    // Sets the accumulator's bits in memory. Z is set the same way as BIT.
+    pub fn tsb(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::set_bits)
+    }
 
-        let result = Rotate::rotate_left(cpu, memory_value);
+    // RMB0-7 - Reset Memory Bit [65C02]
+    // Clears the opcode's bit in a zero page byte, leaving the flags alone.
+    pub fn rmb(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::RESET_BIT[opcode.bit_index()])
+    }
 
-        cpu.bus.write(address, result);
+    // SMB0-7 - Set Memory Bit [65C02]
+    pub fn smb(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::SET_BIT[opcode.bit_index()])
+    }
 
-        // Then perform the EOR operation on the result & Acca
-        cpu.registers.a &= result;
+    // SRE - Shift right one bit in memory, then EOR accumulator with memory. [undocumented]
+    pub fn sre(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::shift_right_then_eor)
+    }
 
-        cpu.status.set_zero_flag(cpu.registers.a);
-        cpu.status.set_negative_flag(cpu.registers.a);
+    // SLO - Shift left one bit in memory, then OR accumulator with memory. [undocumented]
+    pub fn slo(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::shift_left_then_ora)
+    }
 
-        InstructionResult::Ok
+    // RLA - Rotate one bit left in memory, then AND accumulator with memory. [undocumented]
+    pub fn rla(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        Logical::read_modify_write_rule(opcode, cpu, Logical::rotate_left_then_and)
     }
 }
 
@@ -144,9 +213,34 @@ mod test {
 
     use super::*;
     use crate::cpus::mos_6502::{
-        address_mode::AddressMode, cpu::Registers, instruction_set::helpers::Helpers, status::Flags,
+        address_mode::AddressMode, cpu::Registers, instruction_set::helpers::Helpers,
+        opcode::WDC_65C02_OPCODES, status::Flags,
     };
 
+    #[parameterized]
+    #[case(0x07, 0xFF, 0xFE)]
+    #[case(0x57, 0xFF, 0xDF)]
+    #[case(0x77, 0x80, 0x00)]
+    #[case(0x87, 0x00, 0x01)]
+    #[case(0xD7, 0x01, 0x21)]
+    #[case(0xF7, 0x80, 0x80)]
+    fn test_rmb_and_smb_change_one_bit(opcode: u8, memory_value: u8, expected: u8) {
+        let mut cpu = Helpers::create_cpu(
+            0xAA,
+            0x0,
+            Some(vec![(0xAA, 0x02), (0x02, memory_value)]),
+            None,
+            None,
+        );
+
+        let opcode = &WDC_65C02_OPCODES[&opcode];
+
+        assert_eq!(InstructionResult::Ok, (opcode.execute)(opcode, &mut cpu));
+
+        assert_eq_hex!(expected, cpu.bus.read(0x02));
+        assert_eq!(Flags::empty(), cpu.status);
+    }
+
     #[parameterized]
     #[case(0x02, 0x03, 0x06, 0x02, Flags::empty())]
     #[case(0x00, 0x80, 0x00, 0x00, Flags::CARRY | Flags::ZERO)]
@@ -331,4 +425,25 @@ mod test {
         assert_eq_hex!(0x00, cpu.registers.a);
         assert_eq!(Flags::ZERO, cpu.status);
     }
+
+    #[test]
+    fn test_bit_immediate_only_sets_zero_flag() {
+        let mut cpu = Helpers::create_cpu(
+            0xAA,
+            0x0,
+            Some(vec![(0xAA, 0xC0)]),
+            Some(Registers {
+                a: 0x3F,
+                x: 0,
+                y: 0,
+            }),
+            None,
+        );
+
+        let opcode = Helpers::create_opcode(1, AddressMode::Immediate);
+
+        Logical::bit(&opcode, &mut cpu);
+
+        assert_eq!(Flags::ZERO, cpu.status);
+    }
 }
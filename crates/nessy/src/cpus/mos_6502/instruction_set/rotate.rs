@@ -60,15 +60,13 @@ impl Rotate {
         cpu: &mut Mos6502,
         direction: Direction,
     ) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        let memory_value = cpu.bus.read(address);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let result = match direction {
-            Direction::Left => Rotate::rotate_left(cpu, memory_value),
-            Direction::Right => Rotate::rotate_right(cpu, memory_value),
-        };
-        cpu.bus.write(address, result);
+        cpu.read_modify_write(address, |cpu, value| match direction {
+            Direction::Left => Rotate::rotate_left(cpu, value),
+            Direction::Right => Rotate::rotate_right(cpu, value),
+        });
 
         InstructionResult::Ok
     }
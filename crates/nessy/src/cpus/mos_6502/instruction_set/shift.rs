@@ -40,13 +40,10 @@ impl Shift {
     }
 
     pub fn asl_memory(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        let address_value = cpu.bus.read(address);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let shifted_result = Shift::arithmetic_shift(cpu, address_value);
-
-        cpu.bus.write(address, shifted_result);
+        cpu.read_modify_write(address, Shift::arithmetic_shift);
 
         InstructionResult::Ok
     }
@@ -59,13 +56,10 @@ impl Shift {
     }
 
     pub fn lsr_memory(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
-        let address_value = cpu.bus.read(address);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
-        let shifted_result = Shift::logical_shift(cpu, address_value);
-
-        cpu.bus.write(address, shifted_result);
+        cpu.read_modify_write(address, Shift::logical_shift);
 
         InstructionResult::Ok
     }
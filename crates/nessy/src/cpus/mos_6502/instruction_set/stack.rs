@@ -1,86 +1,147 @@
 use crate::{
-    cpus::mos_6502::{cpu::Mos6502, status::Flags},
+    cpus::mos_6502::{cpu::Mos6502, instruction_set::load::Load, status::Flags},
     interpret_result::InstructionResult,
 };
 
 // SP points to the top (effectively 0xFF, which pages as 0x0100 + SP = 0x01FF) and grows down.
-// Stack underflow occurs if the SP attempts to POP beyond this value.
+// Pulling with SP at the top wraps around to the bottom.
 // const STACK_TOP: u16 = 0x01FF;
 
-// Stack overflow occurs if the SP attempts to PUSH beyond this value.
+// Pushing with SP at the bottom wraps around to the top.
 pub const STACK_BOTTOM: u16 = 0x0100;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StackWrapKind {
+    Overflow,
+    Underflow,
+}
+
+// A push or pull that went off the end of page $01 and came back round the other side. Real
+// hardware doesn't mind, but it's usually a bug when it happens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StackWrap {
+    pub kind: StackWrapKind,
+    // Where the program had got to, part way through the instruction doing the pushing.
+    pub program_counter: u16,
+    pub cycles: u64,
+}
+
 pub struct Stack {}
 
 // Push / Pull to/from the stack
 impl Stack {
-    pub fn push(cpu: &mut Mos6502, operand: u8) -> Result<(), InstructionResult> {
+    pub fn push(cpu: &mut Mos6502, operand: u8) {
         let stack_address = STACK_BOTTOM + cpu.stack_pointer as u16;
 
         cpu.bus.write(stack_address, operand);
 
         if cpu.stack_pointer == 0 {
-            Err(InstructionResult::StackOverflow)
-        } else {
-            cpu.stack_pointer -= 1;
-            Ok(())
+            Stack::record_wrap(cpu, StackWrapKind::Overflow);
         }
+
+        cpu.stack_pointer = cpu.stack_pointer.wrapping_sub(1);
     }
 
-    pub fn pop(cpu: &mut Mos6502) -> Result<u8, InstructionResult> {
+    pub fn pop(cpu: &mut Mos6502) -> u8 {
         if cpu.stack_pointer == 0xFF {
-            return Err(InstructionResult::StackUnderflow);
+            Stack::record_wrap(cpu, StackWrapKind::Underflow);
         }
 
-        let stack_address = STACK_BOTTOM + (cpu.stack_pointer as u16 + 1);
+        cpu.stack_pointer = cpu.stack_pointer.wrapping_add(1);
+
+        cpu.bus.read(STACK_BOTTOM + cpu.stack_pointer as u16)
+    }
+
+    fn record_wrap(cpu: &mut Mos6502, kind: StackWrapKind) {
+        let wrap = StackWrap {
+            kind,
+            program_counter: cpu.program_counter,
+            cycles: cpu.cycles,
+        };
 
-        let result = cpu.bus.read(stack_address);
+        if let Some(stack_wraps) = cpu.stack_wraps.as_mut() {
+            stack_wraps.push(wrap);
+        }
+    }
 
-        cpu.stack_pointer += 1;
-        Ok(result)
+    // Pulling takes the 6502 an extra cycle, which it spends reading the byte SP points at
+    // before it increments SP.
+    pub fn read_before_pop(cpu: &mut Mos6502) {
+        cpu.bus.read(STACK_BOTTOM + cpu.stack_pointer as u16);
+    }
+
+    // What PHP pushes, which always has BREAK_COMMAND set.
+    pub fn pushed_status(cpu: &Mos6502) -> u8 {
+        (cpu.status | Flags::BREAK_COMMAND).bits()
+    }
+
+    // What PLP does with the status it pulls.
+    pub fn restore_status(cpu: &mut Mos6502, value: u8) {
+        cpu.status = (Flags::from_bits_truncate(value) & !Flags::BREAK_COMMAND) | Flags::UNUSED;
     }
 
     // PHA - Push Accumulator to Stack
     pub fn pha(cpu: &mut Mos6502) -> InstructionResult {
-        match Stack::push(cpu, cpu.registers.a) {
-            Ok(_) => InstructionResult::Ok,
-            Err(result) => result,
-        }
+        Stack::push(cpu, cpu.registers.a);
+        InstructionResult::Ok
     }
 
     // PLA - Pull Accumulator from Stack
     pub fn pla(cpu: &mut Mos6502) -> InstructionResult {
-        match Stack::pop(cpu) {
-            Ok(value) => {
-                cpu.registers.a = value;
+        Stack::read_before_pop(cpu);
 
-                cpu.status.set_zero_flag(cpu.registers.a);
-                cpu.status.set_negative_flag(cpu.registers.a);
+        let value = Stack::pop(cpu);
+        Load::load_accumulator(cpu, value);
 
-                InstructionResult::Ok
-            }
-            Err(result) => result,
-        }
+        InstructionResult::Ok
     }
 
     // PHP - Push Processor Status
     pub fn php(cpu: &mut Mos6502) -> InstructionResult {
-        // Set BREAK_COMMAND before pushing P to the stack.
-        match Stack::push(cpu, (cpu.status | Flags::BREAK_COMMAND).bits()) {
-            Err(err) => err,
-            _ => InstructionResult::Ok,
-        }
+        Stack::push(cpu, Stack::pushed_status(cpu));
+        InstructionResult::Ok
     }
 
     // PLP - Pull Processor Status
     pub fn plp(cpu: &mut Mos6502) -> InstructionResult {
-        match Stack::pop(cpu) {
-            Ok(v) => {
-                cpu.status = (Flags::from_bits_truncate(v) & !Flags::BREAK_COMMAND) | Flags::UNUSED;
-                InstructionResult::Ok
-            }
-            Err(err) => err,
-        }
+        Stack::read_before_pop(cpu);
+
+        let value = Stack::pop(cpu);
+        Stack::restore_status(cpu, value);
+
+        InstructionResult::Ok
+    }
+
+    // PHX - Push X Register [65C02]
+    pub fn phx(cpu: &mut Mos6502) -> InstructionResult {
+        Stack::push(cpu, cpu.registers.x);
+        InstructionResult::Ok
+    }
+
+    // PLX - Pull X Register [65C02]
+    pub fn plx(cpu: &mut Mos6502) -> InstructionResult {
+        Stack::read_before_pop(cpu);
+
+        let value = Stack::pop(cpu);
+        Load::load_x(cpu, value);
+
+        InstructionResult::Ok
+    }
+
+    // PHY - Push Y Register [65C02]
+    pub fn phy(cpu: &mut Mos6502) -> InstructionResult {
+        Stack::push(cpu, cpu.registers.y);
+        InstructionResult::Ok
+    }
+
+    // PLY - Pull Y Register [65C02]
+    pub fn ply(cpu: &mut Mos6502) -> InstructionResult {
+        Stack::read_before_pop(cpu);
+
+        let value = Stack::pop(cpu);
+        Load::load_y(cpu, value);
+
+        InstructionResult::Ok
     }
 }
 
@@ -122,7 +183,7 @@ mod test {
     }
 
     #[test]
-    fn test_pha_returns_overflow_given_full_stack() {
+    fn test_pha_wraps_given_full_stack() {
         let mut cpu = Mos6502 {
             registers: Registers {
                 a: 0xDD,
@@ -133,7 +194,10 @@ mod test {
             ..Default::default()
         };
 
-        assert_eq!(InstructionResult::StackOverflow, Stack::pha(&mut cpu));
+        assert_eq!(InstructionResult::Ok, Stack::pha(&mut cpu));
+
+        assert_eq_hex!(0xDD, cpu.bus.read(STACK_BOTTOM));
+        assert_eq_hex!(0xFF, cpu.stack_pointer);
     }
 
     #[parameterized]
@@ -154,13 +218,41 @@ mod test {
     }
 
     #[test]
-    fn test_pla_returns_underflow_given_empty_stack() {
-        let mut cpu = Mos6502 {
-            stack_pointer: 0xFF,
-            ..Default::default()
-        };
+    fn test_pla_wraps_given_empty_stack() {
+        let mut cpu = Helpers::create_cpu(0x0, 0xFF, Some(vec![(STACK_BOTTOM, 0x42)]), None, None);
 
-        assert_eq!(InstructionResult::StackUnderflow, Stack::pla(&mut cpu));
+        assert_eq!(InstructionResult::Ok, Stack::pla(&mut cpu));
+
+        assert_eq_hex!(0x42, cpu.registers.a);
+        assert_eq_hex!(0x00, cpu.stack_pointer);
+    }
+
+    #[test]
+    fn test_stack_wraps_are_only_logged_when_asked() {
+        let mut cpu = Helpers::create_cpu(0x0600, 0x00, None, None, None);
+
+        Stack::push(&mut cpu, 0x01);
+        assert!(cpu.stack_wraps().is_empty());
+
+        cpu.log_stack_wraps(true);
+        Stack::pop(&mut cpu);
+        Stack::push(&mut cpu, 0x01);
+
+        assert_eq!(
+            [
+                StackWrap {
+                    kind: StackWrapKind::Underflow,
+                    program_counter: 0x0600,
+                    cycles: 0,
+                },
+                StackWrap {
+                    kind: StackWrapKind::Overflow,
+                    program_counter: 0x0600,
+                    cycles: 0,
+                },
+            ],
+            cpu.stack_wraps()
+        );
     }
 
     #[test]
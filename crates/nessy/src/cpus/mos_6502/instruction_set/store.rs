@@ -8,7 +8,7 @@ pub struct Store {}
 impl Store {
     // STA - Store Accumulator
     pub fn sta(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
 
         cpu.bus.write(address, cpu.registers.a);
 
@@ -19,7 +19,7 @@ impl Store {
 
     // STX - Store X Register
     pub fn stx(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
 
         cpu.bus.write(address, cpu.registers.x);
 
@@ -30,7 +30,7 @@ impl Store {
 
     // STY - Store Y Register
     pub fn sty(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
 
         cpu.bus.write(address, cpu.registers.y);
 
@@ -39,9 +39,20 @@ impl Store {
         InstructionResult::Ok
     }
 
+    // STZ - Store Zero [65C02]
+    pub fn stz(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        let address = cpu.get_write_address(&opcode.address_mode);
+
+        cpu.bus.write(address, 0);
+
+        cpu.program_counter += opcode.bytes as u16;
+
+        InstructionResult::Ok
+    }
+
     // SAX - AND X register with accumulator and store result in memory. [undocumented]
     pub fn sax(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
-        let address = cpu.get_address(&opcode.address_mode);
+        let address = cpu.get_write_address(&opcode.address_mode);
         cpu.program_counter += opcode.bytes as u16;
 
         let value = cpu.registers.a & cpu.registers.x;
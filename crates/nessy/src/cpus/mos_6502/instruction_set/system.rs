@@ -1,19 +1,89 @@
 use crate::{
-    cpus::mos_6502::{cpu::Mos6502, instruction_set::stack::Stack, opcode::OpCode, status::Flags},
+    cpus::mos_6502::{
+        address_mode::MemoryAddressing,
+        cpu::{Halt, Mos6502},
+        instruction_set::stack::Stack,
+        interrupt::{IRQ_VECTOR, Interrupt, NMI_VECTOR},
+        opcode::OpCode,
+        status::Flags,
+        variant::Variant,
+    },
     interpret_result::InstructionResult,
 };
 
-const INTERRUPT_VECTOR: u16 = 0xFFFE;
+pub const BRK_OPCODE: u8 = 0x00;
+
+// The 65C02's eight cycle NOP, and the cycles it spends after reading its operand.
+pub const LONG_NOP_OPCODE: u8 = 0x5C;
+pub const LONG_NOP_EXTRA_CYCLES: u8 = 4;
 
 // The remaining instructions perform useful but rarely used functions.
 pub struct System {}
 
 impl System {
     // BRK - Force Interrupt
+    // Also how NMIs and IRQs are run, which push the status without the B flag and leave the
+    // program counter where it was. BRK skips the byte after it. Either way, an NMI can take
+    // the sequence over before it gets to its vector.
+    // On a flat bus, BRK still ends the program when the CPU is `run`, with the handler ready
+    // to go.
     pub fn brk(cpu: &mut Mos6502) -> InstructionResult {
-        cpu.status |= Flags::BREAK_COMMAND;
-        cpu.program_counter = INTERRUPT_VECTOR;
-        InstructionResult::EndProgram
+        let interrupt = cpu.interrupts.pending.take();
+
+        if interrupt.is_none() {
+            cpu.program_counter = cpu.program_counter.wrapping_add(1);
+        }
+
+        for byte in cpu.program_counter.to_be_bytes() {
+            Stack::push(cpu, byte);
+        }
+
+        Stack::push(cpu, System::interrupted_status(cpu, interrupt));
+        System::start_handler(cpu);
+
+        let vector = System::interrupt_vector(cpu, interrupt);
+        let lo = cpu.bus.read(vector);
+        let hi = cpu.bus.read(vector + 1);
+        cpu.program_counter = u16::from(lo) | (u16::from(hi) << 8);
+
+        System::break_result(cpu, interrupt)
+    }
+
+    // The status BRK and the interrupts push. Only BRK sets the B flag.
+    pub fn interrupted_status(cpu: &Mos6502, interrupt: Option<Interrupt>) -> u8 {
+        match interrupt {
+            Some(_) => (cpu.status - Flags::BREAK_COMMAND) | Flags::UNUSED,
+            None => cpu.status | Flags::BREAK_COMMAND | Flags::UNUSED,
+        }
+        .bits()
+    }
+
+    // Once the status is on the stack, IRQs are held off until the handler's done. The 65C02
+    // also leaves decimal mode, so handlers don't have to.
+    pub fn start_handler(cpu: &mut Mos6502) {
+        cpu.status |= Flags::INTERRUPT_DISABLE;
+
+        if cpu.variant == Variant::Wdc65C02 {
+            cpu.status -= Flags::DECIMAL_MODE;
+        }
+    }
+
+    // The vector a BRK or interrupt sequence jumps through, decided as it gets there.
+    pub fn interrupt_vector(cpu: &mut Mos6502, interrupt: Option<Interrupt>) -> u16 {
+        if interrupt == Some(Interrupt::Nmi) || cpu.nmi_hijacks_sequence() {
+            cpu.interrupts.nmi_taken = true;
+            NMI_VECTOR
+        } else {
+            IRQ_VECTOR
+        }
+    }
+
+    pub fn break_result(cpu: &Mos6502, interrupt: Option<Interrupt>) -> InstructionResult {
+        if interrupt.is_none() && cpu.bus.ends_on_brk() {
+            InstructionResult::EndProgram
+        } else {
+            InstructionResult::Ok
+        }
     }
 
     // NOP - No Operation
@@ -21,34 +91,66 @@ impl System {
     // - DOP: Double NOP
     // - TOP: Triple NOP
     pub fn nop(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        // The undocumented ones still read their operand, and throw it away.
+        if opcode.bytes > 0 {
+            cpu.bus.read(cpu.get_address(&opcode.address_mode));
+        }
+
         // burn the opcode's byte, if it's present.
         cpu.program_counter += opcode.bytes as u16;
 
         InstructionResult::Ok
     }
 
+    // The 65C02's NOP $5C reads its absolute operand, then keeps the bus busy for another
+    // four cycles. Where those reads go isn't modelled, so they read the operand again.
+    pub fn long_nop(opcode: &OpCode, cpu: &mut Mos6502) -> InstructionResult {
+        let address = cpu.get_address(&opcode.address_mode);
+        cpu.program_counter += opcode.bytes as u16;
+
+        for _ in 0..=LONG_NOP_EXTRA_CYCLES {
+            cpu.bus.read(address);
+        }
+
+        InstructionResult::Ok
+    }
+
+    // WAI - Wait for Interrupt [65C02]
+    // Both WAI and STP read the byte after them twice, then the CPU sits idle.
+    pub fn wai(cpu: &mut Mos6502) -> InstructionResult {
+        cpu.bus.read(cpu.program_counter);
+        cpu.halt = Some(Halt::Interrupt);
+
+        InstructionResult::Ok
+    }
+
+    // STP - Stop [65C02]
+    // Only a reset gets the CPU going again, so it's treated as the end of the program.
+    pub fn stp(cpu: &mut Mos6502) -> InstructionResult {
+        cpu.bus.read(cpu.program_counter);
+        cpu.halt = Some(Halt::Reset);
+
+        InstructionResult::EndProgram
+    }
+
+    // What RTI does with the status it pulls.
+    pub fn restore_status(cpu: &mut Mos6502, value: u8) {
+        cpu.status = Flags::from_bits_truncate(value) | Flags::UNUSED;
+    }
+
     // RTI - Return from Interrupt
     // The RTI instruction is used at the end of an interrupt processing routine.
     // It pulls the processor flags from the stack followed by the program counter.
     pub fn rti(cpu: &mut Mos6502) -> InstructionResult {
-        let flags = match Stack::pop(cpu) {
-            Ok(v) => v,
-            Err(err) => return err,
-        };
-
-        let lo = match Stack::pop(cpu) {
-            Ok(v) => v,
-            Err(err) => return err,
-        };
+        Stack::read_before_pop(cpu);
 
-        let hi = match Stack::pop(cpu) {
-            Ok(v) => v,
-            Err(err) => return err,
-        };
+        let flags = Stack::pop(cpu);
+        let lo = Stack::pop(cpu);
+        let hi = Stack::pop(cpu);
 
         cpu.program_counter = u16::from(lo) | (u16::from(hi) << 8);
 
-        cpu.status = Flags::from_bits_truncate(flags) | Flags::UNUSED;
+        System::restore_status(cpu, flags);
 
         InstructionResult::Ok
     }
@@ -60,7 +162,9 @@ mod tests {
     use sif::parameterized;
 
     use crate::{
-        cpus::mos_6502::{address_mode::AddressMode, instruction_set::helpers::Helpers},
+        cpus::mos_6502::{
+            address_mode::AddressMode, flat_bus::FlatBus, instruction_set::helpers::Helpers,
+        },
         interpret_result::InstructionResult,
     };
 
@@ -68,11 +172,31 @@ mod tests {
 
     #[test]
     fn test_brk_returns_end_program() {
-        let mut cpu = Helpers::create_cpu(0x0, 0x0, None, None, None);
+        let mut cpu = Mos6502::new(Box::new(FlatBus::default()));
+        cpu.program_counter = 0x0201;
+        cpu.stack_pointer = 0xFF;
+        cpu.status = Flags::UNUSED;
+        cpu.bus.write_u16(IRQ_VECTOR, 0x0400);
 
         assert_eq!(InstructionResult::EndProgram, System::brk(&mut cpu));
-        assert_eq_hex!(INTERRUPT_VECTOR, cpu.program_counter);
-        assert_eq_hex!(Flags::BREAK_COMMAND, cpu.status);
+        assert_eq_hex!(0x0400, cpu.program_counter);
+        assert_eq_hex!(Flags::UNUSED | Flags::INTERRUPT_DISABLE, cpu.status);
+
+        // The return address skips the byte after BRK.
+        assert_eq_hex!(0x02, cpu.bus.read(0x01FF));
+        assert_eq_hex!(0x02, cpu.bus.read(0x01FE));
+        assert_eq_hex!(
+            (Flags::UNUSED | Flags::BREAK_COMMAND).bits(),
+            cpu.bus.read(0x01FD)
+        );
+        assert_eq_hex!(0xFC, cpu.stack_pointer);
+    }
+
+    #[test]
+    fn test_brk_is_just_an_interrupt_on_the_nes() {
+        let cpu = Mos6502::default();
+
+        assert_eq!(InstructionResult::Ok, System::break_result(&cpu, None));
     }
 
     #[parameterized]
@@ -0,0 +1,381 @@
+use crate::cpus::mos_6502::{cpu::Mos6502, opcode::OpCode, status::Flags};
+
+pub const NMI_VECTOR: u16 = 0xFFFA;
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+
+// An NMI which arrives within this many cycles of the start of a BRK or IRQ sequence takes it
+// over, sending it through the NMI vector instead.
+const NMI_HIJACK_CYCLES: u64 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interrupt {
+    Nmi,
+    Irq,
+}
+
+// What the interrupt lines looked like at the end of a cycle.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Lines {
+    nmi: bool,
+    irq: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Interrupts {
+    // NMI is edge triggered, so the cycle an edge arrived on is kept until the CPU gets to it.
+    nmi_latched_at: Option<u64>,
+    irq_line: bool,
+    // What the poll at the end of the last instruction decided to run before the next one.
+    pub(crate) pending: Option<Interrupt>,
+    // Whether the sequence being run has gone through the NMI vector, which uses up the latch.
+    pub(crate) nmi_taken: bool,
+    // Set by a taken branch which stayed on the same page.
+    pub(crate) skip_last_poll: bool,
+    // The cycle the current instruction started on.
+    pub(crate) instruction_start: u64,
+    // The lines at the end of the last two cycles `tick` ran, newest first.
+    pub(crate) recent_lines: [Lines; 2],
+}
+
+impl Mos6502 {
+    // Signals an edge on the NMI line. It's run before the next instruction that gets polled,
+    // whatever the interrupt disable flag says.
+    pub fn trigger_nmi(&mut self) {
+        self.interrupts.nmi_latched_at.get_or_insert(self.cycles);
+    }
+
+    // Holds the IRQ line, on top of anything on the bus asserting it, until it's released.
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.interrupts.irq_line = asserted;
+    }
+
+    // Lets the bus catch up with the cycles just spent, latching any NMI it raised on the way.
+    pub(crate) fn tick_bus(&mut self, cycles: u8) {
+        self.bus.tick(cycles);
+
+        if self.bus.take_nmi() {
+            self.trigger_nmi();
+        }
+    }
+
+    // The interrupt that will run before the next instruction, if there is one.
+    pub fn pending_interrupt(&self) -> Option<Interrupt> {
+        self.interrupts.pending
+    }
+
+    pub(crate) fn interrupt_lines(&self) -> Lines {
+        Lines {
+            nmi: self.interrupts.nmi_latched_at.is_some(),
+            irq: self.interrupts.irq_line || self.bus.irq_pending(),
+        }
+    }
+
+    // Whether a BRK or IRQ sequence should go through the NMI vector. Checked as the sequence
+    // gets to its vector.
+    pub(crate) fn nmi_hijacks_sequence(&self) -> bool {
+        self.interrupts
+            .nmi_latched_at
+            .is_some_and(|at| at < self.interrupts.instruction_start + NMI_HIJACK_CYCLES)
+    }
+
+    // Whether an interrupt line has woken the CPU from WAI. Even an IRQ that's disabled wakes
+    // it, but then it just carries on with the next instruction instead of running it.
+    pub(crate) fn wakes_from_wait(&mut self) -> bool {
+        let lines = self.interrupt_lines();
+
+        if !lines.nmi && !lines.irq {
+            return false;
+        }
+
+        if self.interrupts.pending.is_none() {
+            self.interrupts.pending = if lines.nmi {
+                Some(Interrupt::Nmi)
+            } else if !self.status.contains(Flags::INTERRUPT_DISABLE) {
+                Some(Interrupt::Irq)
+            } else {
+                None
+            };
+        }
+
+        true
+    }
+
+    pub(crate) fn reset_interrupts(&mut self) {
+        self.interrupts.nmi_latched_at = None;
+        self.interrupts.pending = None;
+        self.interrupts.nmi_taken = false;
+        self.interrupts.skip_last_poll = false;
+    }
+
+    // The 6502 polls its interrupt lines on the second to last cycle of every instruction, so
+    // `polled` is what they were by the end of that. Only a few instructions change what a
+    // poll sees:
+    // - CLI, SEI and PLP change the interrupt disable flag on their last cycle, after the poll,
+    //   so the change doesn't count until the next instruction. RTI changes it in time.
+    // - A taken branch which stays on the same page doesn't poll on its last cycle, so
+    //   `polled_early` (the cycle before) is used.
+    pub(crate) fn poll_interrupts(
+        &mut self,
+        opcode: &OpCode,
+        status_before: Flags,
+        polled: Lines,
+        polled_early: Lines,
+    ) {
+        let mut lines = if std::mem::take(&mut self.interrupts.skip_last_poll) {
+            polled_early
+        } else {
+            polled
+        };
+
+        if self.interrupts.nmi_taken {
+            // The edge that was polled has just been dealt with.
+            self.interrupts.nmi_taken = false;
+            self.interrupts.nmi_latched_at = None;
+            lines.nmi = false;
+        }
+
+        let interrupt_disable = match opcode.mnemonic {
+            "CLI" | "SEI" | "PLP" => status_before,
+            _ => self.status,
+        }
+        .contains(Flags::INTERRUPT_DISABLE);
+
+        self.interrupts.pending = if lines.nmi {
+            Some(Interrupt::Nmi)
+        } else if lines.irq && !interrupt_disable {
+            Some(Interrupt::Irq)
+        } else {
+            None
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_hex::assert_eq_hex;
+
+    use super::*;
+    use crate::{
+        cpus::mos_6502::{flat_bus::FlatBus, instruction_set::stack::STACK_BOTTOM},
+        interpret_result::InstructionResult,
+        roms::program::Program,
+    };
+
+    const NMI_HANDLER: u16 = 0x0300;
+    const IRQ_HANDLER: u16 = 0x0400;
+
+    fn create_cpu(code: &[u8], status: Flags) -> Mos6502 {
+        let mut cpu = Mos6502::new(Box::new(FlatBus::default()));
+        cpu.load_program_image(&Program::from_binary(code, 0x0200).unwrap());
+        cpu.bus.write_u16(NMI_VECTOR, NMI_HANDLER);
+        cpu.bus.write_u16(IRQ_VECTOR, IRQ_HANDLER);
+        cpu.status = status;
+        cpu
+    }
+
+    fn pulled_status(cpu: &Mos6502) -> Flags {
+        Flags::from_bits_truncate(cpu.bus.read(STACK_BOTTOM + cpu.stack_pointer as u16 + 1))
+    }
+
+    fn pulled_return_address(cpu: &Mos6502) -> u16 {
+        cpu.bus
+            .read_u16(STACK_BOTTOM + cpu.stack_pointer as u16 + 2)
+    }
+
+    fn tick_instruction(cpu: &mut Mos6502) -> u64 {
+        let start = cpu.cycles;
+        while cpu.tick().is_none() {}
+        cpu.cycles - start
+    }
+
+    #[test]
+    fn irq_runs_after_instruction_when_enabled() {
+        // NOP, NOP
+        let mut cpu = create_cpu(&[0xEA, 0xEA], Flags::UNUSED);
+        cpu.set_irq_line(true);
+
+        cpu.step();
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+
+        let cycles = cpu.cycles;
+        assert_eq!(InstructionResult::Ok, cpu.step());
+
+        assert_eq_hex!(IRQ_HANDLER, cpu.program_counter);
+        assert_eq!(7, cpu.cycles - cycles);
+        assert_eq_hex!(0x0201, pulled_return_address(&cpu));
+        assert!(!pulled_status(&cpu).contains(Flags::BREAK_COMMAND));
+        assert!(cpu.status.contains(Flags::INTERRUPT_DISABLE));
+    }
+
+    #[test]
+    fn irq_is_ignored_while_disabled() {
+        // NOP, NOP
+        let mut cpu = create_cpu(&[0xEA, 0xEA], Flags::UNUSED | Flags::INTERRUPT_DISABLE);
+        cpu.set_irq_line(true);
+
+        cpu.step();
+        cpu.step();
+
+        assert_eq!(None, cpu.pending_interrupt());
+        assert_eq_hex!(0x0202, cpu.program_counter);
+    }
+
+    #[test]
+    fn nmi_runs_while_irq_disabled_and_only_once() {
+        // NOP, NOP
+        let mut cpu = create_cpu(&[0xEA, 0xEA], Flags::UNUSED | Flags::INTERRUPT_DISABLE);
+        cpu.trigger_nmi();
+
+        cpu.step();
+        cpu.step();
+
+        assert_eq_hex!(NMI_HANDLER, cpu.program_counter);
+        assert_eq!(None, cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn cli_delays_irq_by_an_instruction() {
+        // CLI, NOP, NOP
+        let mut cpu = create_cpu(
+            &[0x58, 0xEA, 0xEA],
+            Flags::UNUSED | Flags::INTERRUPT_DISABLE,
+        );
+        cpu.set_irq_line(true);
+
+        cpu.step();
+        assert_eq!(None, cpu.pending_interrupt());
+
+        cpu.step();
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+
+        cpu.step();
+        assert_eq_hex!(IRQ_HANDLER, cpu.program_counter);
+        assert_eq_hex!(0x0202, pulled_return_address(&cpu));
+    }
+
+    #[test]
+    fn sei_still_lets_irq_through_after_it() {
+        // SEI, NOP
+        let mut cpu = create_cpu(&[0x78, 0xEA], Flags::UNUSED);
+        cpu.set_irq_line(true);
+
+        cpu.step();
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+
+        cpu.step();
+        assert_eq_hex!(IRQ_HANDLER, cpu.program_counter);
+        assert!(pulled_status(&cpu).contains(Flags::INTERRUPT_DISABLE));
+    }
+
+    #[test]
+    fn plp_delays_irq_by_an_instruction() {
+        // PLP (pulling a clear I flag), NOP
+        let mut cpu = create_cpu(&[0x28, 0xEA], Flags::UNUSED | Flags::INTERRUPT_DISABLE);
+        cpu.bus.write(0x01FE, Flags::UNUSED.bits());
+        cpu.set_irq_line(true);
+
+        cpu.step();
+        assert_eq!(None, cpu.pending_interrupt());
+
+        cpu.step();
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn rti_enables_irq_straight_away() {
+        // RTI (pulling a clear I flag and a return to $0280)
+        let mut cpu = create_cpu(&[0x40], Flags::UNUSED | Flags::INTERRUPT_DISABLE);
+        cpu.stack_pointer = 0xFC;
+        cpu.bus
+            .write_slice(0x01FD, &[Flags::UNUSED.bits(), 0x80, 0x02]);
+        cpu.set_irq_line(true);
+
+        cpu.step();
+
+        assert_eq_hex!(0x0280, cpu.program_counter);
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn taken_branch_on_same_page_doesnt_poll_on_last_cycle() {
+        // BNE +0, NOP
+        let mut cpu = create_cpu(&[0xD0, 0x00, 0xEA], Flags::UNUSED);
+
+        // The IRQ arrives during the branch's second cycle, too late for it to be polled.
+        assert_eq!(None, cpu.tick());
+        cpu.set_irq_line(true);
+        assert_eq!(None, cpu.tick());
+        assert_eq!(Some(InstructionResult::Ok), cpu.tick());
+        assert_eq!(None, cpu.pending_interrupt());
+
+        tick_instruction(&mut cpu);
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn taken_branch_across_page_polls_on_last_cycle() {
+        // BNE to the next page
+        let mut cpu = create_cpu(&[], Flags::UNUSED);
+        cpu.bus.write_slice(0x02F0, &[0xD0, 0x10]);
+        cpu.program_counter = 0x02F0;
+
+        assert_eq!(None, cpu.tick());
+        cpu.set_irq_line(true);
+
+        // The other three of its four cycles.
+        assert_eq!(3, tick_instruction(&mut cpu));
+        assert_eq!(Some(Interrupt::Irq), cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn nmi_hijacks_brk() {
+        // BRK
+        let mut cpu = create_cpu(&[0x00, 0xFF], Flags::UNUSED);
+        cpu.trigger_nmi();
+
+        assert_eq!(InstructionResult::EndProgram, cpu.step());
+
+        assert_eq_hex!(NMI_HANDLER, cpu.program_counter);
+        assert_eq_hex!(0x0202, pulled_return_address(&cpu));
+        assert!(pulled_status(&cpu).contains(Flags::BREAK_COMMAND));
+        assert_eq!(None, cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn nmi_hijacks_irq_sequence() {
+        // NOP
+        let mut cpu = create_cpu(&[0xEA], Flags::UNUSED);
+        cpu.set_irq_line(true);
+        cpu.step();
+        cpu.set_irq_line(false);
+
+        // Arriving on the IRQ sequence's third cycle.
+        cpu.tick();
+        cpu.tick();
+        cpu.trigger_nmi();
+        tick_instruction(&mut cpu);
+
+        assert_eq_hex!(NMI_HANDLER, cpu.program_counter);
+        assert!(!pulled_status(&cpu).contains(Flags::BREAK_COMMAND));
+        assert_eq!(None, cpu.pending_interrupt());
+    }
+
+    #[test]
+    fn late_nmi_runs_after_irq_sequence() {
+        // NOP
+        let mut cpu = create_cpu(&[0xEA], Flags::UNUSED);
+        cpu.set_irq_line(true);
+        cpu.step();
+        cpu.set_irq_line(false);
+
+        // Arriving after the IRQ sequence has pushed the status.
+        for _ in 0..5 {
+            cpu.tick();
+        }
+        cpu.trigger_nmi();
+        tick_instruction(&mut cpu);
+
+        assert_eq_hex!(IRQ_HANDLER, cpu.program_counter);
+        assert_eq!(Some(Interrupt::Nmi), cpu.pending_interrupt());
+    }
+}
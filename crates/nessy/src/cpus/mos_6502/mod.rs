@@ -1,7 +1,12 @@
 pub mod address_mode;
 pub mod bus;
 pub mod cpu;
+pub mod cycle;
+pub mod flat_bus;
 pub mod instruction_set;
+pub mod interrupt;
 pub mod memory;
 pub mod opcode;
 pub mod status;
+pub mod trace;
+pub mod variant;
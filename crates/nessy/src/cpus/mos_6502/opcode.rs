@@ -5,7 +5,7 @@ use crate::{
         address_mode::AddressMode,
         cpu::Mos6502,
         instruction_set::{
-            arithmetic::Arithmetic,
+            arithmetic::{Arithmetic, DecimalMode},
             branch::Branch,
             clear::Clear,
             compare::Compare,
@@ -71,14 +71,14 @@ lazy_static! {
     #[rustfmt::skip]
     // Opcode, Mnemonic, Bytes, Cycles, AddressMode, (optional: Undocumented), Exec_fn
     pub static ref OPCODES: HashMap<u8, OpCode> = generate_opcodes!(
-        (0x69, "ADC", 2, 2, AddressMode::Immediate, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x65, "ADC", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x75, "ADC", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x6D, "ADC", 3, 4, AddressMode::Absolute, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x7D, "ADC", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x79, "ADC", 3, 4, AddressMode::AbsoluteY, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x61, "ADC", 2, 6, AddressMode::IndirectX, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
-        (0x71, "ADC", 2, 5, AddressMode::IndirectY, |opcode, cpu| { Arithmetic::adc(opcode, cpu) }),
+        (0x69, "ADC", 2, 2, AddressMode::Immediate, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x65, "ADC", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x75, "ADC", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x6D, "ADC", 3, 4, AddressMode::Absolute, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x7D, "ADC", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x79, "ADC", 3, 4, AddressMode::AbsoluteY, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x61, "ADC", 2, 6, AddressMode::IndirectX, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x71, "ADC", 2, 5, AddressMode::IndirectY, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Unsupported) }),
 
         (0x29, "AND", 2, 2, AddressMode::Immediate, |opcode, cpu| { Logical::and(opcode, cpu) }),
         (0x25, "AND", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Logical::and(opcode, cpu) }),
@@ -130,8 +130,8 @@ lazy_static! {
         (0xCD, "CMP", 3, 4, AddressMode::Absolute, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
         (0xDD, "CMP", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
         (0xD9, "CMP", 3, 4, AddressMode::AbsoluteY, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
-        (0xC1, "CMP", 2, 2, AddressMode::IndirectX, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
-        (0xD1, "CMP", 2, 2, AddressMode::IndirectY, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
+        (0xC1, "CMP", 2, 6, AddressMode::IndirectX, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
+        (0xD1, "CMP", 2, 5, AddressMode::IndirectY, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
 
         (0xE0, "CPX", 2, 2, AddressMode::Immediate, |opcode, cpu| { Compare::cpx(opcode, cpu) }),
         (0xE4, "CPX", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Compare::cpx(opcode, cpu) }),
@@ -172,19 +172,19 @@ lazy_static! {
         (0xEE, "INC", 3, 6, AddressMode::Absolute, |opcode, cpu| { Increment::inc(opcode, cpu) }),
         (0xFE, "INC", 3, 7, AddressMode::AbsoluteX, |opcode, cpu| { Increment::inc(opcode, cpu) }),
 
-        (0xE7, "ISB", 2, 5, AddressMode::ZeroPage, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xF7, "ISB", 2, 6, AddressMode::ZeroPageX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xEF, "ISB", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xFF, "ISB", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xFB, "ISB", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xE3, "ISB", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
-        (0xF3, "ISB", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu) }),
+        (0xE7, "ISB", 2, 5, AddressMode::ZeroPage, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xF7, "ISB", 2, 6, AddressMode::ZeroPageX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xEF, "ISB", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xFF, "ISB", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xFB, "ISB", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xE3, "ISB", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xF3, "ISB", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Arithmetic::isb(opcode, cpu, DecimalMode::Unsupported) }),
 
         (0xE8, "INX", 1, 2, AddressMode::Implied, |_, cpu| { Increment::inx(cpu) }),
 
         (0xC8, "INY", 1, 2, AddressMode::Implied, |_, cpu| { Increment::iny(cpu) }),
 
-        (0x4C, "JMP", 3, 2, AddressMode::Absolute, |_, cpu| { Jump::jmp(cpu, JumpType::Absolute) }),
+        (0x4C, "JMP", 3, 3, AddressMode::Absolute, |_, cpu| { Jump::jmp(cpu, JumpType::Absolute) }),
         (0x6C, "JMP", 3, 5, AddressMode::None, |_, cpu| { Jump::jmp(cpu, JumpType::Indirect) }),
 
         (0x20, "JSR", 3, 6, AddressMode::Absolute, |opcode, cpu| { Jump::jsr(opcode, cpu) }),
@@ -276,13 +276,13 @@ lazy_static! {
 
         (0x68, "PLA", 1, 4, AddressMode::Implied, |_, cpu| { Stack::pla(cpu) }),
 
-        (0x67, "RRA", 2, 5, AddressMode::ZeroPage, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x77, "RRA", 2, 6, AddressMode::ZeroPageX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x6F, "RRA", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x7F, "RRA", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x7B, "RRA", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x63, "RRA", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
-        (0x73, "RRA", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu) }),
+        (0x67, "RRA", 2, 5, AddressMode::ZeroPage, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x77, "RRA", 2, 6, AddressMode::ZeroPageX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x6F, "RRA", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x7F, "RRA", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x7B, "RRA", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x63, "RRA", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
+        (0x73, "RRA", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Arithmetic::rra(opcode, cpu, DecimalMode::Unsupported) }),
 
 
         // Undocumented
@@ -291,8 +291,8 @@ lazy_static! {
         (0x2F, "RLA", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
         (0x3F, "RLA", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
         (0x3B, "RLA", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
-        (0x23, "RLA", 2, 5, AddressMode::IndirectX, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
-        (0x33, "RLA", 2, 5, AddressMode::IndirectY, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
+        (0x23, "RLA", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
+        (0x33, "RLA", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Logical::rla(opcode, cpu) }),
 
         (0x60, "RTS", 1, 6, AddressMode::Implied, |_, cpu| { Jump::rts(cpu) }),
 
@@ -329,17 +329,17 @@ lazy_static! {
         (0x94, "STY", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Store::sty(opcode, cpu) }),
         (0x8C, "STY", 3, 4, AddressMode::Absolute, |opcode, cpu| { Store::sty(opcode, cpu) }),
 
-        (0xE9, "SBC", 2, 2, AddressMode::Immediate, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xE5, "SBC", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xF5, "SBC", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xED, "SBC", 3, 4, AddressMode::Absolute, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xFD, "SBC", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xF9, "SBC", 3, 4, AddressMode::AbsoluteY, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xE1, "SBC", 2, 6, AddressMode::IndirectX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
-        (0xF1, "SBC", 2, 5, AddressMode::IndirectY, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
+        (0xE9, "SBC", 2, 2, AddressMode::Immediate, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xE5, "SBC", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xF5, "SBC", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xED, "SBC", 3, 4, AddressMode::Absolute, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xFD, "SBC", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xF9, "SBC", 3, 4, AddressMode::AbsoluteY, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xE1, "SBC", 2, 6, AddressMode::IndirectX, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
+        (0xF1, "SBC", 2, 5, AddressMode::IndirectY, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
 
         // an undocumented version of SBC
-        (0xEB, "SBC", 2, 2, AddressMode::Immediate, true, |opcode, cpu| { Arithmetic::sbc(opcode, cpu) }),
+        (0xEB, "SBC", 2, 2, AddressMode::Immediate, true, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Unsupported) }),
 
         (0x38, "SEC", 1, 2, AddressMode::Implied, |_, cpu| { Set::sec(cpu) }),
         (0xF8, "SED", 1, 2, AddressMode::Implied, |_, cpu| { Set::sed(cpu) }),
@@ -352,8 +352,8 @@ lazy_static! {
         (0x0F, "SLO", 3, 6, AddressMode::Absolute, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
         (0x1F, "SLO", 3, 7, AddressMode::AbsoluteX, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
         (0x1B, "SLO", 3, 7, AddressMode::AbsoluteY, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
-        (0x03, "SLO", 2, 5, AddressMode::IndirectX, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
-        (0x13, "SLO", 2, 5, AddressMode::IndirectY, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
+        (0x03, "SLO", 2, 8, AddressMode::IndirectX, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
+        (0x13, "SLO", 2, 8, AddressMode::IndirectY, true, |opcode, cpu| { Logical::slo(opcode, cpu) }),
 
         (0x2A, "ROL", 1, 2, AddressMode::Accumulator, |_, cpu| { Rotate::rotate_accumulator(cpu, Direction::Left) }),
         (0x26, "ROL", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Rotate::rotate_memory(opcode, cpu, Direction::Left) }),
@@ -379,9 +379,181 @@ lazy_static! {
 
         (0x98, "TYA", 1, 2, AddressMode::Implied, |_, cpu| { Transfer::tya(cpu) }),
     );
+
+    // The same opcodes on a stock NMOS 6502, where ADC and SBC honour the decimal flag.
+    pub static ref NMOS_6502_OPCODES: HashMap<u8, OpCode> = OPCODES
+        .iter()
+        .map(|(&code, opcode)| (code, with_decimal_mode(opcode, DecimalMode::Nmos)))
+        .collect();
+
+    // The WDC 65C02 drops the undocumented opcodes and adds its own instructions, leaving no
+    // opcode undefined. The ones it doesn't use are NOPs of various sizes.
+    #[rustfmt::skip]
+    pub static ref WDC_65C02_OPCODES: HashMap<u8, OpCode> = {
+        let mut hash_map: HashMap<u8, OpCode> = OPCODES
+            .iter()
+            .filter(|(_, opcode)| !opcode.undocumented)
+            .map(|(&code, opcode)| (code, with_decimal_mode(opcode, DecimalMode::Cmos)))
+            .collect();
+
+        hash_map.extend(generate_opcodes!(
+            (0x72, "ADC", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Arithmetic::adc(opcode, cpu, DecimalMode::Cmos) }),
+
+            (0x32, "AND", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Logical::and(opcode, cpu) }),
+
+            (0x89, "BIT", 2, 2, AddressMode::Immediate, |opcode, cpu| { Logical::bit(opcode, cpu) }),
+            (0x34, "BIT", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Logical::bit(opcode, cpu) }),
+            (0x3C, "BIT", 3, 4, AddressMode::AbsoluteX, |opcode, cpu| { Logical::bit(opcode, cpu) }),
+
+            (0x0F, "BBR0", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x1F, "BBR1", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x2F, "BBR2", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x3F, "BBR3", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x4F, "BBR4", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x5F, "BBR5", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x6F, "BBR6", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+            (0x7F, "BBR7", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbr(opcode, cpu) }),
+
+            (0x8F, "BBS0", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0x9F, "BBS1", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xAF, "BBS2", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xBF, "BBS3", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xCF, "BBS4", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xDF, "BBS5", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xEF, "BBS6", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+            (0xFF, "BBS7", 3, 5, AddressMode::ZeroPageRelative, |opcode, cpu| { Branch::bbs(opcode, cpu) }),
+
+            (0x80, "BRA", 2, 3, AddressMode::Relative, |_, cpu| { Branch::bra(cpu) }),
+
+            (0xD2, "CMP", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Compare::cmp(opcode, cpu) }),
+
+            (0x3A, "DEC", 1, 2, AddressMode::Accumulator, |_, cpu| { Decrement::dec_accumulator(cpu) }),
+
+            (0x52, "EOR", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Logical::eor(opcode, cpu) }),
+
+            (0x1A, "INC", 1, 2, AddressMode::Accumulator, |_, cpu| { Increment::inc_accumulator(cpu) }),
+
+            (0x6C, "JMP", 3, 6, AddressMode::None, |_, cpu| { Jump::jmp(cpu, JumpType::IndirectFixed) }),
+            (0x7C, "JMP", 3, 6, AddressMode::AbsoluteIndirectX, |_, cpu| { Jump::jmp(cpu, JumpType::IndexedIndirect) }),
+
+            (0xB2, "LDA", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Load::lda(opcode, cpu) }),
+
+            // The undefined opcodes. The one byte NOPs are over in a single cycle.
+            (0x03, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x0B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x13, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x1B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x23, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x2B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x33, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x3B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x43, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x4B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x53, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x5B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x63, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x6B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x73, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x7B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x83, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x8B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x93, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x9B, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xA3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xAB, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xB3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xBB, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xC3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xD3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xE3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xEB, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xF3, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xFB, "NOP", 1, 1, AddressMode::None, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x02, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x22, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x42, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x62, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x82, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xC2, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xE2, "NOP", 2, 2, AddressMode::Immediate, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x44, "NOP", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x54, "NOP", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xD4, "NOP", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xF4, "NOP", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xDC, "NOP", 3, 4, AddressMode::Absolute, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0xFC, "NOP", 3, 4, AddressMode::Absolute, |opcode, cpu| { System::nop(opcode, cpu) }),
+            (0x5C, "NOP", 3, 8, AddressMode::Absolute, |opcode, cpu| { System::long_nop(opcode, cpu) }),
+
+            (0x12, "ORA", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Logical::ora(opcode, cpu) }),
+
+            (0xDA, "PHX", 1, 3, AddressMode::Implied, |_, cpu| { Stack::phx(cpu) }),
+
+            (0x5A, "PHY", 1, 3, AddressMode::Implied, |_, cpu| { Stack::phy(cpu) }),
+
+            (0xFA, "PLX", 1, 4, AddressMode::Implied, |_, cpu| { Stack::plx(cpu) }),
+
+            (0x7A, "PLY", 1, 4, AddressMode::Implied, |_, cpu| { Stack::ply(cpu) }),
+
+            (0x07, "RMB0", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x17, "RMB1", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x27, "RMB2", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x37, "RMB3", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x47, "RMB4", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x57, "RMB5", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x67, "RMB6", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+            (0x77, "RMB7", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::rmb(opcode, cpu) }),
+
+            (0xF2, "SBC", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Arithmetic::sbc(opcode, cpu, DecimalMode::Cmos) }),
+
+            (0x87, "SMB0", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0x97, "SMB1", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xA7, "SMB2", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xB7, "SMB3", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xC7, "SMB4", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xD7, "SMB5", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xE7, "SMB6", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+            (0xF7, "SMB7", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::smb(opcode, cpu) }),
+
+            (0x92, "STA", 2, 5, AddressMode::ZeroPageIndirect, |opcode, cpu| { Store::sta(opcode, cpu) }),
+
+            (0xDB, "STP", 1, 3, AddressMode::Implied, |_, cpu| { System::stp(cpu) }),
+
+            (0x64, "STZ", 2, 3, AddressMode::ZeroPage, |opcode, cpu| { Store::stz(opcode, cpu) }),
+            (0x74, "STZ", 2, 4, AddressMode::ZeroPageX, |opcode, cpu| { Store::stz(opcode, cpu) }),
+            (0x9C, "STZ", 3, 4, AddressMode::Absolute, |opcode, cpu| { Store::stz(opcode, cpu) }),
+            (0x9E, "STZ", 3, 5, AddressMode::AbsoluteX, |opcode, cpu| { Store::stz(opcode, cpu) }),
+
+            (0x14, "TRB", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::trb(opcode, cpu) }),
+            (0x1C, "TRB", 3, 6, AddressMode::Absolute, |opcode, cpu| { Logical::trb(opcode, cpu) }),
+
+            (0x04, "TSB", 2, 5, AddressMode::ZeroPage, |opcode, cpu| { Logical::tsb(opcode, cpu) }),
+            (0x0C, "TSB", 3, 6, AddressMode::Absolute, |opcode, cpu| { Logical::tsb(opcode, cpu) }),
+
+            (0xCB, "WAI", 1, 3, AddressMode::Implied, |_, cpu| { System::wai(cpu) }),
+        ));
+
+        hash_map
+    };
+}
+
+// Copies an opcode, pointing ADC, SBC and the undocumented opcodes built on them at a different
+// decimal mode. The execute fns can't capture, so each combination gets its own closure.
+#[rustfmt::skip]
+fn with_decimal_mode(opcode: &OpCode, mode: DecimalMode) -> OpCode {
+    let execute: fn(&OpCode, &mut Mos6502) -> InstructionResult = match (opcode.mnemonic, mode) {
+        ("ADC", DecimalMode::Nmos) => |opcode, cpu| Arithmetic::adc(opcode, cpu, DecimalMode::Nmos),
+        ("ADC", DecimalMode::Cmos) => |opcode, cpu| Arithmetic::adc(opcode, cpu, DecimalMode::Cmos),
+        ("SBC", DecimalMode::Nmos) => |opcode, cpu| Arithmetic::sbc(opcode, cpu, DecimalMode::Nmos),
+        ("SBC", DecimalMode::Cmos) => |opcode, cpu| Arithmetic::sbc(opcode, cpu, DecimalMode::Cmos),
+        ("ISB", DecimalMode::Nmos) => |opcode, cpu| Arithmetic::isb(opcode, cpu, DecimalMode::Nmos),
+        ("RRA", DecimalMode::Nmos) => |opcode, cpu| Arithmetic::rra(opcode, cpu, DecimalMode::Nmos),
+        _ => opcode.execute,
+    };
+
+    OpCode { execute, ..opcode.clone() }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct OpCode {
     pub opcode: u8,
     pub mnemonic: &'static str,
@@ -412,6 +584,11 @@ impl OpCode {
             execute,
         }
     }
+
+    // Which bit RMB, SMB, BBR and BBS work on, which is in the top half of their opcode.
+    pub fn bit_index(&self) -> usize {
+        ((self.opcode >> 4) & 0x7) as usize
+    }
 }
 
 #[cfg(test)]
@@ -437,5 +614,15 @@ mod test {
     #[test]
     fn test_opcodes_count() {
         assert_eq!(231, OPCODES.len());
+        assert_eq!(231, NMOS_6502_OPCODES.len());
+        assert_eq!(256, WDC_65C02_OPCODES.len());
+    }
+
+    #[test]
+    fn test_bit_index() {
+        assert_eq!(0, WDC_65C02_OPCODES[&0x07].bit_index());
+        assert_eq!(7, WDC_65C02_OPCODES[&0xF7].bit_index());
+        assert_eq!(0, WDC_65C02_OPCODES[&0x8F].bit_index());
+        assert_eq!(3, WDC_65C02_OPCODES[&0x3F].bit_index());
     }
 }
@@ -1,26 +1,55 @@
-use nessy::cpus::mos_6502::{address_mode::AddressMode, cpu::Mos6502, opcode::OpCode};
-
-use crate::integration::nestest::opcode_behaviour::OpcodeBehaviour;
+use crate::cpus::mos_6502::{
+    address_mode::AddressMode, cpu::Mos6502, opcode::OpCode,
+    trace::opcode_behaviour::OpcodeBehaviour,
+};
 
+// Writes out the instruction at the program counter the way nestest's log does, with the
+// addresses and values it'll work with.
 pub struct Disassembler {}
 
 impl Disassembler {
+    // Everything is peeked, so tracing a register doesn't change what the program reads.
+    fn peek_u16(cpu: &Mos6502, address: u16) -> u16 {
+        let lo_byte = cpu.bus.peek(address) as u16;
+        let hi_byte = cpu.bus.peek(address.wrapping_add(1)) as u16;
+        (hi_byte << 8) | lo_byte
+    }
+
     fn relative(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let offset = cpu.bus.read(cpu.program_counter.wrapping_add(1)) as i8;
-        let target = ((cpu.program_counter + 2) as i32 + offset as i32) as u16;
+        let offset = cpu.bus.peek(cpu.program_counter.wrapping_add(1)) as i8;
+        let target = cpu
+            .program_counter
+            .wrapping_add(2)
+            .wrapping_add(offset as i16 as u16);
         Some(format!("{} ${:04X}", opcode.mnemonic, target))
     }
 
+    // BBR and BBS, with the zero page byte they test and where they'd branch to.
+    fn zeropage_relative(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
+        let addr = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
+        let value = cpu.bus.peek(addr as u16);
+        let offset = cpu.bus.peek(cpu.program_counter.wrapping_add(2)) as i8;
+        let target = cpu
+            .program_counter
+            .wrapping_add(3)
+            .wrapping_add(offset as i16 as u16);
+
+        Some(format!(
+            "{} ${:02X} = {:02X},${:04X}",
+            opcode.mnemonic, addr, value, target
+        ))
+    }
+
     fn zeropage(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let addr = cpu.bus.read(cpu.program_counter.wrapping_add(1));
-        let value = cpu.bus.read(addr as u16);
+        let addr = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
+        let value = cpu.bus.peek(addr as u16);
         Some(format!("{} ${:02X} = {:02X}", opcode.mnemonic, addr, value))
     }
 
     fn zeropage_x(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let memory_address = cpu.bus.read(cpu.program_counter.wrapping_add(1));
+        let memory_address = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
         let target_address = memory_address.wrapping_add(cpu.registers.x);
-        let target_value = cpu.bus.read(target_address as u16);
+        let target_value = cpu.bus.peek(target_address as u16);
 
         Some(format!(
             "{} ${:02X},X @ {:02X} = {:02X}",
@@ -29,9 +58,9 @@ impl Disassembler {
     }
 
     fn zeropage_y(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let memory_address = cpu.bus.read(cpu.program_counter.wrapping_add(1));
+        let memory_address = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
         let target_address = memory_address.wrapping_add(cpu.registers.y);
-        let target_value = cpu.bus.read(target_address as u16);
+        let target_value = cpu.bus.peek(target_address as u16);
 
         Some(format!(
             "{} ${:02X},Y @ {:02X} = {:02X}",
@@ -40,7 +69,7 @@ impl Disassembler {
     }
 
     fn absolute(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let address = cpu.bus.read_u16(cpu.program_counter.wrapping_add(1));
+        let address = Disassembler::peek_u16(cpu, cpu.program_counter.wrapping_add(1));
         let mnemonic_address = format!("{} ${:04X}", opcode.mnemonic, address);
 
         match OpcodeBehaviour::from_mnemonic(opcode.mnemonic) {
@@ -49,7 +78,7 @@ impl Disassembler {
                 | OpcodeBehaviour::Write
                 | OpcodeBehaviour::ReadModifyWrite = opcode_behaviour
                 {
-                    let memory_value = cpu.bus.read(address);
+                    let memory_value = cpu.bus.peek(address);
                     Some(format!("{} = {:02X}", mnemonic_address, memory_value))
                 } else {
                     Some(mnemonic_address)
@@ -60,9 +89,9 @@ impl Disassembler {
     }
 
     fn absolute_x(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let memory_address = cpu.bus.read_u16(cpu.program_counter.wrapping_add(1));
+        let memory_address = Disassembler::peek_u16(cpu, cpu.program_counter.wrapping_add(1));
         let target_address = memory_address.wrapping_add(cpu.registers.x as u16);
-        let target_value = cpu.bus.read(target_address);
+        let target_value = cpu.bus.peek(target_address);
 
         Some(format!(
             "{} ${:04X},X @ {:04X} = {:02X}",
@@ -71,9 +100,9 @@ impl Disassembler {
     }
 
     fn absolute_y(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
-        let memory_address = cpu.bus.read_u16(cpu.program_counter.wrapping_add(1));
+        let memory_address = Disassembler::peek_u16(cpu, cpu.program_counter.wrapping_add(1));
         let target_address = memory_address.wrapping_add(cpu.registers.y as u16);
-        let target_value = cpu.bus.read(target_address);
+        let target_value = cpu.bus.peek(target_address);
 
         Some(format!(
             "{} ${:04X},Y @ {:04X} = {:02X}",
@@ -91,19 +120,19 @@ impl Disassembler {
                 | OpcodeBehaviour::ReadModifyWrite = opcode_behaviour
                 {
                     // Get the byte of this opcode to add as the indirect value - ($FF,X)
-                    let opcode_byte = cpu.bus.read(cpu.program_counter.wrapping_add(1));
+                    let opcode_byte = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
 
                     // Memory value is the indirect byte plus the register involved. - @ FF
                     let memory_address = opcode_byte.wrapping_add(cpu.registers.x);
 
                     // Read the target address from the memory address generated. - 0400
-                    let target_address_lo = cpu.bus.read(memory_address as u16);
-                    let target_address_hi = cpu.bus.read(memory_address.wrapping_add(1) as u16);
+                    let target_address_lo = cpu.bus.peek(memory_address as u16);
+                    let target_address_hi = cpu.bus.peek(memory_address.wrapping_add(1) as u16);
 
                     // Then get the target value from that address. - 5D
                     let target_address =
                         ((target_address_hi as u16) << 8) | (target_address_lo as u16);
-                    let target_value = cpu.bus.read(target_address);
+                    let target_value = cpu.bus.peek(target_address);
 
                     Some(format!(
                         "{} (${:02X},X) @ {:02X} = {:04X} = {:02X}",
@@ -127,15 +156,15 @@ impl Disassembler {
                 | OpcodeBehaviour::ReadModifyWrite = opcode_behaviour
                 {
                     // Get the byte of this opcode to add as the indirect value - ($FF),Y
-                    let base_address = cpu.bus.read(cpu.program_counter.wrapping_add(1));
+                    let base_address = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
 
-                    let lo_byte = cpu.bus.read(base_address as u16);
-                    let hi_byte = cpu.bus.read(base_address.wrapping_add(1) as u16);
+                    let lo_byte = cpu.bus.peek(base_address as u16);
+                    let hi_byte = cpu.bus.peek(base_address.wrapping_add(1) as u16);
 
                     let deref_base = (hi_byte as u16) << 8 | (lo_byte as u16);
                     let memory_address = deref_base.wrapping_add(cpu.registers.y as u16);
 
-                    let target_value = cpu.bus.read(memory_address);
+                    let target_value = cpu.bus.peek(memory_address);
 
                     Some(format!(
                         "{} (${:02X}),Y = {:04X} @ {:04X} = {:02X}",
@@ -149,6 +178,35 @@ impl Disassembler {
         }
     }
 
+    fn zeropage_indirect(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
+        let base_address = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
+
+        let lo_byte = cpu.bus.peek(base_address as u16);
+        let hi_byte = cpu.bus.peek(base_address.wrapping_add(1) as u16);
+
+        let target_address = (hi_byte as u16) << 8 | (lo_byte as u16);
+        let target_value = cpu.bus.peek(target_address);
+
+        Some(format!(
+            "{} (${:02X}) = {:04X} = {:02X}",
+            opcode.mnemonic, base_address, target_address, target_value
+        ))
+    }
+
+    fn absolute_indirect_x(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
+        let ptr = Disassembler::peek_u16(cpu, cpu.program_counter.wrapping_add(1));
+        let indexed_ptr = ptr.wrapping_add(cpu.registers.x as u16);
+
+        let jump_target_lo = cpu.bus.peek(indexed_ptr);
+        let jump_target_hi = cpu.bus.peek(indexed_ptr.wrapping_add(1));
+        let jump_target_address = u16::from(jump_target_lo) | (u16::from(jump_target_hi) << 8);
+
+        Some(format!(
+            "{} (${:04X},X) = {:04X}",
+            opcode.mnemonic, ptr, jump_target_address
+        ))
+    }
+
     fn no_address_mode(cpu: &Mos6502, opcode: &OpCode) -> Option<String> {
         let mnemonic_str = String::from(opcode.mnemonic);
 
@@ -156,18 +214,18 @@ impl Disassembler {
             Some(opcode_behaviour) => {
                 if let OpcodeBehaviour::Control = opcode_behaviour {
                     // First address
-                    let ptr_lo = cpu.bus.read(cpu.program_counter.wrapping_add(1));
-                    let ptr_hi = cpu.bus.read(cpu.program_counter.wrapping_add(2));
+                    let ptr_lo = cpu.bus.peek(cpu.program_counter.wrapping_add(1));
+                    let ptr_hi = cpu.bus.peek(cpu.program_counter.wrapping_add(2));
 
                     // Form 16-bit ptr address
                     let ptr = u16::from(ptr_lo) | (u16::from(ptr_hi) << 8);
 
                     // Get the indirect jump's lo byte of the address
-                    let jump_target_lo = cpu.bus.read(ptr);
+                    let jump_target_lo = cpu.bus.peek(ptr);
 
                     // Build up the high byte of the jump target
                     let hi_address = (ptr & 0xFF00) | ((ptr.wrapping_add(1)) & 0x00FF);
-                    let jump_target_hi = cpu.bus.read(hi_address);
+                    let jump_target_hi = cpu.bus.peek(hi_address);
 
                     let jump_target_address =
                         u16::from(jump_target_lo) | (u16::from(jump_target_hi) << 8);
@@ -191,7 +249,7 @@ impl Disassembler {
             AddressMode::Immediate => Some(format!(
                 "{} #${:02X}",
                 opcode.mnemonic,
-                cpu.bus.read(cpu.program_counter.wrapping_add(1))
+                cpu.bus.peek(cpu.program_counter.wrapping_add(1))
             )),
             AddressMode::Relative => Disassembler::relative(cpu, opcode),
             AddressMode::ZeroPage => Disassembler::zeropage(cpu, opcode),
@@ -202,6 +260,9 @@ impl Disassembler {
             AddressMode::AbsoluteY => Disassembler::absolute_y(cpu, opcode),
             AddressMode::IndirectX => Disassembler::indirect_x(cpu, opcode),
             AddressMode::IndirectY => Disassembler::indirect_y(cpu, opcode),
+            AddressMode::ZeroPageIndirect => Disassembler::zeropage_indirect(cpu, opcode),
+            AddressMode::AbsoluteIndirectX => Disassembler::absolute_indirect_x(cpu, opcode),
+            AddressMode::ZeroPageRelative => Disassembler::zeropage_relative(cpu, opcode),
             AddressMode::None => Disassembler::no_address_mode(cpu, opcode),
         }
     }
@@ -210,13 +271,10 @@ impl Disassembler {
 #[cfg(test)]
 mod test {
     use mockall::{mock, predicate};
-    use nessy::{
-        cpus::mos_6502::{address_mode::AddressMode, bus::MemoryBus, cpu::Mos6502, opcode::OpCode},
-        interpret_result::InstructionResult,
-    };
     use sif::parameterized;
 
-    use crate::integration::nestest::Disassembler;
+    use super::*;
+    use crate::{cpus::mos_6502::bus::MemoryBus, interpret_result::InstructionResult};
 
     const TEST_MNEMONIC: &str = "TEST";
     const LDA_MNEMONIC: &str = "LDA";
@@ -238,7 +296,10 @@ mod test {
             fn read_u16(&self, address: u16) -> u16;
             fn write_u16(&mut self, address: u16, data: u16);
 
-            fn insert_rom(&mut self, rom: nessy::roms::ROM);
+            fn peek(&self, address: u16) -> u8;
+
+            fn insert_rom(&mut self, rom: crate::roms::ROM);
+            fn insert_cartridge(&mut self, cartridge: Box<dyn crate::roms::mappers::Mapper>);
         }
     }
 
@@ -259,7 +320,15 @@ mod test {
     fn test_absolute() {
         let mut mock_bus = MockBus::default();
 
-        mock_bus.expect_read_u16().returning(move |_| 0xC5F5);
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x1))
+            .returning(move |_| 0xF5);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x2))
+            .returning(move |_| 0xC5);
 
         let cpu = Mos6502::new(Box::new(mock_bus));
 
@@ -276,12 +345,12 @@ mod test {
 
         #[rustfmt::skip]
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(1))
             .returning(move |_| 0x10);
 
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x10))
             .returning(move |_| 0x41);
 
@@ -300,7 +369,7 @@ mod test {
 
         #[rustfmt::skip]
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x2))
             .returning(move |_| 0x10);
 
@@ -315,13 +384,63 @@ mod test {
         assert_eq!("TEST $0013", result.unwrap());
     }
 
+    #[test]
+    fn test_relative_wraps_around_the_top_of_memory() {
+        let mut mock_bus = MockBus::default();
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0xFFFF))
+            .returning(move |_| 0x10);
+
+        let mut cpu = Mos6502::new(Box::new(mock_bus));
+
+        cpu.program_counter = 0xFFFE;
+
+        let opcode = create_opcode(TEST_MNEMONIC, AddressMode::Relative);
+
+        let result = Disassembler::generate_disassembly(&cpu, &opcode);
+
+        assert_eq!("TEST $0010", result.unwrap());
+    }
+
+    #[test]
+    fn test_zeropage_relative() {
+        let mut mock_bus = MockBus::default();
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x2))
+            .returning(move |_| 0x10);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x10))
+            .returning(move |_| 0x41);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x3))
+            .returning(move |_| 0xFC);
+
+        let mut cpu = Mos6502::new(Box::new(mock_bus));
+
+        cpu.program_counter = 0x1;
+
+        let opcode = create_opcode("BBR0", AddressMode::ZeroPageRelative);
+
+        let result = Disassembler::generate_disassembly(&cpu, &opcode);
+
+        assert_eq!("BBR0 $10 = 41,$0000", result.unwrap());
+    }
+
     #[test]
     fn test_immediate() {
         let mut mock_bus = MockBus::default();
 
         #[rustfmt::skip]
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x1))
             .returning(move |_| 0x10);
 
@@ -341,22 +460,22 @@ mod test {
         let mut mock_bus = MockBus::default();
 
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x1))
             .returning(move |_| 0x10);
 
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x11))
             .returning(move |_| 0x0);
 
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x12))
             .returning(move |_| 0x02);
 
         mock_bus
-            .expect_read()
+            .expect_peek()
             .with(predicate::eq(0x0200))
             .returning(move |_| 0xAA);
 
@@ -372,6 +491,41 @@ mod test {
         assert_eq!("LDA ($10,X) @ 11 = 0200 = AA", result.unwrap());
     }
 
+    #[test]
+    fn test_zeropage_indirect() {
+        let mut mock_bus = MockBus::default();
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x1))
+            .returning(move |_| 0x10);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x10))
+            .returning(move |_| 0x0);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x11))
+            .returning(move |_| 0x02);
+
+        mock_bus
+            .expect_peek()
+            .with(predicate::eq(0x0200))
+            .returning(move |_| 0xAA);
+
+        let mut cpu = Mos6502::new(Box::new(mock_bus));
+
+        cpu.program_counter = 0x0;
+
+        let opcode = create_opcode(LDA_MNEMONIC, AddressMode::ZeroPageIndirect);
+
+        let result = Disassembler::generate_disassembly(&cpu, &opcode);
+
+        assert_eq!("LDA ($10) = 0200 = AA", result.unwrap());
+    }
+
     #[test]
     fn test_accumulator_returns_correctly() {
         let cpu = Mos6502::default();
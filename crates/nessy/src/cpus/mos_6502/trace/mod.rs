@@ -0,0 +1,224 @@
+use std::{io, str::FromStr};
+
+use crate::cpus::mos_6502::{
+    cpu::Mos6502,
+    trace::{sink::TraceSink, state::State},
+};
+
+pub mod disassembler;
+pub mod opcode_behaviour;
+pub mod sink;
+pub mod state;
+
+// How each traced instruction is written out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TraceFormat {
+    // nestest.log's, less the PPU and cycle columns, so it can be diffed against it.
+    #[default]
+    Nestest,
+    // Like Mesen's trace logger: the instruction first, then the registers and cycle count.
+    Mesen,
+    // Like FCEUX's trace logger: the registers first, then the instruction.
+    Fceux,
+}
+
+impl TraceFormat {
+    pub fn line(self, cpu: &Mos6502) -> String {
+        let state = State::capture(cpu);
+        let registers = &state.registers;
+
+        match self {
+            TraceFormat::Nestest => format!(
+                "{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+                registers.program_counter,
+                state.opcode_bytes_string(),
+                if state.opcode.undocumented { "*" } else { " " },
+                state.opcode.opcode_string,
+                registers.a,
+                registers.x,
+                registers.y,
+                registers.status,
+                registers.stack_pointer
+            ),
+            TraceFormat::Mesen => format!(
+                "{:04X}  {:<9} {:<32} A:{:02X} X:{:02X} Y:{:02X} S:{:02X} P:{} Cyc:{}",
+                registers.program_counter,
+                state.opcode_bytes_string(),
+                state.opcode.opcode_string,
+                registers.a,
+                registers.x,
+                registers.y,
+                registers.stack_pointer,
+                state.status_string(),
+                state.cycles
+            ),
+            TraceFormat::Fceux => format!(
+                "A:{:02X} X:{:02X} Y:{:02X} S:{:02X} P:{}  ${:04X}:{:<9} {}",
+                registers.a,
+                registers.x,
+                registers.y,
+                registers.stack_pointer,
+                state.status_string(),
+                registers.program_counter,
+                state.opcode_bytes_string(),
+                state.opcode.opcode_string
+            ),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = &'static str;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "nestest" => Ok(TraceFormat::Nestest),
+            "mesen" => Ok(TraceFormat::Mesen),
+            "fceux" => Ok(TraceFormat::Fceux),
+            _ => Err("Unknown trace format, expected nestest, mesen or fceux."),
+        }
+    }
+}
+
+// Logs every instruction the CPU runs, in the chosen format. Hand it the CPU before each
+// instruction, e.g. from `run_with_callback`.
+pub struct TraceLogger {
+    format: TraceFormat,
+    sink: TraceSink,
+}
+
+impl TraceLogger {
+    pub fn new(format: TraceFormat, sink: TraceSink) -> Self {
+        Self { format, sink }
+    }
+
+    // Logs the instruction at the program counter, which is yet to run.
+    pub fn trace(&mut self, cpu: &Mos6502) -> io::Result<()> {
+        self.sink.write_line(self.format.line(cpu))
+    }
+
+    // The lines a ring buffer is holding on to, oldest first. Other sinks don't keep any.
+    pub fn recent_lines(&self) -> Vec<&str> {
+        match &self.sink {
+            TraceSink::RingBuffer { lines, .. } => lines.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use sif::parameterized;
+
+    use super::*;
+    use crate::cpus::mos_6502::flat_bus::FlatBus;
+
+    fn create_cpu() -> Mos6502 {
+        let mut cpu = Mos6502::new(Box::new(FlatBus::default()));
+
+        // LDA #$42, STA $0200, BRK
+        cpu.bus
+            .write_slice(0xC000, &[0xA9, 0x42, 0x8D, 0x00, 0x02, 0x00]);
+        cpu.program_counter = 0xC000;
+
+        cpu
+    }
+
+    #[parameterized]
+    #[case(
+        TraceFormat::Nestest,
+        "C000  A9 42     LDA #$42                        A:00 X:00 Y:00 P:24 SP:FD"
+    )]
+    #[case(
+        TraceFormat::Mesen,
+        "C000  A9 42     LDA #$42                         A:00 X:00 Y:00 S:FD P:nvUbdIzc Cyc:0"
+    )]
+    #[case(
+        TraceFormat::Fceux,
+        "A:00 X:00 Y:00 S:FD P:nvUbdIzc  $C000:A9 42     LDA #$42"
+    )]
+    fn line_is_written_in_the_format(format: TraceFormat, expected: &str) {
+        assert_eq!(expected, format.line(&create_cpu()));
+    }
+
+    #[test]
+    fn line_shows_what_the_instruction_works_with() {
+        let mut cpu = create_cpu();
+        cpu.step();
+
+        assert_eq!(
+            "C002  8D 00 02  STA $0200 = 00                  A:42 X:00 Y:00 P:24 SP:FD",
+            TraceFormat::Nestest.line(&cpu)
+        );
+    }
+
+    #[parameterized]
+    #[case("nestest", TraceFormat::Nestest)]
+    #[case("Mesen", TraceFormat::Mesen)]
+    #[case("FCEUX", TraceFormat::Fceux)]
+    fn format_is_parsed_from_its_name(name: &str, expected: TraceFormat) {
+        assert_eq!(Ok(expected), name.parse());
+    }
+
+    #[test]
+    fn format_is_not_parsed_from_an_unknown_name() {
+        assert!("nintendulator".parse::<TraceFormat>().is_err());
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_most_recent_lines() {
+        let mut cpu = create_cpu();
+        let mut logger = TraceLogger::new(TraceFormat::Fceux, TraceSink::ring_buffer(2));
+
+        for _ in 0..3 {
+            logger.trace(&cpu).unwrap();
+            cpu.step();
+        }
+
+        let lines = logger.recent_lines();
+
+        assert_eq!(2, lines.len());
+        assert!(lines[0].ends_with("$C002:8D 00 02  STA $0200 = 00"));
+        assert!(lines[1].ends_with("$C005:00        BRK"));
+    }
+
+    #[test]
+    fn callback_is_given_every_line() {
+        let cpu = create_cpu();
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink_lines = lines.clone();
+
+        let mut logger = TraceLogger::new(
+            TraceFormat::Nestest,
+            TraceSink::callback(move |line| sink_lines.borrow_mut().push(line.to_string())),
+        );
+
+        logger.trace(&cpu).unwrap();
+
+        assert_eq!(vec![TraceFormat::Nestest.line(&cpu)], *lines.borrow());
+        assert!(logger.recent_lines().is_empty());
+    }
+
+    #[test]
+    fn writer_writes_a_line_per_instruction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.log");
+        let cpu = create_cpu();
+
+        let mut logger = TraceLogger::new(TraceFormat::Mesen, TraceSink::file(&path).unwrap());
+        logger.trace(&cpu).unwrap();
+        logger.trace(&cpu).unwrap();
+        logger.flush().unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(2, log.lines().count());
+        assert_eq!(TraceFormat::Mesen.line(&cpu), log.lines().next().unwrap());
+    }
+}
@@ -16,10 +16,10 @@ impl OpcodeBehaviour {
         match mnemonic {
             "ADC" | "AND" | "BIT" | "CMP" | "CPX" | "CPY" | "EOR" | "LDA" | "LAX" | "LDX"
             | "LDY" | "NOP" | "ORA" | "SBC" => Some(OpcodeBehaviour::Read),
-            "SAX" | "STA" | "STX" | "STY" => Some(OpcodeBehaviour::Write),
+            "SAX" | "STA" | "STX" | "STY" | "STZ" => Some(OpcodeBehaviour::Write),
             "ASL" | "DEC" | "DCP" | "INC" | "ISB" | "LSR" | "RLA" | "RRA" | "ROL" | "ROR"
-            | "SLO" | "SRE" => Some(OpcodeBehaviour::ReadModifyWrite),
-            "BCC" | "BCS" | "BEQ" | "BMI" | "BNE" | "BPL" | "BVC" | "BVS" => {
+            | "SLO" | "SRE" | "TRB" | "TSB" => Some(OpcodeBehaviour::ReadModifyWrite),
+            "BCC" | "BCS" | "BEQ" | "BMI" | "BNE" | "BPL" | "BRA" | "BVC" | "BVS" => {
                 Some(OpcodeBehaviour::Branch)
             }
             "JMP" | "JSR" | "RTS" | "RTI" | "BRK" => Some(OpcodeBehaviour::Control),
@@ -0,0 +1,64 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// Where trace lines end up.
+pub enum TraceSink {
+    // Anything that can be written to, a line at a time. Usually a file.
+    Writer(Box<dyn Write>),
+    // Only keeps the last `capacity` lines, for when it's what led up to a crash that matters.
+    RingBuffer {
+        lines: VecDeque<String>,
+        capacity: usize,
+    },
+    Callback(Box<dyn FnMut(&str)>),
+}
+
+impl TraceSink {
+    pub fn file(path: &Path) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(TraceSink::Writer(Box::new(BufWriter::new(file))))
+    }
+
+    pub fn ring_buffer(capacity: usize) -> Self {
+        TraceSink::RingBuffer {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn callback(callback: impl FnMut(&str) + 'static) -> Self {
+        TraceSink::Callback(Box::new(callback))
+    }
+
+    pub(crate) fn write_line(&mut self, line: String) -> io::Result<()> {
+        match self {
+            TraceSink::Writer(writer) => writeln!(writer, "{}", line),
+            TraceSink::RingBuffer { lines, capacity } => {
+                if *capacity > 0 {
+                    if lines.len() == *capacity {
+                        lines.pop_front();
+                    }
+
+                    lines.push_back(line);
+                }
+
+                Ok(())
+            }
+            TraceSink::Callback(callback) => {
+                callback(&line);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TraceSink::Writer(writer) => writer.flush(),
+            _ => Ok(()),
+        }
+    }
+}
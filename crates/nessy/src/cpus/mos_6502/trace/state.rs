@@ -0,0 +1,69 @@
+use crate::cpus::{
+    Cpu, RegisterSnapshot,
+    mos_6502::{cpu::Mos6502, trace::disassembler::Disassembler},
+};
+
+pub struct OpcodeState {
+    pub opcode_bytes: Vec<u8>,
+    pub opcode_string: String,
+    pub undocumented: bool,
+}
+
+// Everything a trace line is made from, taken just before the instruction runs.
+pub struct State {
+    pub registers: RegisterSnapshot,
+    pub opcode: OpcodeState,
+    pub cycles: u64,
+}
+
+impl State {
+    pub fn capture(cpu: &Mos6502) -> Self {
+        let opcode_byte = cpu.bus.peek(cpu.program_counter);
+
+        // An opcode the variant doesn't have still gets a line, as it's likely what's wanted.
+        let opcode = match cpu.variant().opcodes().get(&opcode_byte) {
+            Some(opcode) => OpcodeState {
+                opcode_bytes: (0..=opcode.bytes)
+                    .map(|i| cpu.bus.peek(cpu.program_counter.wrapping_add(i as u16)))
+                    .collect(),
+                opcode_string: Disassembler::generate_disassembly(cpu, opcode).unwrap_or_default(),
+                undocumented: opcode.undocumented,
+            },
+            None => OpcodeState {
+                opcode_bytes: vec![opcode_byte],
+                opcode_string: String::from("???"),
+                undocumented: true,
+            },
+        };
+
+        State {
+            registers: cpu.snapshot(),
+            opcode,
+            cycles: cpu.cycles,
+        }
+    }
+
+    pub fn opcode_bytes_string(&self) -> String {
+        self.opcode
+            .opcode_bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    // The status register as letters, upper case when set: NVUBDIZC.
+    pub fn status_string(&self) -> String {
+        "NVUBDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, flag)| {
+                if self.registers.status & (0x80 >> i) != 0 {
+                    flag
+                } else {
+                    flag.to_ascii_lowercase()
+                }
+            })
+            .collect()
+    }
+}
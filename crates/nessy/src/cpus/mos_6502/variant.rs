@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use crate::cpus::mos_6502::{
+    instruction_set::arithmetic::DecimalMode,
+    opcode::{NMOS_6502_OPCODES, OPCODES, OpCode, WDC_65C02_OPCODES},
+};
+
+// The chips the core can stand in for, which differ in the opcodes they understand.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Variant {
+    // The NES's CPU: an NMOS 6502 with decimal mode cut out.
+    #[default]
+    Ricoh2A03,
+    // A stock NMOS 6502, decimal mode and all.
+    Nmos6502,
+    // The WDC 65C02, with its extra instructions and bug fixes.
+    Wdc65C02,
+}
+
+impl Variant {
+    pub fn opcodes(self) -> &'static HashMap<u8, OpCode> {
+        match self {
+            Variant::Ricoh2A03 => &OPCODES,
+            Variant::Nmos6502 => &NMOS_6502_OPCODES,
+            Variant::Wdc65C02 => &WDC_65C02_OPCODES,
+        }
+    }
+
+    // How ADC and SBC treat the decimal flag, the same as the opcode table does.
+    pub fn decimal_mode(self) -> DecimalMode {
+        match self {
+            Variant::Ricoh2A03 => DecimalMode::Unsupported,
+            Variant::Nmos6502 => DecimalMode::Nmos,
+            Variant::Wdc65C02 => DecimalMode::Cmos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sif::parameterized;
+
+    use super::*;
+    use crate::{
+        cpus::mos_6502::{
+            cpu::Mos6502,
+            flat_bus::FlatBus,
+            interrupt::{IRQ_VECTOR, NMI_VECTOR},
+            status::Flags,
+        },
+        interpret_result::InstructionResult,
+        roms::program::Program,
+    };
+
+    fn create_cpu(variant: Variant, code: &[u8]) -> Mos6502 {
+        let mut cpu = Mos6502::new(Box::new(FlatBus::default())).with_variant(variant);
+        cpu.load_program_image(&Program::from_binary(code, 0x0200).unwrap());
+        cpu
+    }
+
+    fn run_until_brk(cpu: &mut Mos6502) {
+        while cpu.step() != InstructionResult::EndProgram {}
+    }
+
+    #[parameterized]
+    #[case(Variant::Ricoh2A03, 0x1E)]
+    #[case(Variant::Nmos6502, 0x24)]
+    #[case(Variant::Wdc65C02, 0x24)]
+    fn adc_only_adds_bcd_with_decimal_mode(variant: Variant, expected: u8) {
+        // SED, CLC, LDA #$15, ADC #$09, BRK
+        let mut cpu = create_cpu(variant, &[0xF8, 0x18, 0xA9, 0x15, 0x69, 0x09, 0x00]);
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(expected, cpu.registers.a);
+    }
+
+    #[parameterized]
+    #[case(Variant::Ricoh2A03, 0x0C)]
+    #[case(Variant::Nmos6502, 0x06)]
+    #[case(Variant::Wdc65C02, 0x06)]
+    fn sbc_only_subtracts_bcd_with_decimal_mode(variant: Variant, expected: u8) {
+        // SED, SEC, LDA #$15, SBC #$09, BRK
+        let mut cpu = create_cpu(variant, &[0xF8, 0x38, 0xA9, 0x15, 0xE9, 0x09, 0x00]);
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(expected, cpu.registers.a);
+        assert!(cpu.status.contains(Flags::CARRY));
+    }
+
+    #[parameterized]
+    #[case(Variant::Nmos6502, 2)]
+    #[case(Variant::Wdc65C02, 3)]
+    fn decimal_adc_takes_an_extra_cycle_on_the_65c02(variant: Variant, expected: u64) {
+        // SED, ADC #$09
+        let mut cpu = create_cpu(variant, &[0xF8, 0x69, 0x09]);
+        cpu.step();
+        let start = cpu.cycles;
+
+        while cpu.tick().is_none() {}
+
+        assert_eq!(expected, cpu.cycles - start);
+    }
+
+    #[parameterized]
+    // The NMOS chips take the high byte from $0200, which is the JMP itself.
+    #[case(Variant::Ricoh2A03, 0x6C34)]
+    #[case(Variant::Nmos6502, 0x6C34)]
+    #[case(Variant::Wdc65C02, 0x5634)]
+    fn jmp_indirect_only_wraps_its_pointer_on_nmos(variant: Variant, expected: u16) {
+        // JMP ($02FF)
+        let mut cpu = create_cpu(variant, &[0x6C, 0xFF, 0x02]);
+        cpu.bus.write_slice(0x02FF, &[0x34, 0x56]);
+
+        cpu.step();
+
+        assert_eq!(expected, cpu.program_counter);
+    }
+
+    #[test]
+    fn wdc_65c02_runs_its_own_instructions() {
+        let code = [
+            0xA2, 0x11, // LDX #$11
+            0xA0, 0x22, // LDY #$22
+            0xDA, // PHX
+            0x5A, // PHY
+            0xA2, 0x00, // LDX #$00
+            0xFA, // PLX
+            0x7A, // PLY
+            0x64, 0x10, // STZ $10
+            0xA9, 0x0F, // LDA #$0F
+            0x85, 0x11, // STA $11
+            0xA9, 0x03, // LDA #$03
+            0x14, 0x11, // TRB $11
+            0xA9, 0xF0, // LDA #$F0
+            0x04, 0x11, // TSB $11
+            0x80, 0x01, // BRA +1
+            0xEA, // NOP, skipped over
+            0x00, // BRK
+        ];
+        let mut cpu = create_cpu(Variant::Wdc65C02, &code);
+        cpu.bus.write(0x0010, 0xFF);
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(0x22, cpu.registers.x);
+        assert_eq!(0x11, cpu.registers.y);
+        assert_eq!(0x00, cpu.bus.read(0x0010));
+        assert_eq!(0xFC, cpu.bus.read(0x0011));
+        assert!(cpu.status.contains(Flags::ZERO));
+    }
+
+    #[test]
+    fn wdc_65c02_runs_its_new_address_modes() {
+        let code = [
+            0xB2, 0x10, // LDA ($10)
+            0x1A, // INC A
+            0x92, 0x12, // STA ($12)
+            0x89, 0x80, // BIT #$80
+            0xA2, 0x02, // LDX #$02
+            0x7C, 0x20, 0x00, // JMP ($0020,X)
+            0x00, // BRK, jumped over
+            0x3A, // DEC A
+            0x00, // BRK
+        ];
+        let mut cpu = create_cpu(Variant::Wdc65C02, &code);
+        cpu.bus.write_slice(0x0010, &[0x00, 0x03, 0x01, 0x03]);
+        cpu.bus.write_slice(0x0022, &[0x0D, 0x02]);
+        cpu.bus.write(0x0300, 0x41);
+        cpu.status |= Flags::OVERFLOW;
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(0x42, cpu.bus.read(0x0301));
+        assert_eq!(0x41, cpu.registers.a);
+        // BIT # leaves N and V alone.
+        assert!(cpu.status.contains(Flags::OVERFLOW));
+        assert!(!cpu.status.contains(Flags::NEGATIVE));
+    }
+
+    #[parameterized]
+    #[case(&[0x03], 1, 0x0201)]
+    #[case(&[0x02, 0xFF], 2, 0x0202)]
+    #[case(&[0x44, 0x10], 3, 0x0202)]
+    #[case(&[0xD4, 0x10], 4, 0x0202)]
+    #[case(&[0xDC, 0x00, 0x10], 4, 0x0203)]
+    #[case(&[0x5C, 0x00, 0x10], 8, 0x0203)]
+    fn wdc_65c02_runs_undefined_opcodes_as_nops(code: &[u8], cycles: u64, expected_pc: u16) {
+        let mut cpu = create_cpu(Variant::Wdc65C02, code);
+
+        assert_eq!(InstructionResult::Ok, cpu.step());
+
+        assert_eq!(cycles, cpu.cycles);
+        assert_eq!(expected_pc, cpu.program_counter);
+    }
+
+    #[parameterized]
+    #[case(Variant::Nmos6502, true)]
+    #[case(Variant::Wdc65C02, false)]
+    fn only_the_65c02_leaves_decimal_mode_for_brk(variant: Variant, expected: bool) {
+        // SED, BRK
+        let mut cpu = create_cpu(variant, &[0xF8, 0x00]);
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(expected, cpu.status.contains(Flags::DECIMAL_MODE));
+    }
+
+    #[test]
+    fn wdc_65c02_leaves_out_the_undocumented_opcodes() {
+        assert!(Variant::Ricoh2A03.opcodes()[&0xDA].undocumented);
+        assert!(
+            Variant::Wdc65C02
+                .opcodes()
+                .values()
+                .all(|opcode| !opcode.undocumented)
+        );
+        assert_eq!("PHX", Variant::Wdc65C02.opcodes()[&0xDA].mnemonic);
+    }
+
+    #[test]
+    fn wdc_65c02_defines_every_opcode() {
+        let opcodes = Variant::Wdc65C02.opcodes();
+
+        assert!((0..=0xFF).all(|code| opcodes.contains_key(&code)));
+    }
+
+    #[test]
+    fn wdc_65c02_runs_its_bit_instructions() {
+        let code = [
+            0x87, 0x10, // SMB0 $10
+            0xF7, 0x10, // SMB7 $10
+            0x37, 0x11, // RMB3 $11
+            0x0F, 0x10, 0x01, // BBR0 $10,+1, not taken
+            0xE8, // INX
+            0x8F, 0x10, 0x01, // BBS0 $10,+1
+            0xE8, // INX, skipped over
+            0x3F, 0x11, 0x01, // BBR3 $11,+1
+            0xE8, // INX, skipped over
+            0x00, // BRK
+        ];
+        let mut cpu = create_cpu(Variant::Wdc65C02, &code);
+        cpu.bus.write(0x0011, 0xFF);
+
+        run_until_brk(&mut cpu);
+
+        assert_eq!(0x81, cpu.bus.read(0x0010));
+        assert_eq!(0xF7, cpu.bus.read(0x0011));
+        assert_eq!(0x01, cpu.registers.x);
+    }
+
+    #[parameterized]
+    #[case(0x0200, 0x01, 5, 0x0203)]
+    #[case(0x0200, 0x00, 6, 0x0213)]
+    #[case(0x02F0, 0x00, 7, 0x0303)]
+    fn bbr_counts_branch_cycles(start: u16, value: u8, expected_cycles: u64, expected_pc: u16) {
+        // BBR0 $10,+$10
+        let mut cpu = create_cpu(Variant::Wdc65C02, &[]);
+        cpu.bus.write_slice(start, &[0x0F, 0x10, 0x10]);
+        cpu.bus.write(0x0010, value);
+        cpu.program_counter = start;
+
+        cpu.step();
+
+        assert_eq!(expected_cycles, cpu.cycles);
+        assert_eq!(expected_pc, cpu.program_counter);
+    }
+
+    #[parameterized]
+    // An IRQ that's disabled still wakes the CPU up, which carries on after the WAI.
+    #[case(Flags::UNUSED | Flags::INTERRUPT_DISABLE, false, 0x0202)]
+    #[case(Flags::UNUSED, false, 0x0400)]
+    #[case(Flags::UNUSED | Flags::INTERRUPT_DISABLE, true, 0x0300)]
+    fn wai_waits_for_an_interrupt(status: Flags, nmi: bool, expected_pc: u16) {
+        // WAI, NOP
+        let mut cpu = create_cpu(Variant::Wdc65C02, &[0xCB, 0xEA]);
+        cpu.bus.write_u16(NMI_VECTOR, 0x0300);
+        cpu.bus.write_u16(IRQ_VECTOR, 0x0400);
+        cpu.status = status;
+
+        assert_eq!(InstructionResult::Ok, cpu.step());
+        assert_eq!(3, cpu.cycles);
+
+        for _ in 0..10 {
+            assert_eq!(InstructionResult::Ok, cpu.step());
+        }
+        assert_eq!(13, cpu.cycles);
+        assert_eq!(0x0201, cpu.program_counter);
+
+        if nmi {
+            cpu.trigger_nmi();
+        } else {
+            cpu.set_irq_line(true);
+        }
+        cpu.step();
+
+        assert_eq!(expected_pc, cpu.program_counter);
+    }
+
+    #[test]
+    fn stp_stops_until_reset() {
+        // STP, NOP
+        let mut cpu = create_cpu(Variant::Wdc65C02, &[0xDB, 0xEA]);
+        cpu.set_irq_line(true);
+        cpu.trigger_nmi();
+
+        assert_eq!(InstructionResult::EndProgram, cpu.step());
+        assert_eq!(InstructionResult::EndProgram, cpu.step());
+        assert_eq!(0x0201, cpu.program_counter);
+
+        cpu.reset();
+        cpu.program_counter = 0x0201;
+
+        assert_eq!(InstructionResult::Ok, cpu.step());
+        assert_eq!(0x0202, cpu.program_counter);
+    }
+}
@@ -3,8 +3,6 @@ pub enum InstructionResult {
     Ok,
     IllegalInstruction,
     EndProgram,
-    StackOverflow,
-    StackUnderflow,
 }
 
 #[derive(Debug, PartialEq)]
@@ -1,4 +1,8 @@
+pub mod audio;
 pub mod cpus;
 pub mod interpret_result;
 pub mod nes;
+pub mod nsf_player;
+pub mod ppu;
 pub mod roms;
+pub mod test_rom;
@@ -1,6 +1,21 @@
-use crate::cpus::mos_6502::cpu::Mos6502;
+use crate::cpus::{Cpu, mos_6502::cpu::Mos6502};
 
-#[derive(Default)]
-pub struct NES {
-    pub cpu: Mos6502,
+// The console, around whichever core it's been given. That's the 2A03's `Mos6502` unless said
+// otherwise.
+pub struct NES<C: Cpu = Mos6502> {
+    pub cpu: C,
+}
+
+impl Default for NES {
+    fn default() -> Self {
+        Self {
+            cpu: Mos6502::default(),
+        }
+    }
+}
+
+impl<C: Cpu> NES<C> {
+    pub fn new(cpu: C) -> Self {
+        Self { cpu }
+    }
 }
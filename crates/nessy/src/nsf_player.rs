@@ -0,0 +1,367 @@
+use crate::{
+    cpus::mos_6502::{
+        bus::{Bus, MemoryBus},
+        cpu::Mos6502,
+        instruction_set::stack::Stack,
+    },
+    interpret_result::InstructionResult,
+    roms::{mappers::nsf::NsfCartridge, nsf::Nsf, region::Region},
+};
+
+pub const NTSC_CPU_CLOCK_HZ: f64 = 1_789_773.0;
+pub const PAL_CPU_CLOCK_HZ: f64 = 1_662_607.0;
+
+// INIT and PLAY are called as if by JSR from here, and have finished once the CPU comes back.
+// It's never actually executed, and is in the unused space below the bank registers.
+const RETURN_ADDRESS: u16 = 0x5FF6;
+
+// Samples this close together count as the same level when listening for silence.
+const SILENCE_THRESHOLD: f32 = 1.0 / 1024.0;
+
+// A routine still running after this many cycles (about a second) is never going to return.
+const MAX_ROUTINE_CYCLES: u64 = 2_000_000;
+
+const RAM_END: u16 = 0x07FF;
+const PRG_RAM_START: u16 = 0x6000;
+const PRG_RAM_END: u16 = 0x7FFF;
+
+const APU_CHANNEL_REGISTERS_END: u16 = 0x4013;
+const APU_STATUS: u16 = 0x4015;
+const APU_FRAME_COUNTER: u16 = 0x4017;
+
+// Plays an NSF by doing what the spec asks of a player: set up the machine, call INIT with
+// the song number, then call PLAY at the rate the header gives.
+pub struct NsfPlayer {
+    cpu: Mos6502,
+    nsf: Nsf,
+    region: Region,
+    song: u8,
+    sample_rate: u32,
+    cycles_per_sample: f64,
+    next_sample_cycle: f64,
+}
+
+impl NsfPlayer {
+    pub fn new(nsf: Nsf, sample_rate: u32) -> Self {
+        let region = match nsf.region() {
+            Region::Pal => Region::Pal,
+            _ => Region::Ntsc,
+        };
+
+        let mut bus = Bus::default().with_region(region);
+        bus.insert_cartridge(Box::new(NsfCartridge::new(&nsf)));
+
+        let cpu_clock = match region {
+            Region::Pal => PAL_CPU_CLOCK_HZ,
+            _ => NTSC_CPU_CLOCK_HZ,
+        };
+
+        let song = nsf.starting_song();
+
+        Self {
+            cpu: Mos6502::new(Box::new(bus)),
+            nsf,
+            region,
+            song,
+            sample_rate,
+            cycles_per_sample: cpu_clock / sample_rate as f64,
+            next_sample_cycle: 0.0,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        self.nsf.title()
+    }
+
+    pub fn artist(&self) -> &str {
+        self.nsf.artist()
+    }
+
+    pub fn copyright(&self) -> &str {
+        self.nsf.copyright()
+    }
+
+    pub fn track_count(&self) -> u8 {
+        self.nsf.total_songs()
+    }
+
+    // The current track, counting from 0.
+    pub fn track(&self) -> u8 {
+        self.song
+    }
+
+    // CPU cycles between calls to PLAY.
+    pub fn cycles_per_frame(&self) -> u64 {
+        let cpu_clock = match self.region {
+            Region::Pal => PAL_CPU_CLOCK_HZ,
+            _ => NTSC_CPU_CLOCK_HZ,
+        };
+
+        (self.nsf.play_period_us(self.region) as f64 * cpu_clock / 1_000_000.0) as u64
+    }
+
+    // Resets the machine and calls INIT for the given track, counting from 0.
+    pub fn start_track(&mut self, track: u8) -> Result<(), &'static str> {
+        self.song = track % self.track_count().max(1);
+
+        for address in (0..=RAM_END).chain(PRG_RAM_START..=PRG_RAM_END) {
+            self.cpu.bus.write(address, 0);
+        }
+
+        for address in 0x4000..=APU_CHANNEL_REGISTERS_END {
+            self.cpu.bus.write(address, 0);
+        }
+        self.cpu.bus.write(APU_STATUS, 0x00);
+        self.cpu.bus.write(APU_STATUS, 0x0F);
+        self.cpu.bus.write(APU_FRAME_COUNTER, 0x40);
+
+        if self.nsf.is_bankswitched() {
+            for (register, bank) in (0x5FF8..=0x5FFF).zip(self.nsf.bank_init()) {
+                self.cpu.bus.write(register, bank);
+            }
+        }
+
+        self.cpu.reset();
+
+        let region = match self.region {
+            Region::Pal => 1,
+            _ => 0,
+        };
+
+        self.call(self.nsf.init_address(), self.song, region, &mut vec![])
+    }
+
+    pub fn next_track(&mut self) -> Result<(), &'static str> {
+        self.start_track((self.song + 1) % self.track_count().max(1))
+    }
+
+    pub fn previous_track(&mut self) -> Result<(), &'static str> {
+        let track_count = self.track_count().max(1);
+        self.start_track(self.song.checked_sub(1).unwrap_or(track_count - 1))
+    }
+
+    // Calls PLAY, then lets the rest of the frame pass, returning the audio samples made.
+    pub fn play_frame(&mut self) -> Result<Vec<f32>, &'static str> {
+        let mut samples = vec![];
+        let frame_end = self.cpu.cycles + self.cycles_per_frame();
+
+        self.call(self.nsf.play_address(), 0, 0, &mut samples)?;
+
+        while self.cpu.cycles < frame_end {
+            self.cpu.bus.tick(1);
+            self.cpu.cycles += 1;
+            self.take_samples(&mut samples);
+        }
+
+        Ok(samples)
+    }
+
+    // Plays a track from the start for `seconds`, or until its output has held still for
+    // `silence_seconds`, in which case the silence is left off the end.
+    pub fn render_track(
+        &mut self,
+        track: u8,
+        seconds: f32,
+        silence_seconds: Option<f32>,
+    ) -> Result<Vec<f32>, &'static str> {
+        let total_samples = (seconds * self.sample_rate as f32) as usize;
+        let silence_samples =
+            silence_seconds.map(|seconds| (seconds * self.sample_rate as f32) as usize);
+
+        self.start_track(track)?;
+
+        let mut samples: Vec<f32> = vec![];
+        let mut silent_run = 0;
+
+        while samples.len() < total_samples {
+            for sample in self.play_frame()? {
+                let previous = samples.last().copied().unwrap_or(sample);
+
+                if (sample - previous).abs() < SILENCE_THRESHOLD {
+                    silent_run += 1;
+                } else {
+                    silent_run = 0;
+                }
+
+                samples.push(sample);
+            }
+
+            if silence_samples.is_some_and(|silence_samples| silent_run >= silence_samples) {
+                samples.truncate(samples.len() - silent_run);
+                break;
+            }
+        }
+
+        samples.truncate(total_samples);
+
+        Ok(samples)
+    }
+
+    fn take_samples(&mut self, samples: &mut Vec<f32>) {
+        while self.next_sample_cycle <= self.cpu.cycles as f64 {
+            samples.push(self.cpu.bus.audio_output());
+            self.next_sample_cycle += self.cycles_per_sample;
+        }
+    }
+
+    // Runs the routine at `address` until it returns, as if it had been JSR'd to.
+    fn call(
+        &mut self,
+        address: u16,
+        a: u8,
+        x: u8,
+        samples: &mut Vec<f32>,
+    ) -> Result<(), &'static str> {
+        let return_address = RETURN_ADDRESS - 1;
+
+        Stack::push(&mut self.cpu, (return_address >> 8) as u8);
+        Stack::push(&mut self.cpu, return_address as u8);
+
+        self.cpu.registers.a = a;
+        self.cpu.registers.x = x;
+        self.cpu.program_counter = address;
+
+        let start = self.cpu.cycles;
+
+        while self.cpu.program_counter != RETURN_ADDRESS {
+            if self.cpu.cycles - start > MAX_ROUTINE_CYCLES {
+                return Err("NSF routine never returned.");
+            }
+
+            if self.cpu.step() != InstructionResult::Ok {
+                return Err("NSF routine hit an instruction it couldn't run.");
+            }
+
+            self.take_samples(samples);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::roms::nsf::test::create_nsf_data;
+
+    // INIT ($8000) stores the song number at $00, PLAY ($8010) increments $01.
+    const CODE: [u8; 0x15] = [
+        0x85, 0x00, // STA $00
+        0x60, // RTS
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // padding
+        0xE6, 0x01, // INC $01
+        0x60, // RTS
+        0, 0,
+    ];
+
+    fn create_player() -> NsfPlayer {
+        NsfPlayer::new(Nsf::load(&create_nsf_data(&CODE, [0; 8])).unwrap(), 44100)
+    }
+
+    #[test]
+    fn test_start_track_calls_init_with_song() {
+        let mut player = create_player();
+
+        player.start_track(2).unwrap();
+
+        assert_eq!(2, player.track());
+        assert_eq!(2, player.cpu.bus.read(0x00));
+    }
+
+    #[test]
+    fn test_previous_track_wraps_given_255_tracks() {
+        let mut nsf_data = create_nsf_data(&CODE, [0; 8]);
+        nsf_data[0x06] = 255;
+        let mut player = NsfPlayer::new(Nsf::load(&nsf_data).unwrap(), 44100);
+
+        player.start_track(0).unwrap();
+        player.previous_track().unwrap();
+        assert_eq!(254, player.track());
+
+        player.previous_track().unwrap();
+        assert_eq!(253, player.track());
+    }
+
+    #[test]
+    fn test_play_frame_calls_play_for_a_frame() {
+        let mut player = create_player();
+        player.start_track(0).unwrap();
+
+        let start = player.cpu.cycles;
+        let samples = player.play_frame().unwrap();
+        player.play_frame().unwrap();
+
+        assert_eq!(2, player.cpu.bus.read(0x01));
+        assert_eq!(29780, player.cycles_per_frame());
+        assert!(player.cpu.cycles - start >= 2 * player.cycles_per_frame());
+
+        // A 60Hz frame at 44.1kHz.
+        assert!((734..=737).contains(&samples.len()));
+    }
+
+    #[test]
+    fn test_next_and_previous_track_wrap() {
+        let mut player = create_player();
+
+        player.start_track(2).unwrap();
+        player.next_track().unwrap();
+        assert_eq!(0, player.track());
+
+        player.previous_track().unwrap();
+        assert_eq!(2, player.track());
+        assert_eq!(2, player.cpu.bus.read(0x00));
+    }
+
+    #[test]
+    fn test_render_track_renders_for_seconds() {
+        let mut player = create_player();
+
+        let samples = player.render_track(1, 0.5, None).unwrap();
+
+        assert_eq!(22050, samples.len());
+        assert_eq!(1, player.cpu.bus.read(0x00));
+    }
+
+    #[test]
+    fn test_render_track_stops_on_silence() {
+        let mut player = create_player();
+
+        // The tune never touches the APU, so it's silent from the start.
+        let samples = player.render_track(0, 10.0, Some(1.0)).unwrap();
+
+        assert!(samples.is_empty());
+        assert!(player.cpu.cycles < NTSC_CPU_CLOCK_HZ as u64 * 2);
+    }
+
+    #[test]
+    fn test_render_track_plays_apu() {
+        // INIT ($8000) returns straight away, PLAY ($8010) holds a 50% pulse at full volume.
+        let code = [
+            0x60, // RTS
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // padding
+            0xA9, 0xBF, // LDA #$BF
+            0x8D, 0x00, 0x40, // STA $4000
+            0xA9, 0xFD, // LDA #$FD
+            0x8D, 0x02, 0x40, // STA $4002
+            0xA9, 0x00, // LDA #$00
+            0x8D, 0x03, 0x40, // STA $4003
+            0x60, // RTS
+        ];
+        let nsf = Nsf::load(&create_nsf_data(&code, [0; 8])).unwrap();
+        let mut player = NsfPlayer::new(nsf, 44100);
+
+        let samples = player.render_track(0, 0.5, Some(0.1)).unwrap();
+
+        assert_eq!(22050, samples.len());
+        assert!(samples.iter().any(|&sample| sample > 0.1));
+    }
+
+    #[test]
+    fn test_call_returns_error_given_endless_routine() {
+        // INIT is JMP $8000.
+        let nsf = Nsf::load(&create_nsf_data(&[0x4C, 0x00, 0x80], [0; 8])).unwrap();
+        let mut player = NsfPlayer::new(nsf, 44100);
+
+        assert_eq!(Err("NSF routine never returned."), player.start_track(0));
+    }
+}
@@ -0,0 +1,185 @@
+use std::cell::Cell;
+
+const CONTROL: u16 = 0x2000;
+const STATUS: u16 = 0x2002;
+
+const CONTROL_NMI_ENABLE: u8 = 0b1000_0000;
+const STATUS_VBLANK: u8 = 0b1000_0000;
+
+const DOTS_PER_SCANLINE: u16 = 341;
+const SCANLINES_PER_FRAME: u16 = 262;
+const VBLANK_SCANLINE: u16 = 241;
+const PRE_RENDER_SCANLINE: u16 = 261;
+
+// The PPU runs three dots to every CPU cycle on NTSC.
+const DOTS_PER_CPU_CYCLE: u8 = 3;
+
+// Stands in for the PPU until it draws anything: only the frame timing is real, so the
+// vblank flag in $2002 and the NMI at the start of vblank behave, which is what games and
+// test ROMs wait on. Everything else reads back whatever was last written to the PPU.
+#[derive(Debug, Default)]
+pub struct Ppu {
+    control: u8,
+    // Reading $2002 acknowledges vblank, which reads can't otherwise change.
+    status: Cell<u8>,
+    // The PPU's data bus holds the last value written, which is what open bus reads see.
+    latch: u8,
+    dot: u16,
+    scanline: u16,
+    nmi: bool,
+}
+
+impl Ppu {
+    // Reads one of the eight registers, $2000-$2007.
+    pub fn read(&self, register: u16) -> u8 {
+        if register != STATUS {
+            return self.latch;
+        }
+
+        let status = self.peek(register);
+        self.status.set(self.status.get() & !STATUS_VBLANK);
+
+        status
+    }
+
+    // Reads a register without acknowledging vblank, for debuggers and trace logs.
+    pub fn peek(&self, register: u16) -> u8 {
+        if register != STATUS {
+            return self.latch;
+        }
+
+        self.status.get() | (self.latch & 0x1F)
+    }
+
+    pub fn write(&mut self, register: u16, data: u8) {
+        self.latch = data;
+
+        if register == CONTROL {
+            // Enabling NMIs part way through vblank raises one straight away.
+            if self.control & CONTROL_NMI_ENABLE == 0
+                && data & CONTROL_NMI_ENABLE != 0
+                && self.status.get() & STATUS_VBLANK != 0
+            {
+                self.nmi = true;
+            }
+
+            self.control = data;
+        }
+    }
+
+    // Advances the PPU by a single CPU cycle.
+    pub fn clock(&mut self) {
+        for _ in 0..DOTS_PER_CPU_CYCLE {
+            self.clock_dot();
+        }
+    }
+
+    fn clock_dot(&mut self) {
+        self.dot += 1;
+
+        if self.dot == DOTS_PER_SCANLINE {
+            self.dot = 0;
+            self.scanline = (self.scanline + 1) % SCANLINES_PER_FRAME;
+        }
+
+        if self.dot != 1 {
+            return;
+        }
+
+        if self.scanline == VBLANK_SCANLINE {
+            self.status.set(self.status.get() | STATUS_VBLANK);
+
+            if self.control & CONTROL_NMI_ENABLE != 0 {
+                self.nmi = true;
+            }
+        } else if self.scanline == PRE_RENDER_SCANLINE {
+            self.status.set(self.status.get() & !STATUS_VBLANK);
+        }
+    }
+
+    // Whether vblank has raised an NMI since this was last asked.
+    pub fn take_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // CPU cycles from power on to the start of vblank.
+    const CYCLES_TO_VBLANK: u32 =
+        (VBLANK_SCANLINE as u32 * DOTS_PER_SCANLINE as u32 + 1).div_ceil(DOTS_PER_CPU_CYCLE as u32);
+
+    fn clock(ppu: &mut Ppu, cycles: u32) {
+        for _ in 0..cycles {
+            ppu.clock();
+        }
+    }
+
+    #[test]
+    fn test_status_reports_vblank_once() {
+        let mut ppu = Ppu::default();
+
+        clock(&mut ppu, CYCLES_TO_VBLANK - 1);
+        assert_eq!(0, ppu.read(STATUS) & STATUS_VBLANK);
+
+        clock(&mut ppu, 1);
+        assert_eq!(STATUS_VBLANK, ppu.read(STATUS) & STATUS_VBLANK);
+        assert_eq!(0, ppu.read(STATUS) & STATUS_VBLANK);
+    }
+
+    #[test]
+    fn test_peek_leaves_vblank_set() {
+        let mut ppu = Ppu::default();
+
+        clock(&mut ppu, CYCLES_TO_VBLANK);
+
+        assert_eq!(STATUS_VBLANK, ppu.peek(STATUS) & STATUS_VBLANK);
+        assert_eq!(STATUS_VBLANK, ppu.read(STATUS) & STATUS_VBLANK);
+    }
+
+    #[test]
+    fn test_vblank_ends_on_pre_render_scanline() {
+        let mut ppu = Ppu::default();
+
+        clock(
+            &mut ppu,
+            CYCLES_TO_VBLANK + 20 * DOTS_PER_SCANLINE as u32 / 3 + 1,
+        );
+
+        assert_eq!(0, ppu.read(STATUS) & STATUS_VBLANK);
+    }
+
+    #[test]
+    fn test_vblank_raises_nmi_when_enabled() {
+        let mut ppu = Ppu::default();
+        ppu.write(CONTROL, CONTROL_NMI_ENABLE);
+
+        clock(&mut ppu, CYCLES_TO_VBLANK);
+
+        assert!(ppu.take_nmi());
+        assert!(!ppu.take_nmi());
+    }
+
+    #[test]
+    fn test_enabling_nmi_during_vblank_raises_nmi() {
+        let mut ppu = Ppu::default();
+
+        clock(&mut ppu, CYCLES_TO_VBLANK);
+        assert!(!ppu.take_nmi());
+
+        ppu.write(CONTROL, CONTROL_NMI_ENABLE);
+        assert!(ppu.take_nmi());
+    }
+
+    #[test]
+    fn test_read_returns_latch() {
+        let mut ppu = Ppu::default();
+
+        ppu.write(0x2005, 0xAB);
+
+        assert_eq!(0xAB, ppu.read(0x2007));
+        assert_eq!(0x0B, ppu.read(STATUS));
+    }
+}
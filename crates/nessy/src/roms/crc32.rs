@@ -0,0 +1,30 @@
+// The reflected CRC-32 polynomial, as used by zip, PNG and every ROM database out there.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+
+    #[parameterized]
+    #[case(b"", 0x0000_0000)]
+    #[case(b"a", 0xE8B7_BE43)]
+    #[case(b"123456789", 0xCBF4_3926)]
+    fn test_crc32(data: &[u8], expected: u32) {
+        assert_eq_hex!(expected, crc32(data));
+    }
+}
@@ -0,0 +1,321 @@
+use std::{fmt::Display, sync::LazyLock};
+
+use crate::roms::{loader::Metadata, mappers, mirroring::Mirroring, region::Region};
+
+// Known-good header values for a game, keyed by the CRC32 of its PRG and CHR ROM together
+// (that is, everything after the header and trainer).
+#[derive(Debug, PartialEq)]
+pub struct GameEntry {
+    pub crc32: u32,
+    pub name: &'static str,
+    pub mapper: u8,
+    // None when the board switches mirroring itself, so the header bit means nothing.
+    pub mirroring: Option<Mirroring>,
+    pub has_battery_ram: bool,
+    pub region: Region,
+}
+
+// Generated from NesCartDB by scripts/generate_database.py. Only cartridges NesCartDB has
+// verified against the real board belong in here, otherwise the database would just be
+// trading one bad header for another.
+const DATABASE: &str = include_str!("database.tsv");
+
+// The ROMs nessy's own tests run, which NesCartDB has never heard of.
+const ASSETS: &str = include_str!("assets.tsv");
+
+static GAMES: LazyLock<Vec<GameEntry>> =
+    LazyLock::new(|| [DATABASE, ASSETS].into_iter().flat_map(parse).collect());
+
+// One game per line: CRC32, mapper, mirroring (H, V, 4 or - for mapper controlled), battery
+// (0 or 1), region and name, separated by tabs. Games on mappers we can't run are left out,
+// since correcting their headers would only turn them into an unsupported mapper error.
+fn parse(data: &'static str) -> Vec<GameEntry> {
+    data.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| parse_entry(line).unwrap_or_else(|| panic!("Bad database line: {}", line)))
+        .filter(|game| mappers::is_supported(game.mapper))
+        .collect()
+}
+
+fn parse_entry(line: &'static str) -> Option<GameEntry> {
+    let mut fields = line.split('\t');
+
+    let crc32 = u32::from_str_radix(fields.next()?, 16).ok()?;
+    // Mappers past 255 need NES 2.0, which we can't run either.
+    let mapper = fields
+        .next()?
+        .parse::<u16>()
+        .ok()?
+        .try_into()
+        .unwrap_or(u8::MAX);
+    let mirroring = match fields.next()? {
+        "H" => Some(Mirroring::Horizontal),
+        "V" => Some(Mirroring::Vertical),
+        "4" => Some(Mirroring::FourScreen),
+        "-" => None,
+        _ => return None,
+    };
+    let has_battery_ram = match fields.next()? {
+        "0" => false,
+        "1" => true,
+        _ => return None,
+    };
+    let region = match fields.next()? {
+        "NTSC" => Region::Ntsc,
+        "PAL" => Region::Pal,
+        "MULTI" => Region::MultiRegion,
+        "DENDY" => Region::Dendy,
+        _ => return None,
+    };
+    let name = fields.next()?;
+
+    Some(GameEntry {
+        crc32,
+        name,
+        mapper,
+        mirroring,
+        has_battery_ram,
+        region,
+    })
+}
+
+// A header value which disagreed with the database and was replaced.
+#[derive(Debug, PartialEq)]
+pub enum HeaderCorrection {
+    Mapper {
+        header: u8,
+        database: u8,
+    },
+    Mirroring {
+        header: Mirroring,
+        database: Mirroring,
+    },
+    BatteryRam {
+        header: bool,
+        database: bool,
+    },
+    Region {
+        header: Region,
+        database: Region,
+    },
+}
+
+impl Display for HeaderCorrection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderCorrection::Mapper { header, database } => {
+                write!(f, "mapper {} corrected to {}", header, database)
+            }
+            HeaderCorrection::Mirroring { header, database } => {
+                write!(f, "mirroring {:?} corrected to {:?}", header, database)
+            }
+            HeaderCorrection::BatteryRam { header, database } => {
+                write!(f, "battery RAM {} corrected to {}", header, database)
+            }
+            HeaderCorrection::Region { header, database } => {
+                write!(f, "region {:?} corrected to {:?}", header, database)
+            }
+        }
+    }
+}
+
+pub fn lookup(crc32: u32) -> Option<&'static GameEntry> {
+    GAMES.iter().find(|game| game.crc32 == crc32)
+}
+
+// Overwrites the header values in `metadata` with those from the database, returning
+// whichever of them were actually wrong.
+pub fn correct(metadata: &mut Metadata, game: &GameEntry) -> Vec<HeaderCorrection> {
+    let mut corrections = vec![];
+
+    if metadata.mapper != game.mapper {
+        corrections.push(HeaderCorrection::Mapper {
+            header: metadata.mapper,
+            database: game.mapper,
+        });
+        metadata.mapper = game.mapper;
+    }
+
+    // Four screen boards carry their own VRAM, which dumps can't get wrong by accident.
+    if let Some(mirroring) = game.mirroring
+        && metadata.mirroring != mirroring
+        && metadata.mirroring != Mirroring::FourScreen
+    {
+        corrections.push(HeaderCorrection::Mirroring {
+            header: metadata.mirroring,
+            database: mirroring,
+        });
+        metadata.mirroring = mirroring;
+    }
+
+    if metadata.has_battery_ram != game.has_battery_ram {
+        corrections.push(HeaderCorrection::BatteryRam {
+            header: metadata.has_battery_ram,
+            database: game.has_battery_ram,
+        });
+        metadata.has_battery_ram = game.has_battery_ram;
+    }
+
+    // A game that runs on either system can't disagree with whatever the header says.
+    if metadata.region != game.region && game.region != Region::MultiRegion {
+        corrections.push(HeaderCorrection::Region {
+            header: metadata.region,
+            database: game.region,
+        });
+        metadata.region = game.region;
+    }
+
+    corrections
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    const GAME: GameEntry = GameEntry {
+        crc32: 0x1234_5678,
+        name: "Test Game",
+        mapper: 9,
+        mirroring: Some(Mirroring::Vertical),
+        has_battery_ram: true,
+        region: Region::Pal,
+    };
+
+    #[test]
+    fn test_parse_reads_entries() {
+        let games = parse("# comment\n1234ABCD\t0\tV\t1\tPAL\tTest Game\n");
+
+        assert_eq!(
+            vec![GameEntry {
+                crc32: 0x1234_ABCD,
+                name: "Test Game",
+                mapper: 0,
+                mirroring: Some(Mirroring::Vertical),
+                has_battery_ram: true,
+                region: Region::Pal,
+            }],
+            games
+        );
+    }
+
+    #[parameterized]
+    #[case("00000001\t2\tV\t0\tNTSC\tUxROM Game")]
+    #[case("00000001\t342\tV\t0\tNTSC\tNES 2.0 Game")]
+    fn test_parse_skips_unsupported_mappers(data: &'static str) {
+        assert!(parse(data).is_empty());
+    }
+
+    #[test]
+    fn test_parse_reads_mapper_controlled_mirroring() {
+        let games = parse("00000001\t69\t-\t1\tMULTI\tFME-7 Game");
+
+        assert_eq!(None, games[0].mirroring);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_panics_given_malformed_line() {
+        parse("00000001\t0\tX\t0\tNTSC\tBad Game");
+    }
+
+    #[test]
+    fn test_database_parses() {
+        assert!(GAMES.iter().all(|game| mappers::is_supported(game.mapper)));
+    }
+
+    #[test]
+    fn test_lookup_returns_none_given_unknown_crc32() {
+        assert_eq!(None, lookup(0xDEAD_BEEF));
+    }
+
+    #[test]
+    fn test_correct_replaces_wrong_header_values() {
+        let mut metadata = Loader::parse_metadata(0b0000_0000, 0b0);
+
+        let corrections = correct(&mut metadata, &GAME);
+
+        assert_eq!(
+            vec![
+                HeaderCorrection::Mapper {
+                    header: 0,
+                    database: 9
+                },
+                HeaderCorrection::Mirroring {
+                    header: Mirroring::Horizontal,
+                    database: Mirroring::Vertical
+                },
+                HeaderCorrection::BatteryRam {
+                    header: false,
+                    database: true
+                },
+                HeaderCorrection::Region {
+                    header: Region::Ntsc,
+                    database: Region::Pal
+                },
+            ],
+            corrections
+        );
+        assert_eq!(9, metadata.mapper());
+        assert_eq!(Mirroring::Vertical, metadata.mirroring());
+        assert!(metadata.has_battery_ram());
+        assert_eq!(Region::Pal, metadata.region());
+    }
+
+    #[test]
+    fn test_correct_returns_nothing_given_matching_header() {
+        let mut metadata = Loader::parse_metadata(0b1001_0011, 0b0);
+        metadata.region = Region::Pal;
+
+        assert!(correct(&mut metadata, &GAME).is_empty());
+    }
+
+    #[test]
+    fn test_correct_keeps_four_screen_mirroring() {
+        let mut metadata = Loader::parse_metadata(0b1001_1010, 0b0);
+        metadata.region = Region::Pal;
+
+        assert!(correct(&mut metadata, &GAME).is_empty());
+        assert_eq!(Mirroring::FourScreen, metadata.mirroring());
+    }
+
+    #[test]
+    fn test_correct_keeps_header_mirroring_given_mapper_controlled_mirroring() {
+        let game = GameEntry {
+            mirroring: None,
+            ..GAME
+        };
+        let mut metadata = Loader::parse_metadata(0b1001_0010, 0b0);
+        metadata.region = Region::Pal;
+
+        assert!(correct(&mut metadata, &game).is_empty());
+        assert_eq!(Mirroring::Horizontal, metadata.mirroring());
+    }
+
+    #[parameterized]
+    #[case(Region::Ntsc)]
+    #[case(Region::Pal)]
+    fn test_correct_keeps_header_region_given_multi_region_game(region: Region) {
+        let game = GameEntry {
+            region: Region::MultiRegion,
+            ..GAME
+        };
+        let mut metadata = Loader::parse_metadata(0b1001_0011, 0b0);
+        metadata.region = region;
+
+        assert!(correct(&mut metadata, &game).is_empty());
+        assert_eq!(region, metadata.region());
+    }
+
+    #[test]
+    fn test_correction_display() {
+        let correction = HeaderCorrection::Mapper {
+            header: 0,
+            database: 9,
+        };
+
+        assert_eq!("mapper 0 corrected to 9", correction.to_string());
+    }
+}
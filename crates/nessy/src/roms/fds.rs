@@ -0,0 +1,257 @@
+// The fwNES header magic - FDS^Z
+pub const FDS_MAGIC: [u8; 4] = [0x46, 0x44, 0x53, 0x1A];
+
+// Size of the optional fwNES header in bytes.
+const HEADER_SIZE_BYTES: usize = 16;
+
+// Every side in an .fds image is stored as exactly this many bytes, padded with zeros.
+pub const SIDE_SIZE_BYTES: usize = 65500;
+
+// The disk info block which starts each side, block code 1 followed by the verification text.
+const DISK_INFO_BLOCK_START: &[u8] = b"\x01*NINTENDO-HVC*";
+
+// Sizes of the blocks stored on disk, by block code. File data blocks (code 4) are one byte
+// for the code plus the size given in the file header block before it.
+const DISK_INFO_BLOCK_SIZE: usize = 56;
+const FILE_AMOUNT_BLOCK_SIZE: usize = 2;
+const FILE_HEADER_BLOCK_SIZE: usize = 16;
+
+// Where the file size sits within a file header block.
+const FILE_HEADER_SIZE_OFFSET: usize = 13;
+
+// The .fds format strips out everything on the disk that isn't block data, but the drive
+// sees the gaps between blocks, the mark starting each one, and the CRC ending it. These
+// come from the gap lengths (in bits) the BIOS writes: 28300 before the first block, and
+// 976 between the rest.
+const LEAD_IN_GAP_BYTES: usize = 28300 / 8;
+const BLOCK_GAP_BYTES: usize = 976 / 8;
+const BLOCK_START_MARK: u8 = 0x80;
+const BLOCK_CRC_BYTES: usize = 2;
+
+// Roughly how much data fits on a side as the drive sees it, gaps and all.
+const STREAM_SIZE_BYTES: usize = 68000;
+
+// A Famicom Disk System image, either a bare .fds dump or one behind a fwNES header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskImage {
+    header: Option<Vec<u8>>,
+    sides: Vec<Vec<u8>>,
+}
+
+impl DiskImage {
+    pub fn is_disk_image(data: &[u8]) -> bool {
+        data.starts_with(&FDS_MAGIC) || data.starts_with(DISK_INFO_BLOCK_START)
+    }
+
+    pub fn load(data: &[u8]) -> Result<Self, &'static str> {
+        let (header, disk_data) = if data.starts_with(&FDS_MAGIC) {
+            let header = data
+                .get(..HEADER_SIZE_BYTES)
+                .ok_or("Truncated fwNES header in disk image.")?;
+            (Some(header.to_vec()), &data[HEADER_SIZE_BYTES..])
+        } else {
+            (None, data)
+        };
+
+        if !disk_data.starts_with(DISK_INFO_BLOCK_START) || disk_data.len() < SIDE_SIZE_BYTES {
+            return Err("No disk sides found in disk image.");
+        }
+
+        // Any trailing partial side is junk from whatever made the dump.
+        let sides = disk_data
+            .chunks_exact(SIDE_SIZE_BYTES)
+            .map(|side| side.to_vec())
+            .collect();
+
+        Ok(Self { header, sides })
+    }
+
+    pub fn sides(&self) -> &[Vec<u8>] {
+        &self.sides
+    }
+
+    // The image as it would be written back to a file, header included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = self.header.clone().unwrap_or_default();
+        data.extend(self.sides.concat());
+        data
+    }
+
+    // Rebuilds the image with the given sides, as read back from the drive.
+    pub fn with_sides(&self, sides: Vec<Vec<u8>>) -> Self {
+        Self {
+            header: self.header.clone(),
+            sides,
+        }
+    }
+
+    fn block_size(block_code: u8, file_size: usize) -> Option<usize> {
+        match block_code {
+            1 => Some(DISK_INFO_BLOCK_SIZE),
+            2 => Some(FILE_AMOUNT_BLOCK_SIZE),
+            3 => Some(FILE_HEADER_BLOCK_SIZE),
+            4 => Some(1 + file_size),
+            _ => None,
+        }
+    }
+
+    // Lays a side out the way the drive reads it, with the gaps, marks and CRCs put back.
+    // The CRCs are placeholders, as the drive never reports a CRC error.
+    pub fn side_to_stream(side: &[u8]) -> Vec<u8> {
+        let mut stream = vec![0; LEAD_IN_GAP_BYTES];
+        let mut position = 0;
+        let mut file_size = 0;
+
+        while let Some(size) = side
+            .get(position)
+            .and_then(|&block_code| Self::block_size(block_code, file_size))
+        {
+            let Some(block) = side.get(position..position + size) else {
+                break;
+            };
+
+            if block[0] == 3 {
+                file_size = u16::from_le_bytes([
+                    block[FILE_HEADER_SIZE_OFFSET],
+                    block[FILE_HEADER_SIZE_OFFSET + 1],
+                ]) as usize;
+            }
+
+            stream.push(BLOCK_START_MARK);
+            stream.extend(block);
+            stream.extend([0; BLOCK_CRC_BYTES]);
+            stream.extend([0; BLOCK_GAP_BYTES]);
+
+            position += size;
+        }
+
+        stream.resize(stream.len().max(STREAM_SIZE_BYTES), 0);
+        stream
+    }
+
+    // The reverse of `side_to_stream`, picking the blocks back out from between the gaps.
+    pub fn side_from_stream(stream: &[u8]) -> Vec<u8> {
+        let mut side = vec![];
+        let mut position = 0;
+        let mut file_size = 0;
+
+        loop {
+            while stream.get(position) == Some(&0) {
+                position += 1;
+            }
+
+            if stream.get(position) != Some(&BLOCK_START_MARK) {
+                break;
+            }
+            position += 1;
+
+            let Some(size) = stream
+                .get(position)
+                .and_then(|&block_code| Self::block_size(block_code, file_size))
+            else {
+                break;
+            };
+
+            let Some(block) = stream.get(position..position + size) else {
+                break;
+            };
+
+            if block[0] == 3 {
+                file_size = u16::from_le_bytes([
+                    block[FILE_HEADER_SIZE_OFFSET],
+                    block[FILE_HEADER_SIZE_OFFSET + 1],
+                ]) as usize;
+            }
+
+            side.extend(block);
+            position += size + BLOCK_CRC_BYTES;
+        }
+
+        side.resize(SIDE_SIZE_BYTES, 0);
+        side
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_side() -> Vec<u8> {
+        let mut side = DISK_INFO_BLOCK_START.to_vec();
+        side.resize(DISK_INFO_BLOCK_SIZE, 0x11);
+        side.extend([2, 1]);
+
+        let mut file_header = vec![3; FILE_HEADER_BLOCK_SIZE];
+        file_header[FILE_HEADER_SIZE_OFFSET] = 4;
+        file_header[FILE_HEADER_SIZE_OFFSET + 1] = 0;
+        side.extend(file_header);
+        side.extend([4, 0xAA, 0xBB, 0xCC, 0xDD]);
+
+        side.resize(SIDE_SIZE_BYTES, 0);
+        side
+    }
+
+    #[test]
+    fn test_load_reads_bare_image() {
+        let data = [create_side(), create_side()].concat();
+
+        let disk = DiskImage::load(&data).unwrap();
+
+        assert_eq!(2, disk.sides().len());
+        assert_eq!(data, disk.to_bytes());
+    }
+
+    #[test]
+    fn test_load_reads_fwnes_header() {
+        let mut data = FDS_MAGIC.to_vec();
+        data.extend([1; 12]);
+        data.extend(create_side());
+
+        let disk = DiskImage::load(&data).unwrap();
+
+        assert_eq!(1, disk.sides().len());
+        assert_eq!(create_side(), disk.sides()[0]);
+        assert_eq!(data, disk.to_bytes());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_no_sides() {
+        let result = DiskImage::load(DISK_INFO_BLOCK_START);
+
+        assert_eq!(Err("No disk sides found in disk image."), result);
+    }
+
+    #[test]
+    fn test_is_disk_image() {
+        assert!(DiskImage::is_disk_image(&create_side()));
+        assert!(DiskImage::is_disk_image(&FDS_MAGIC));
+        assert!(!DiskImage::is_disk_image(b"NES\x1A"));
+    }
+
+    #[test]
+    fn test_side_to_stream_adds_gaps_and_marks() {
+        let stream = DiskImage::side_to_stream(&create_side());
+
+        assert_eq!(STREAM_SIZE_BYTES, stream.len());
+        assert!(stream[..LEAD_IN_GAP_BYTES].iter().all(|x| x == &0));
+        assert_eq!(BLOCK_START_MARK, stream[LEAD_IN_GAP_BYTES]);
+        assert_eq!(
+            DISK_INFO_BLOCK_START,
+            &stream[LEAD_IN_GAP_BYTES + 1..][..15]
+        );
+
+        let file_amount_block =
+            LEAD_IN_GAP_BYTES + 1 + DISK_INFO_BLOCK_SIZE + BLOCK_CRC_BYTES + BLOCK_GAP_BYTES;
+        assert_eq!([BLOCK_START_MARK, 2, 1], stream[file_amount_block..][..3]);
+    }
+
+    #[test]
+    fn test_side_from_stream_round_trips() {
+        let side = create_side();
+
+        assert_eq!(
+            side,
+            DiskImage::side_from_stream(&DiskImage::side_to_stream(&side))
+        );
+    }
+}
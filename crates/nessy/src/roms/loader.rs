@@ -1,4 +1,7 @@
-use crate::roms::{ROM, mirroring::Mirroring};
+use crate::roms::{
+    ROM, crc32::crc32, database, fds::DiskImage, mappers, mirroring::Mirroring, nsf::Nsf,
+    region::Region, unif,
+};
 
 // The NES magic - NES^Z
 const NES_MAGIC: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
@@ -9,20 +12,62 @@ const PRG_ROM_BLOCK_SIZE_KB: usize = 16384;
 // Size of an 8KB block for CHR ROM data.
 const CHR_ROM_BLOCK_SIZE_KB: usize = 8192;
 
+// Size of the CHR RAM given to boards with no CHR ROM, unless the header says otherwise.
+pub(super) const CHR_RAM_DEFAULT_SIZE_BYTES: usize = 8192;
+
+// The header version for NES 2.0, as read from F7.
+const NES_2_0_VERSION: u8 = 2;
+
 // Size of the header in bytes.
 const HEADER_SIZE_BYTES: usize = 16;
 
 // Size of the trainer (if present), in bytes.
 const TRAINER_SIZE_BYTES: usize = 512;
 
-// The collective info gleaned from F6 and F7 of the header bytes.
+// The collective info gleaned from F6 and F7 of the header bytes, plus the TV region.
 #[derive(Debug, PartialEq)]
 pub struct Metadata {
-    mapper: u8,
-    mirroring: Mirroring,
+    pub(super) mapper: u8,
+    pub(super) mirroring: Mirroring,
     has_trainer: bool,
-    has_battery_ram: bool,
+    pub(super) has_battery_ram: bool,
     version: u8,
+    pub(super) region: Region,
+}
+
+impl Metadata {
+    pub fn mapper(&self) -> u8 {
+        self.mapper
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    // For formats without iNES flag bytes to parse, such as UNIF.
+    pub(super) fn new(
+        mapper: u8,
+        mirroring: Mirroring,
+        has_battery_ram: bool,
+        region: Region,
+    ) -> Self {
+        Self {
+            mapper,
+            mirroring,
+            has_trainer: false,
+            has_battery_ram,
+            version: 0,
+            region,
+        }
+    }
+
+    pub fn has_battery_ram(&self) -> bool {
+        self.has_battery_ram
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
 }
 
 pub struct Loader {}
@@ -56,23 +101,61 @@ impl Loader {
             has_trainer,
             has_battery_ram,
             version,
+            // Neither flag byte carries the region, so assume NTSC until told otherwise.
+            region: Region::Ntsc,
         }
     }
 
+    // Loads either an iNES or UNIF ROM, going by the magic at the start of the file.
     pub fn load(data: &[u8]) -> Result<ROM, &str> {
-        if data[0..4] != NES_MAGIC {
+        if data.starts_with(&unif::UNIF_MAGIC) {
+            return unif::load(data);
+        }
+
+        // Disks can't run without the BIOS, so they're loaded into an `Fds` cartridge instead.
+        if DiskImage::is_disk_image(data) {
+            return Err("Disk images need loading with the FDS BIOS.");
+        }
+
+        if Nsf::is_nsf(data) {
+            return Err("NSF files are music, and need loading with the NSF player.");
+        }
+
+        if !data.starts_with(&NES_MAGIC) {
             return Err("Incorrect NES magic in ROM.");
         }
 
+        if data.len() < HEADER_SIZE_BYTES {
+            return Err("ROM is shorter than its header says.");
+        }
+
         let prg_rom_length = data[4] as usize * PRG_ROM_BLOCK_SIZE_KB;
         let chr_rom_length = data[5] as usize * CHR_ROM_BLOCK_SIZE_KB;
 
-        let metadata = Self::parse_metadata(data[6], data[7]);
+        // Every board maps PRG ROM over the vectors, so there's nothing to run without it.
+        if prg_rom_length == 0 {
+            return Err("No PRG ROM in ROM.");
+        }
+
+        let mut metadata = Self::parse_metadata(data[6], data[7]);
 
-        if metadata.version > 1 {
-            return Err("Cannot load the iNES version 2. Please use iNES version 1.");
+        if metadata.version > NES_2_0_VERSION {
+            return Err("Unknown iNES version. Please use iNES version 1 or NES 2.0.");
         }
 
+        let is_nes_2_0 = metadata.version == NES_2_0_VERSION;
+
+        // NES 2.0 puts the upper bits of the ROM sizes in F9, which only matters past 4MB.
+        if is_nes_2_0 && data[9] != 0 {
+            return Err("Unsupported ROM size in ROM.");
+        }
+
+        metadata.region = if is_nes_2_0 {
+            Region::from_nes_2_0(data[12])
+        } else {
+            Region::from_ines(data[9])
+        };
+
         let prg_rom_trainer_offset = if metadata.has_trainer {
             TRAINER_SIZE_BYTES
         } else {
@@ -85,15 +168,60 @@ impl Loader {
         let chr_rom_start = prg_rom_start + prg_rom_length;
         let chr_rom_end = chr_rom_start + chr_rom_length;
 
+        // Headers from old dumps are often wrong, so known games get their values from the
+        // database instead.
+        let corrections = data
+            .get(prg_rom_start..chr_rom_end)
+            .and_then(|rom_data| database::lookup(crc32(rom_data)))
+            .map(|game| database::correct(&mut metadata, game))
+            .unwrap_or_default();
+
+        // NES 2.0 extends the mapper number past 255 with the lower bits of F8.
+        if !mappers::is_supported(metadata.mapper) || (is_nes_2_0 && data[8] & 0x0F != 0) {
+            return Err("Unsupported mapper in ROM.");
+        }
+
+        if data.len() < chr_rom_end {
+            return Err("ROM is shorter than its header says.");
+        }
+
         let prg_rom = data[prg_rom_start..prg_rom_end].to_vec();
-        let chr_rom = data[chr_rom_start..chr_rom_end].to_vec();
+        let has_chr_ram = chr_rom_length == 0;
+
+        let chr_rom = if has_chr_ram {
+            vec![0; Self::chr_ram_size(data, is_nes_2_0)]
+        } else {
+            data[chr_rom_start..chr_rom_end].to_vec()
+        };
 
         Ok(ROM {
             metadata,
             prg_rom,
             chr_rom,
+            has_chr_ram,
+            corrections,
         })
     }
+
+    // NES 2.0 declares the CHR RAM size in F11 as a shift count, where the lower nibble is
+    // volatile RAM and the upper nibble battery-backed. Either way it's 64 << n bytes.
+    fn chr_ram_size(data: &[u8], is_nes_2_0: bool) -> usize {
+        let declared_size = if is_nes_2_0 {
+            [data[11] & 0x0F, data[11] >> 4]
+                .into_iter()
+                .filter(|&shift| shift != 0)
+                .map(|shift| 64 << shift)
+                .sum()
+        } else {
+            0
+        };
+
+        if declared_size == 0 {
+            CHR_RAM_DEFAULT_SIZE_BYTES
+        } else {
+            declared_size
+        }
+    }
 }
 
 #[cfg(test)]
@@ -101,10 +229,44 @@ mod test {
     use sif::parameterized;
 
     use crate::roms::{
-        loader::{CHR_ROM_BLOCK_SIZE_KB, Loader, Metadata, NES_MAGIC, PRG_ROM_BLOCK_SIZE_KB},
+        loader::{
+            CHR_RAM_DEFAULT_SIZE_BYTES, CHR_ROM_BLOCK_SIZE_KB, Loader, Metadata, NES_MAGIC,
+            PRG_ROM_BLOCK_SIZE_KB,
+        },
         mirroring::Mirroring,
+        region::Region,
+        unif::UNIF_MAGIC,
     };
 
+    #[test]
+    fn test_load_detects_unif_magic() {
+        let mut rom_data = UNIF_MAGIC.to_vec();
+        rom_data.extend([0; 28]);
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "No board name in UNIF ROM."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_disk_image() {
+        let rom_data = [0x46, 0x44, 0x53, 0x1A, 0x01];
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "Disk images need loading with the FDS BIOS."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_nsf() {
+        let rom_data = [0x4E, 0x45, 0x53, 0x4D, 0x1A];
+
+        let result = Loader::load(&rom_data);
+        assert!(
+            result.is_err_and(
+                |err| err == "NSF files are music, and need loading with the NSF player."
+            )
+        );
+    }
+
     #[test]
     fn test_load_returns_error_given_invalid_magic() {
         let rom_data = vec![0x55, 0x55, 0xAA, 0xAA];
@@ -156,6 +318,7 @@ mod test {
             has_trainer: false,
             has_battery_ram: false,
             version: 1,
+            region: Region::Ntsc,
         };
 
         let result = Loader::load(&rom_data);
@@ -171,6 +334,104 @@ mod test {
 
         assert!(rom.prg_rom.iter().all(|x| x == &0xAA));
         assert_eq!(CHR_ROM_BLOCK_SIZE_KB, rom.chr_rom.len());
+        assert!(!rom.has_character_ram());
+    }
+
+    fn create_chr_ram_rom_data(flag_byte_7: u8, chr_ram_byte: u8) -> Vec<u8> {
+        let mut rom_data = Vec::<u8>::new();
+        rom_data.extend(NES_MAGIC);
+        rom_data.extend([1, 0, 0, flag_byte_7, 0, 0, 0, chr_ram_byte, 0, 0, 0, 0]);
+        rom_data.extend(vec![0xAA; PRG_ROM_BLOCK_SIZE_KB]);
+        rom_data
+    }
+
+    #[parameterized]
+    #[case(0b0000_0000, 0x00, CHR_RAM_DEFAULT_SIZE_BYTES)]
+    #[case(0b0000_0000, 0x09, CHR_RAM_DEFAULT_SIZE_BYTES)]
+    #[case(0b0000_1000, 0x00, CHR_RAM_DEFAULT_SIZE_BYTES)]
+    #[case(0b0000_1000, 0x07, 0x2000)]
+    #[case(0b0000_1000, 0x09, 0x8000)]
+    #[case(0b0000_1000, 0x70, 0x2000)]
+    fn test_load_allocates_chr_ram_given_no_chr_rom(
+        flag_byte_7: u8,
+        chr_ram_byte: u8,
+        expected_size: usize,
+    ) {
+        let rom = Loader::load(&create_chr_ram_rom_data(flag_byte_7, chr_ram_byte)).unwrap();
+
+        assert!(rom.has_character_ram());
+        assert_eq!(expected_size, rom.chr_rom.len());
+        assert!(rom.chr_rom.iter().all(|x| x == &0x00));
+    }
+
+    #[parameterized]
+    #[case(0b0000_0000, 9, 0x01, Region::Pal)]
+    #[case(0b0000_1000, 12, 0x01, Region::Pal)]
+    #[case(0b0000_1000, 12, 0x03, Region::Dendy)]
+    fn test_load_sets_region(flag_byte_7: u8, index: usize, value: u8, expected: Region) {
+        let mut rom_data = create_chr_ram_rom_data(flag_byte_7, 0x00);
+        rom_data[index] = value;
+
+        let rom = Loader::load(&rom_data).unwrap();
+
+        assert_eq!(expected, rom.metadata().region());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_unknown_version() {
+        let rom_data = create_chr_ram_rom_data(0b0000_1100, 0x00);
+
+        let result = Loader::load(&rom_data);
+        assert!(
+            result.is_err_and(
+                |err| err == "Unknown iNES version. Please use iNES version 1 or NES 2.0."
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_returns_error_given_nes_2_0_extended_mapper() {
+        let mut rom_data = create_chr_ram_rom_data(0b0000_1000, 0x07);
+        rom_data[8] = 0x01;
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "Unsupported mapper in ROM."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_unsupported_mapper() {
+        let mut rom_data = Vec::<u8>::new();
+        rom_data.extend(NES_MAGIC);
+        rom_data.extend([1, 1, 0b1111_0000, 0b1111_0000, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "Unsupported mapper in ROM."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_no_prg_rom() {
+        let mut rom_data = create_chr_ram_rom_data(0b0000_0000, 0x00);
+        rom_data[4] = 0;
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "No PRG ROM in ROM."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_truncated_rom() {
+        let mut rom_data = create_chr_ram_rom_data(0b0000_0000, 0x00);
+        rom_data[4] = 2;
+
+        let result = Loader::load(&rom_data);
+        assert!(result.is_err_and(|err| err == "ROM is shorter than its header says."));
+    }
+
+    #[parameterized]
+    #[case(&[0x4E, 0x45], "Incorrect NES magic in ROM.")]
+    #[case(&[0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x00, 0x00], "ROM is shorter than its header says.")]
+    fn test_load_returns_error_given_truncated_header(rom_data: &[u8], expected: &str) {
+        let result = Loader::load(rom_data);
+        assert!(result.is_err_and(|err| err == expected));
     }
 
     #[test]
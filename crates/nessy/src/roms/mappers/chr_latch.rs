@@ -0,0 +1,133 @@
+// The CHR latches used by MMC2 and MMC4.
+//
+// Each 4KB pattern table has two CHR bank registers, one used while its latch holds $FD and
+// one while it holds $FE. The latch is flipped by the PPU fetching the tiles $FD or $FE
+// themselves, which lets a game switch banks partway down the screen without an IRQ.
+// The switch happens after the triggering fetch, so the tile itself comes from the old bank.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LatchState {
+    FD,
+    FE,
+}
+
+// The range of addresses in a pattern table which trigger the latch.
+#[derive(Debug)]
+pub struct LatchTrigger {
+    pub fd_start: u16,
+    pub fd_end: u16,
+    pub fe_start: u16,
+    pub fe_end: u16,
+}
+
+#[derive(Debug)]
+pub struct ChrLatch {
+    // The CHR banks for $0000 and $1000, indexed by the latch state.
+    fd_banks: [u8; 2],
+    fe_banks: [u8; 2],
+    latches: [LatchState; 2],
+    triggers: [LatchTrigger; 2],
+}
+
+impl ChrLatch {
+    pub fn new(triggers: [LatchTrigger; 2]) -> Self {
+        Self {
+            fd_banks: [0; 2],
+            fe_banks: [0; 2],
+            latches: [LatchState::FE; 2],
+            triggers,
+        }
+    }
+
+    pub fn set_bank(&mut self, pattern_table: usize, latch_state: LatchState, bank: u8) {
+        match latch_state {
+            LatchState::FD => self.fd_banks[pattern_table] = bank,
+            LatchState::FE => self.fe_banks[pattern_table] = bank,
+        }
+    }
+
+    pub fn latch(&self, pattern_table: usize) -> LatchState {
+        self.latches[pattern_table]
+    }
+
+    // Returns the 4KB CHR bank currently mapped for the pattern table holding `address`.
+    pub fn bank(&self, address: u16) -> u8 {
+        let pattern_table = Self::pattern_table(address);
+
+        match self.latches[pattern_table] {
+            LatchState::FD => self.fd_banks[pattern_table],
+            LatchState::FE => self.fe_banks[pattern_table],
+        }
+    }
+
+    // Watches a PPU fetch, flipping the latch if it landed on one of the trigger tiles.
+    pub fn observe(&mut self, address: u16) {
+        let pattern_table = Self::pattern_table(address);
+        let trigger = &self.triggers[pattern_table];
+
+        if (trigger.fd_start..=trigger.fd_end).contains(&address) {
+            self.latches[pattern_table] = LatchState::FD;
+        } else if (trigger.fe_start..=trigger.fe_end).contains(&address) {
+            self.latches[pattern_table] = LatchState::FE;
+        }
+    }
+
+    fn pattern_table(address: u16) -> usize {
+        ((address >> 12) & 0x1) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    fn create_latch() -> ChrLatch {
+        let mut chr_latch = ChrLatch::new([
+            LatchTrigger {
+                fd_start: 0x0FD8,
+                fd_end: 0x0FD8,
+                fe_start: 0x0FE8,
+                fe_end: 0x0FE8,
+            },
+            LatchTrigger {
+                fd_start: 0x1FD8,
+                fd_end: 0x1FDF,
+                fe_start: 0x1FE8,
+                fe_end: 0x1FEF,
+            },
+        ]);
+
+        chr_latch.set_bank(0, LatchState::FD, 1);
+        chr_latch.set_bank(0, LatchState::FE, 2);
+        chr_latch.set_bank(1, LatchState::FD, 3);
+        chr_latch.set_bank(1, LatchState::FE, 4);
+
+        chr_latch
+    }
+
+    #[parameterized]
+    #[case(0x0FD8, 0x0000, 1)]
+    #[case(0x0FD9, 0x0000, 2)]
+    #[case(0x1FDF, 0x1000, 3)]
+    #[case(0x1FEF, 0x1000, 4)]
+    #[case(0x1FD8, 0x0000, 2)]
+    fn test_observe_switches_bank(fetch_address: u16, address: u16, expected_bank: u8) {
+        let mut chr_latch = create_latch();
+
+        chr_latch.observe(0x1FD8);
+        chr_latch.observe(0x1FE8);
+        chr_latch.observe(fetch_address);
+
+        assert_eq!(expected_bank, chr_latch.bank(address));
+    }
+
+    #[test]
+    fn test_latches_start_on_fe() {
+        let chr_latch = create_latch();
+
+        assert_eq!(LatchState::FE, chr_latch.latch(0));
+        assert_eq!(LatchState::FE, chr_latch.latch(1));
+    }
+}
@@ -0,0 +1,698 @@
+use std::cell::Cell;
+
+use crate::{
+    audio::{ExpansionAudio, fds::FdsAudio},
+    roms::{
+        fds::DiskImage,
+        mappers::{Mapper, PATTERN_TABLE_END, PRG_RAM_START},
+        mirroring::Mirroring,
+        patches::ips,
+    },
+};
+
+const BIOS_SIZE: usize = 0x2000;
+const BIOS_START: u16 = 0xE000;
+const PRG_RAM_SIZE: usize = 0x8000;
+const PRG_RAM_END: u16 = 0xDFFF;
+const CHR_RAM_SIZE: usize = 0x2000;
+
+// Registers, as seen from the CPU.
+const IRQ_RELOAD_LO: u16 = 0x4020;
+const IRQ_RELOAD_HI: u16 = 0x4021;
+const IRQ_CONTROL: u16 = 0x4022;
+const MASTER_IO_ENABLE: u16 = 0x4023;
+const WRITE_DATA: u16 = 0x4024;
+const DRIVE_CONTROL: u16 = 0x4025;
+const EXTERNAL_CONNECTOR_OUTPUT: u16 = 0x4026;
+const DISK_STATUS: u16 = 0x4030;
+const READ_DATA: u16 = 0x4031;
+const DRIVE_STATUS: u16 = 0x4032;
+const EXTERNAL_CONNECTOR_INPUT: u16 = 0x4033;
+const AUDIO_START: u16 = 0x4040;
+const AUDIO_END: u16 = 0x4097;
+
+const IRQ_REPEAT: u8 = 0b01;
+const IRQ_ENABLE: u8 = 0b10;
+
+const DISK_IO_ENABLE: u8 = 0b01;
+const SOUND_IO_ENABLE: u8 = 0b10;
+
+const MOTOR_ON: u8 = 0b0000_0001;
+const TRANSFER_RESET: u8 = 0b0000_0010;
+const READ_MODE: u8 = 0b0000_0100;
+const HORIZONTAL_MIRRORING: u8 = 0b0000_1000;
+const CRC_CONTROL: u8 = 0b0001_0000;
+const DISK_READY: u8 = 0b0100_0000;
+const DISK_IRQ_ENABLE: u8 = 0b1000_0000;
+
+const STATUS_TIMER_IRQ: u8 = 0b0000_0001;
+const STATUS_TRANSFER_COMPLETE: u8 = 0b0000_0010;
+const STATUS_END_OF_HEAD: u8 = 0b0100_0000;
+
+const DRIVE_STATUS_NO_DISK: u8 = 0b0000_0001;
+const DRIVE_STATUS_NOT_READY: u8 = 0b0000_0010;
+const DRIVE_STATUS_WRITE_PROTECTED: u8 = 0b0000_0100;
+
+// Bit 7 of $4033 reports the battery is good, which it always is here.
+const BATTERY_GOOD: u8 = 0b1000_0000;
+
+// How long the head takes to get back to the start of the disk, and to move past a byte,
+// in CPU cycles. A byte every 150 cycles is the drive's ~96kbit/s.
+const HEAD_RETURN_CYCLES: u32 = 50000;
+const BYTE_CYCLES: u32 = 150;
+
+// The CRC the drive calculates over each block, CRC-16/KERMIT.
+const CRC_POLYNOMIAL: u16 = 0x8408;
+
+// The Famicom Disk System's RAM adapter, which sits in the cartridge slot.
+//
+// The BIOS (supplied by the user, as it's copyrighted) lives at $E000, with 32KB of PRG RAM
+// below it for games to be loaded into, and 8KB of CHR RAM. The drive is a stream of bytes
+// going past the head, which the BIOS reads and writes through $4024/$4031, with an IRQ
+// (or status flag) for every byte transferred. There's also a cycle-counting timer IRQ,
+// and a wavetable sound channel.
+#[derive(Debug)]
+pub struct Fds {
+    bios: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr_ram: Vec<u8>,
+    disk: DiskImage,
+    // Each side as it passes the head, gaps and all. Writes land here.
+    streams: Vec<Vec<u8>>,
+    inserted_side: Option<usize>,
+    next_side: usize,
+    io_enable: u8,
+    irq_reload: u16,
+    irq_counter: u16,
+    irq_control: u8,
+    timer_irq: Cell<bool>,
+    drive_control: u8,
+    write_data: u8,
+    read_data: u8,
+    transfer_complete: Cell<bool>,
+    disk_irq: Cell<bool>,
+    head_position: usize,
+    delay: u32,
+    end_of_head: bool,
+    scanning: bool,
+    gap_ended: bool,
+    crc: u16,
+    previous_crc_control: bool,
+    external_connector: u8,
+    audio: FdsAudio,
+}
+
+impl Fds {
+    pub fn new(bios: Vec<u8>, disk: DiskImage) -> Result<Self, &'static str> {
+        if bios.len() != BIOS_SIZE {
+            return Err("The FDS BIOS must be 8KB.");
+        }
+
+        let streams = disk
+            .sides()
+            .iter()
+            .map(|side| DiskImage::side_to_stream(side))
+            .collect();
+
+        Ok(Self {
+            bios,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            chr_ram: vec![0; CHR_RAM_SIZE],
+            disk,
+            streams,
+            inserted_side: Some(0),
+            next_side: 1,
+            io_enable: 0,
+            irq_reload: 0,
+            irq_counter: 0,
+            irq_control: 0,
+            timer_irq: Cell::new(false),
+            drive_control: 0,
+            write_data: 0,
+            read_data: 0,
+            transfer_complete: Cell::new(false),
+            disk_irq: Cell::new(false),
+            head_position: 0,
+            delay: 0,
+            end_of_head: true,
+            scanning: false,
+            gap_ended: false,
+            crc: 0,
+            previous_crc_control: false,
+            external_connector: 0,
+            audio: FdsAudio::default(),
+        })
+    }
+
+    // Puts back the writes from a previous session, as saved by `save_data`.
+    pub fn with_disk_diff(mut self, diff: &[u8]) -> Result<Self, &'static str> {
+        let written = DiskImage::load(&ips::apply(&self.disk.to_bytes(), diff)?)?;
+
+        if written.sides().len() != self.streams.len() {
+            return Err("Disk diff doesn't match the disk image.");
+        }
+
+        self.streams = written
+            .sides()
+            .iter()
+            .map(|side| DiskImage::side_to_stream(side))
+            .collect();
+
+        Ok(self)
+    }
+
+    // Swaps which side is in the drive, with `None` ejecting the disk.
+    pub fn insert_side(&mut self, side: Option<usize>) {
+        self.inserted_side = side.filter(|&side| side < self.streams.len());
+    }
+
+    pub fn inserted_side(&self) -> Option<usize> {
+        self.inserted_side
+    }
+
+    fn disk_io_enabled(&self) -> bool {
+        self.io_enable & DISK_IO_ENABLE != 0
+    }
+
+    // $4030, which acknowledges both IRQs and the transfer when it's read.
+    fn disk_status(&self) -> u8 {
+        let mut status = 0;
+
+        if self.timer_irq.get() {
+            status |= STATUS_TIMER_IRQ;
+        }
+
+        if self.transfer_complete.get() {
+            status |= STATUS_TRANSFER_COMPLETE;
+        }
+
+        if self.end_of_head {
+            status |= STATUS_END_OF_HEAD;
+        }
+
+        status
+    }
+
+    fn update_crc(&mut self, data: u8) {
+        for bit in 0..8 {
+            let carry = self.crc & 1 != 0;
+            self.crc >>= 1;
+
+            if carry {
+                self.crc ^= CRC_POLYNOMIAL;
+            }
+
+            if data & (1 << bit) != 0 {
+                self.crc ^= 0x8000;
+            }
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if self.irq_control & IRQ_ENABLE == 0 || !self.disk_io_enabled() {
+            return;
+        }
+
+        if self.irq_counter == 0 {
+            self.timer_irq.set(true);
+            self.irq_counter = self.irq_reload;
+
+            if self.irq_control & IRQ_REPEAT == 0 {
+                self.irq_control &= !IRQ_ENABLE;
+            }
+        } else {
+            self.irq_counter -= 1;
+        }
+    }
+
+    fn clock_drive(&mut self) {
+        let Some(side) = self.inserted_side else {
+            self.end_of_head = true;
+            self.scanning = false;
+            return;
+        };
+
+        if self.drive_control & MOTOR_ON == 0 {
+            self.end_of_head = true;
+            self.scanning = false;
+            return;
+        }
+
+        if self.drive_control & TRANSFER_RESET != 0 && !self.scanning {
+            return;
+        }
+
+        if self.end_of_head {
+            self.delay = HEAD_RETURN_CYCLES;
+            self.end_of_head = false;
+            self.head_position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        self.scanning = true;
+
+        let disk_ready = self.drive_control & DISK_READY != 0;
+        let crc_control = self.drive_control & CRC_CONTROL != 0;
+        let mut raise_irq = self.drive_control & DISK_IRQ_ENABLE != 0;
+
+        if self.drive_control & READ_MODE != 0 {
+            let data = self.streams[side][self.head_position];
+
+            if !self.previous_crc_control {
+                self.update_crc(data);
+            }
+
+            if !disk_ready {
+                self.gap_ended = false;
+                self.crc = 0;
+            } else if data != 0 && !self.gap_ended {
+                // The block start mark is swallowed, rather than interrupting for it.
+                self.gap_ended = true;
+                raise_irq = false;
+            }
+
+            if self.gap_ended {
+                self.transfer_complete.set(true);
+                self.read_data = data;
+
+                if raise_irq {
+                    self.disk_irq.set(true);
+                }
+            }
+        } else {
+            let mut data = 0;
+
+            if !crc_control {
+                self.transfer_complete.set(true);
+                data = self.write_data;
+
+                if raise_irq {
+                    self.disk_irq.set(true);
+                }
+            }
+
+            if !disk_ready {
+                data = 0;
+            }
+
+            if !crc_control {
+                self.update_crc(data);
+            } else {
+                if !self.previous_crc_control {
+                    self.update_crc(0);
+                    self.update_crc(0);
+                }
+
+                data = self.crc as u8;
+                self.crc >>= 8;
+            }
+
+            self.streams[side][self.head_position] = data;
+            self.gap_ended = false;
+        }
+
+        self.previous_crc_control = crc_control;
+        self.head_position += 1;
+
+        if self.head_position >= self.streams[side].len() {
+            self.drive_control &= !MOTOR_ON;
+        } else {
+            self.delay = BYTE_CYCLES;
+        }
+    }
+}
+
+impl Mapper for Fds {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            DISK_STATUS if self.disk_io_enabled() => {
+                let status = self.disk_status();
+
+                self.timer_irq.set(false);
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+                status
+            }
+            READ_DATA if self.disk_io_enabled() => {
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+                self.read_data
+            }
+            DRIVE_STATUS if self.disk_io_enabled() => match self.inserted_side {
+                None => {
+                    DRIVE_STATUS_NO_DISK | DRIVE_STATUS_NOT_READY | DRIVE_STATUS_WRITE_PROTECTED
+                }
+                Some(_) if !self.scanning => DRIVE_STATUS_NOT_READY,
+                Some(_) => 0,
+            },
+            EXTERNAL_CONNECTOR_INPUT if self.disk_io_enabled() => {
+                BATTERY_GOOD | (self.external_connector & 0x7F)
+            }
+            AUDIO_START..=AUDIO_END if self.io_enable & SOUND_IO_ENABLE != 0 => {
+                self.audio.read(address)
+            }
+            PRG_RAM_START..=PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
+            BIOS_START.. => self.bios[(address - BIOS_START) as usize],
+            _ => 0,
+        }
+    }
+
+    fn cpu_peek(&self, address: u16) -> u8 {
+        match address {
+            DISK_STATUS if self.disk_io_enabled() => self.disk_status(),
+            READ_DATA if self.disk_io_enabled() => self.read_data,
+            _ => self.cpu_read(address),
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            MASTER_IO_ENABLE => {
+                self.io_enable = data;
+
+                if !self.disk_io_enabled() {
+                    self.irq_control &= !IRQ_ENABLE;
+                    self.timer_irq.set(false);
+                    self.disk_irq.set(false);
+                }
+            }
+            IRQ_RELOAD_LO if self.disk_io_enabled() => {
+                self.irq_reload = (self.irq_reload & 0xFF00) | data as u16;
+            }
+            IRQ_RELOAD_HI if self.disk_io_enabled() => {
+                self.irq_reload = (self.irq_reload & 0x00FF) | ((data as u16) << 8);
+            }
+            IRQ_CONTROL if self.disk_io_enabled() => {
+                self.irq_control = data;
+
+                if data & IRQ_ENABLE != 0 {
+                    self.irq_counter = self.irq_reload;
+                } else {
+                    self.timer_irq.set(false);
+                }
+            }
+            WRITE_DATA if self.disk_io_enabled() => {
+                self.write_data = data;
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+            }
+            DRIVE_CONTROL if self.disk_io_enabled() => {
+                self.drive_control = data;
+                self.disk_irq.set(false);
+            }
+            EXTERNAL_CONNECTOR_OUTPUT if self.disk_io_enabled() => {
+                self.external_connector = data;
+            }
+            AUDIO_START..=AUDIO_END if self.io_enable & SOUND_IO_ENABLE != 0 => {
+                self.audio.write(address, data);
+            }
+            PRG_RAM_START..=PRG_RAM_END => {
+                self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.chr_ram[(address & PATTERN_TABLE_END) as usize]
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.chr_ram[(address & PATTERN_TABLE_END) as usize] = data;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.drive_control & HORIZONTAL_MIRRORING != 0 {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        }
+    }
+
+    fn clock(&mut self) {
+        self.audio.clock();
+        self.clock_timer();
+
+        if self.disk_io_enabled() {
+            self.clock_drive();
+        }
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.timer_irq.get() || self.disk_irq.get()
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output()
+    }
+
+    // Press once to eject the disk, and again to insert the next side.
+    fn switch_disk_side(&mut self) {
+        match self.inserted_side {
+            Some(side) => {
+                self.next_side = (side + 1) % self.streams.len();
+                self.insert_side(None);
+            }
+            None => self.insert_side(Some(self.next_side)),
+        }
+    }
+
+    // An IPS patch of everything written to the disk, against the original image, so the
+    // image itself is never touched. `None` if nothing has been written.
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let sides = self
+            .streams
+            .iter()
+            .map(|stream| DiskImage::side_from_stream(stream))
+            .collect();
+
+        let written = self.disk.with_sides(sides);
+
+        if written == self.disk {
+            return None;
+        }
+
+        ips::create(&self.disk.to_bytes(), &written.to_bytes()).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+
+    use super::*;
+    use crate::roms::fds::SIDE_SIZE_BYTES;
+
+    fn create_disk(sides: usize) -> DiskImage {
+        let mut side = b"\x01*NINTENDO-HVC*".to_vec();
+        side.resize(56, 0x11);
+        side.extend([2, 0]);
+        side.resize(SIDE_SIZE_BYTES, 0);
+
+        DiskImage::load(&side.repeat(sides)).unwrap()
+    }
+
+    fn create_fds() -> Fds {
+        let mut bios = vec![0; BIOS_SIZE];
+        bios[BIOS_SIZE - 4] = 0x24;
+
+        let mut fds = Fds::new(bios, create_disk(2)).unwrap();
+        fds.cpu_write(MASTER_IO_ENABLE, DISK_IO_ENABLE | SOUND_IO_ENABLE);
+        fds
+    }
+
+    fn clock(fds: &mut Fds, cycles: u32) {
+        for _ in 0..cycles {
+            fds.clock();
+        }
+    }
+
+    // Runs the drive until the next byte has been transferred.
+    fn next_byte(fds: &mut Fds) -> u8 {
+        while !fds.transfer_complete.get() {
+            fds.clock();
+        }
+
+        fds.cpu_read(READ_DATA)
+    }
+
+    #[test]
+    fn test_new_returns_error_given_wrong_bios_size() {
+        assert!(
+            Fds::new(vec![0; 0x1000], create_disk(1))
+                .is_err_and(|err| err == "The FDS BIOS must be 8KB.")
+        );
+    }
+
+    #[test]
+    fn test_cpu_read_maps_bios_and_prg_ram() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(0x6000, 0xAA);
+        fds.cpu_write(0xDFFF, 0xBB);
+        fds.cpu_write(0xFFFC, 0xCC);
+
+        assert_eq_hex!(0xAA, fds.cpu_read(0x6000));
+        assert_eq_hex!(0xBB, fds.cpu_read(0xDFFF));
+        assert_eq_hex!(0x24, fds.cpu_read(0xFFFC));
+    }
+
+    #[test]
+    fn test_ppu_write_sets_chr_ram() {
+        let mut fds = create_fds();
+
+        fds.ppu_write(0x1234, 0xAA);
+
+        assert_eq_hex!(0xAA, fds.ppu_read(0x1234));
+    }
+
+    #[test]
+    fn test_timer_irq_fires_after_reload_cycles() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(IRQ_RELOAD_LO, 0x10);
+        fds.cpu_write(IRQ_RELOAD_HI, 0x00);
+        fds.cpu_write(IRQ_CONTROL, IRQ_ENABLE);
+
+        clock(&mut fds, 0x10);
+        assert!(!fds.irq_pending());
+
+        clock(&mut fds, 1);
+        assert!(fds.irq_pending());
+
+        assert_eq!(
+            STATUS_TIMER_IRQ,
+            fds.cpu_read(DISK_STATUS) & STATUS_TIMER_IRQ
+        );
+        assert!(!fds.irq_pending());
+
+        // Without repeat, the timer stops after firing once.
+        clock(&mut fds, 0x100);
+        assert!(!fds.irq_pending());
+    }
+
+    #[test]
+    fn test_peek_leaves_timer_irq_pending() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(IRQ_RELOAD_LO, 0x04);
+        fds.cpu_write(IRQ_CONTROL, IRQ_ENABLE);
+
+        clock(&mut fds, 5);
+
+        assert_eq!(
+            STATUS_TIMER_IRQ,
+            fds.cpu_peek(DISK_STATUS) & STATUS_TIMER_IRQ
+        );
+        assert!(fds.irq_pending());
+    }
+
+    #[test]
+    fn test_timer_irq_repeats() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(IRQ_RELOAD_LO, 0x04);
+        fds.cpu_write(IRQ_CONTROL, IRQ_ENABLE | IRQ_REPEAT);
+
+        clock(&mut fds, 5);
+        fds.cpu_read(DISK_STATUS);
+
+        clock(&mut fds, 5);
+        assert!(fds.irq_pending());
+    }
+
+    #[test]
+    fn test_registers_ignored_with_disk_io_disabled() {
+        let mut fds = create_fds();
+        fds.cpu_write(MASTER_IO_ENABLE, 0);
+
+        fds.cpu_write(IRQ_CONTROL, IRQ_ENABLE);
+        clock(&mut fds, 10);
+
+        assert!(!fds.irq_pending());
+        assert_eq!(0, fds.cpu_read(DRIVE_STATUS));
+    }
+
+    #[test]
+    fn test_mirroring_follows_drive_control() {
+        let mut fds = create_fds();
+
+        assert_eq!(Mirroring::Vertical, fds.mirroring());
+
+        fds.cpu_write(DRIVE_CONTROL, HORIZONTAL_MIRRORING);
+
+        assert_eq!(Mirroring::Horizontal, fds.mirroring());
+    }
+
+    #[test]
+    fn test_drive_reads_blocks_past_gap() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(DRIVE_CONTROL, DISK_READY | READ_MODE | MOTOR_ON);
+
+        assert_eq_hex!(0x80, next_byte(&mut fds));
+        assert_eq_hex!(0x01, next_byte(&mut fds));
+        assert_eq_hex!(b'*', next_byte(&mut fds));
+        assert_eq!(0, fds.cpu_read(DRIVE_STATUS));
+    }
+
+    #[test]
+    fn test_drive_writes_are_saved_as_diff() {
+        let mut fds = create_fds();
+        assert_eq!(None, fds.save_data());
+
+        // Read up to the file amount block, then overwrite its file count.
+        fds.cpu_write(DRIVE_CONTROL, DISK_READY | READ_MODE | MOTOR_ON);
+        while next_byte(&mut fds) != 0x02 {}
+
+        fds.cpu_write(WRITE_DATA, 0x05);
+        fds.cpu_write(DRIVE_CONTROL, DISK_READY | MOTOR_ON);
+        next_byte(&mut fds);
+
+        let diff = fds.save_data().unwrap();
+
+        let restored = Fds::new(vec![0; BIOS_SIZE], create_disk(2))
+            .unwrap()
+            .with_disk_diff(&diff)
+            .unwrap();
+
+        assert_eq!(5, DiskImage::side_from_stream(&restored.streams[0])[57]);
+        assert_eq!(0, DiskImage::side_from_stream(&restored.streams[1])[57]);
+    }
+
+    #[test]
+    fn test_switch_disk_side_ejects_then_inserts_next() {
+        let mut fds = create_fds();
+
+        fds.switch_disk_side();
+        assert_eq!(None, fds.inserted_side());
+        assert_eq_hex!(
+            DRIVE_STATUS_NO_DISK | DRIVE_STATUS_NOT_READY | DRIVE_STATUS_WRITE_PROTECTED,
+            fds.cpu_read(DRIVE_STATUS)
+        );
+
+        fds.switch_disk_side();
+        assert_eq!(Some(1), fds.inserted_side());
+
+        fds.switch_disk_side();
+        fds.switch_disk_side();
+        assert_eq!(Some(0), fds.inserted_side());
+    }
+
+    #[test]
+    fn test_audio_registers_need_sound_io_enabled() {
+        let mut fds = create_fds();
+
+        fds.cpu_write(0x4080, 0x80 | 0x20);
+        assert_eq_hex!(0x20, fds.cpu_read(0x4090));
+
+        fds.cpu_write(MASTER_IO_ENABLE, DISK_IO_ENABLE);
+        assert_eq_hex!(0x00, fds.cpu_read(0x4090));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{
+    audio::{ExpansionAudio, sunsoft_5b::Sunsoft5b},
+    roms::{
+        ROM,
+        mappers::{Mapper, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, read_banked, write_banked},
+        mirroring::Mirroring,
+    },
+};
+
+pub const MAPPER_ID: u8 = 69;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// Register addresses, each covering an 8KB range.
+const COMMAND_SELECT: u16 = 0x8000;
+const PARAMETER_WRITE: u16 = 0xA000;
+const AUDIO_REGISTER_SELECT: u16 = 0xC000;
+const AUDIO_REGISTER_WRITE: u16 = 0xE000;
+
+// Commands, selected by $8000 and carried out by the next write to $A000.
+const COMMAND_CHR_BANK_7: u8 = 0x7;
+const COMMAND_PRG_BANK_6000: u8 = 0x8;
+const COMMAND_PRG_BANK_C000: u8 = 0xB;
+const COMMAND_MIRRORING: u8 = 0xC;
+const COMMAND_IRQ_CONTROL: u8 = 0xD;
+const COMMAND_IRQ_COUNTER_LO: u8 = 0xE;
+const COMMAND_IRQ_COUNTER_HI: u8 = 0xF;
+
+const PRG_RAM_ENABLE: u8 = 0b1000_0000;
+const PRG_RAM_SELECT: u8 = 0b0100_0000;
+
+const IRQ_COUNTER_ENABLE: u8 = 0b1000_0000;
+const IRQ_ENABLE: u8 = 0b0000_0001;
+
+// Sunsoft FME-7 (and the 5B, which is the same board plus expansion audio).
+//
+// Everything goes through a command/parameter pair of registers: write the command number
+// to $8000-$9FFF, then its value to $A000-$BFFF. There are four 8KB PRG windows, the one
+// at $6000 being switchable between ROM and RAM, with $E000 fixed to the last bank.
+// CHR is eight 1KB windows. The IRQ counter decrements every CPU cycle, firing when it
+// wraps from $0000 to $FFFF.
+#[derive(Debug)]
+pub struct Fme7 {
+    rom: ROM,
+    prg_ram: Vec<u8>,
+    command: u8,
+    chr_banks: [u8; 8],
+    prg_banks: [u8; 4],
+    mirroring: Mirroring,
+    irq_control: u8,
+    irq_counter: u16,
+    irq_pending: bool,
+    audio: Sunsoft5b,
+}
+
+impl Fme7 {
+    pub fn new(rom: ROM) -> Self {
+        let mirroring = rom.metadata().mirroring();
+
+        Self {
+            rom,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            command: 0,
+            chr_banks: [0; 8],
+            prg_banks: [0; 4],
+            mirroring,
+            irq_control: 0,
+            irq_counter: 0,
+            irq_pending: false,
+            audio: Sunsoft5b::default(),
+        }
+    }
+
+    fn write_parameter(&mut self, data: u8) {
+        match self.command {
+            0..=COMMAND_CHR_BANK_7 => self.chr_banks[self.command as usize] = data,
+            COMMAND_PRG_BANK_6000..=COMMAND_PRG_BANK_C000 => {
+                self.prg_banks[(self.command - COMMAND_PRG_BANK_6000) as usize] = data;
+            }
+            COMMAND_MIRRORING => {
+                self.mirroring = match data & 0b11 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            COMMAND_IRQ_CONTROL => {
+                // Any write to the control register acknowledges a pending IRQ.
+                self.irq_control = data;
+                self.irq_pending = false;
+            }
+            COMMAND_IRQ_COUNTER_LO => {
+                self.irq_counter = (self.irq_counter & 0xFF00) | data as u16;
+            }
+            COMMAND_IRQ_COUNTER_HI => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((data as u16) << 8);
+            }
+            _ => {}
+        }
+    }
+
+    fn read_prg_rom(&self, bank: u8, address: u16) -> u8 {
+        read_banked(
+            self.rom.program_rom(),
+            (bank & 0x3F) as usize,
+            PRG_BANK_SIZE,
+            address,
+        )
+    }
+}
+
+impl Mapper for Fme7 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            PRG_RAM_START..=PRG_RAM_END => {
+                let bank = self.prg_banks[0];
+
+                match (bank & PRG_RAM_SELECT != 0, bank & PRG_RAM_ENABLE != 0) {
+                    (false, _) => self.read_prg_rom(bank, address),
+                    (true, true) => self.prg_ram[(address - PRG_RAM_START) as usize],
+                    (true, false) => 0,
+                }
+            }
+            0x8000..=0xDFFF => {
+                let window = ((address - 0x6000) as usize) / PRG_BANK_SIZE;
+                self.read_prg_rom(self.prg_banks[window], address)
+            }
+            0xE000.. => {
+                let last_bank =
+                    ((self.rom.program_rom().len() / PRG_BANK_SIZE).saturating_sub(1)) as u8;
+                self.read_prg_rom(last_bank, address)
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let PRG_RAM_START..=PRG_RAM_END = address {
+            let bank = self.prg_banks[0];
+
+            if bank & PRG_RAM_SELECT != 0 && bank & PRG_RAM_ENABLE != 0 {
+                self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            }
+
+            return;
+        }
+
+        match address & 0xE000 {
+            COMMAND_SELECT => self.command = data & 0x0F,
+            PARAMETER_WRITE => self.write_parameter(data),
+            AUDIO_REGISTER_SELECT => self.audio.select_register(data),
+            AUDIO_REGISTER_WRITE => self.audio.write_register(data),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let bank = self.chr_banks[(address as usize / CHR_BANK_SIZE) & 0x7] as usize;
+
+        read_banked(self.rom.character_rom(), bank, CHR_BANK_SIZE, address)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let bank = self.chr_banks[(address as usize / CHR_BANK_SIZE) & 0x7] as usize;
+
+        if let Some(chr_ram) = self.rom.character_ram_mut() {
+            write_banked(chr_ram, bank, CHR_BANK_SIZE, address, data);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn clock(&mut self) {
+        self.audio.clock();
+
+        if self.irq_control & IRQ_COUNTER_ENABLE == 0 {
+            return;
+        }
+
+        let (counter, wrapped) = self.irq_counter.overflowing_sub(1);
+        self.irq_counter = counter;
+
+        if wrapped && self.irq_control & IRQ_ENABLE != 0 {
+            self.irq_pending = true;
+        }
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    // Fills each 8KB PRG bank and 1KB CHR bank with its own bank number.
+    fn create_fme7() -> Fme7 {
+        let prg_rom = (0..0x40000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+        let chr_rom = (0..0x40000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+
+        Fme7::new(ROM {
+            metadata: Loader::parse_metadata(0b0101_0000, 0b0100_0000),
+            prg_rom,
+            chr_rom,
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    fn command(fme7: &mut Fme7, command: u8, parameter: u8) {
+        fme7.cpu_write(COMMAND_SELECT, command);
+        fme7.cpu_write(PARAMETER_WRITE, parameter);
+    }
+
+    #[parameterized]
+    #[case(0x9, 0x8000, 0x03)]
+    #[case(0xA, 0xA000, 0x04)]
+    #[case(0xB, 0xDFFF, 0x05)]
+    #[case(0x8, 0x6000, 0x06)]
+    fn test_cpu_read_maps_prg_banks(command_number: u8, address: u16, expected_bank: u8) {
+        let mut fme7 = create_fme7();
+
+        command(&mut fme7, command_number, expected_bank);
+
+        assert_eq_hex!(expected_bank, fme7.cpu_read(address));
+    }
+
+    #[test]
+    fn test_cpu_read_handles_prg_smaller_than_a_bank() {
+        let fme7 = Fme7::new(ROM {
+            metadata: Loader::parse_metadata(0b0101_0000, 0b0100_0000),
+            prg_rom: vec![0xAB; 0x1000],
+            chr_rom: vec![0; 0x2000],
+            has_chr_ram: false,
+            corrections: vec![],
+        });
+
+        assert_eq_hex!(0xAB, fme7.cpu_read(0xE000));
+    }
+
+    #[test]
+    fn test_cpu_read_fixes_last_prg_bank() {
+        let fme7 = create_fme7();
+
+        assert_eq_hex!(0x1F, fme7.cpu_read(0xE000));
+    }
+
+    #[parameterized]
+    #[case(PRG_RAM_SELECT | PRG_RAM_ENABLE, 0xAA)]
+    #[case(PRG_RAM_SELECT, 0x00)]
+    fn test_prg_ram_is_only_accessible_when_enabled(bank: u8, expected: u8) {
+        let mut fme7 = create_fme7();
+
+        command(
+            &mut fme7,
+            COMMAND_PRG_BANK_6000,
+            PRG_RAM_SELECT | PRG_RAM_ENABLE,
+        );
+        fme7.cpu_write(0x6010, 0xAA);
+        command(&mut fme7, COMMAND_PRG_BANK_6000, bank);
+
+        assert_eq_hex!(expected, fme7.cpu_read(0x6010));
+    }
+
+    #[test]
+    fn test_ppu_read_maps_chr_banks() {
+        let mut fme7 = create_fme7();
+
+        for window in 0..8 {
+            command(&mut fme7, window, 0x10 + window);
+        }
+
+        for window in 0..8 {
+            let address = window as u16 * CHR_BANK_SIZE as u16;
+            assert_eq_hex!(0x10 + window, fme7.ppu_read(address));
+        }
+    }
+
+    #[test]
+    fn test_ppu_write_sets_banked_chr_ram() {
+        let mut fme7 = Fme7::new(ROM {
+            metadata: Loader::parse_metadata(0b0101_0000, 0b0100_0000),
+            prg_rom: vec![0; 0x40000],
+            chr_rom: vec![0; 0x2000],
+            has_chr_ram: true,
+            corrections: vec![],
+        });
+
+        command(&mut fme7, 0, 3);
+        fme7.ppu_write(0x0010, 0xAA);
+
+        assert_eq_hex!(0xAA, fme7.ppu_read(0x0010));
+        assert_eq_hex!(0xAA, fme7.rom.character_rom()[0x0C10]);
+    }
+
+    #[parameterized]
+    #[case(0, Mirroring::Vertical)]
+    #[case(1, Mirroring::Horizontal)]
+    #[case(2, Mirroring::SingleScreenLower)]
+    #[case(3, Mirroring::SingleScreenUpper)]
+    fn test_command_sets_mirroring(data: u8, expected: Mirroring) {
+        let mut fme7 = create_fme7();
+
+        command(&mut fme7, COMMAND_MIRRORING, data);
+
+        assert_eq!(expected, fme7.mirroring());
+    }
+
+    #[test]
+    fn test_irq_fires_when_counter_wraps() {
+        let mut fme7 = create_fme7();
+
+        command(&mut fme7, COMMAND_IRQ_COUNTER_LO, 0x02);
+        command(&mut fme7, COMMAND_IRQ_COUNTER_HI, 0x00);
+        command(
+            &mut fme7,
+            COMMAND_IRQ_CONTROL,
+            IRQ_COUNTER_ENABLE | IRQ_ENABLE,
+        );
+
+        fme7.clock();
+        fme7.clock();
+        assert!(!fme7.irq_pending());
+
+        fme7.clock();
+        assert!(fme7.irq_pending());
+        assert_eq_hex!(0xFFFF, fme7.irq_counter);
+
+        command(
+            &mut fme7,
+            COMMAND_IRQ_CONTROL,
+            IRQ_COUNTER_ENABLE | IRQ_ENABLE,
+        );
+        assert!(!fme7.irq_pending());
+    }
+
+    #[test]
+    fn test_irq_counter_halts_when_disabled() {
+        let mut fme7 = create_fme7();
+
+        command(&mut fme7, COMMAND_IRQ_COUNTER_LO, 0x10);
+        command(&mut fme7, COMMAND_IRQ_CONTROL, IRQ_ENABLE);
+
+        fme7.clock();
+
+        assert_eq_hex!(0x10, fme7.irq_counter);
+    }
+
+    #[test]
+    fn test_cpu_write_drives_audio() {
+        let mut fme7 = create_fme7();
+
+        // Channel A with tone and noise disabled at full volume, so it's held high.
+        fme7.cpu_write(AUDIO_REGISTER_SELECT, 0x7);
+        fme7.cpu_write(AUDIO_REGISTER_WRITE, 0b0011_1111);
+        fme7.cpu_write(AUDIO_REGISTER_SELECT, 0x8);
+        fme7.cpu_write(AUDIO_REGISTER_WRITE, 0x0F);
+
+        assert!(fme7.audio_output() > 0.0);
+    }
+}
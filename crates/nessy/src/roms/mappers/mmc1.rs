@@ -0,0 +1,285 @@
+use crate::roms::{
+    ROM,
+    mappers::{
+        Mapper, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, PRG_ROM_START, read_banked, write_banked,
+    },
+    mirroring::Mirroring,
+};
+
+pub const MAPPER_ID: u8 = 1;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+// Register addresses, each covering an 8KB range.
+const CONTROL: u16 = 0x8000;
+const CHR_BANK_0_SELECT: u16 = 0xA000;
+const CHR_BANK_1_SELECT: u16 = 0xC000;
+const PRG_BANK_SELECT: u16 = 0xE000;
+
+// The shift register starts with a marker bit, which reaches the bottom on the fifth write.
+const SHIFT_RESET: u8 = 0x10;
+
+// Resetting the shift register also puts PRG back to fixing the last bank at $C000.
+const CONTROL_RESET: u8 = 0x0C;
+
+// MMC1 (SxROM), as used by The Legend of Zelda, Metroid and a good share of test ROMs.
+//
+// Registers are loaded a bit at a time through a 5 bit shift register, with the fifth write
+// picking the register by its address. PRG is either a 32KB bank, or 16KB banks with one half
+// fixed, and CHR is either an 8KB bank or two 4KB banks. Writes on consecutive cycles should
+// be ignored, but the bus only ticks the cartridge once an instruction has finished, so they
+// aren't.
+#[derive(Debug)]
+pub struct Mmc1 {
+    rom: ROM,
+    prg_ram: Vec<u8>,
+    shift: u8,
+    control: u8,
+    chr_banks: [u8; 2],
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(rom: ROM) -> Self {
+        Self {
+            rom,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            shift: SHIFT_RESET,
+            control: CONTROL_RESET,
+            chr_banks: [0; 2],
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        self.rom.program_rom().len() / PRG_BANK_SIZE
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    fn prg_bank_at(&self, address: u16) -> usize {
+        let bank = (self.prg_bank & 0x0F) as usize;
+        let upper = address >= 0xC000;
+
+        match (self.control >> 2) & 0x3 {
+            // 32KB mode ignores the bottom bit of the bank number.
+            0 | 1 => (bank & !0x1) | upper as usize,
+            2 if upper => bank,
+            2 => 0,
+            _ if upper => self.prg_bank_count().saturating_sub(1),
+            _ => bank,
+        }
+    }
+
+    fn chr_bank_at(&self, address: u16) -> usize {
+        let upper = address >= 0x1000;
+
+        if self.control & 0x10 == 0 {
+            (self.chr_banks[0] & !0x1) as usize | upper as usize
+        } else {
+            self.chr_banks[upper as usize] as usize
+        }
+    }
+
+    fn write_register(&mut self, address: u16, data: u8) {
+        match address & 0xE000 {
+            CONTROL => self.control = data,
+            CHR_BANK_0_SELECT => self.chr_banks[0] = data,
+            CHR_BANK_1_SELECT => self.chr_banks[1] = data,
+            PRG_BANK_SELECT => self.prg_bank = data,
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled() => {
+                self.prg_ram[(address - PRG_RAM_START) as usize]
+            }
+            PRG_ROM_START.. => read_banked(
+                self.rom.program_rom(),
+                self.prg_bank_at(address),
+                PRG_BANK_SIZE,
+                address,
+            ),
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_enabled() => {
+                self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            }
+            PRG_ROM_START.. if data & 0x80 != 0 => {
+                self.shift = SHIFT_RESET;
+                self.control |= CONTROL_RESET;
+            }
+            PRG_ROM_START.. => {
+                let full = self.shift & 0x1 != 0;
+                self.shift = (self.shift >> 1) | ((data & 0x1) << 4);
+
+                if full {
+                    self.write_register(address, self.shift);
+                    self.shift = SHIFT_RESET;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        read_banked(
+            self.rom.character_rom(),
+            self.chr_bank_at(address),
+            CHR_BANK_SIZE,
+            address,
+        )
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let bank = self.chr_bank_at(address);
+
+        if let Some(chr_ram) = self.rom.character_ram_mut() {
+            write_banked(chr_ram, bank, CHR_BANK_SIZE, address, data);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x3 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    // Fills each 16KB PRG bank and 4KB CHR bank with its own bank number.
+    fn create_mmc1() -> Mmc1 {
+        let prg_rom = (0..0x40000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+        let chr_rom = (0..0x20000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+
+        Mmc1::new(ROM {
+            metadata: Loader::parse_metadata(0b0001_0000, 0b0),
+            prg_rom,
+            chr_rom,
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    // Shifts a register value in a bit at a time, the way games have to.
+    fn load_register(mmc1: &mut Mmc1, address: u16, value: u8) {
+        for bit in 0..5 {
+            mmc1.cpu_write(address, (value >> bit) & 0x1);
+        }
+    }
+
+    #[parameterized]
+    #[case(0x8000, 0x00)]
+    #[case(0xBFFF, 0x00)]
+    #[case(0xC000, 0x0F)]
+    #[case(0xFFFF, 0x0F)]
+    fn test_cpu_read_fixes_last_prg_bank_at_power_on(address: u16, expected_bank: u8) {
+        let mmc1 = create_mmc1();
+
+        assert_eq_hex!(expected_bank, mmc1.cpu_read(address));
+    }
+
+    #[parameterized]
+    #[case(0b0_0000, 0x8000, 0x04)]
+    #[case(0b0_0000, 0xC000, 0x05)]
+    #[case(0b0_1000, 0x8000, 0x00)]
+    #[case(0b0_1000, 0xC000, 0x05)]
+    #[case(0b0_1100, 0x8000, 0x05)]
+    #[case(0b0_1100, 0xC000, 0x0F)]
+    fn test_cpu_read_maps_prg_banks_by_mode(control: u8, address: u16, expected_bank: u8) {
+        let mut mmc1 = create_mmc1();
+
+        load_register(&mut mmc1, 0x8000, control);
+        load_register(&mut mmc1, 0xE000, 0x05);
+
+        assert_eq_hex!(expected_bank, mmc1.cpu_read(address));
+    }
+
+    #[parameterized]
+    #[case(0b0_0000, 0x0000, 0x06)]
+    #[case(0b0_0000, 0x1000, 0x07)]
+    #[case(0b1_0000, 0x0000, 0x07)]
+    #[case(0b1_0000, 0x1000, 0x1A)]
+    fn test_ppu_read_maps_chr_banks_by_mode(control: u8, address: u16, expected_bank: u8) {
+        let mut mmc1 = create_mmc1();
+
+        load_register(&mut mmc1, 0x8000, control);
+        load_register(&mut mmc1, 0xA000, 0x07);
+        load_register(&mut mmc1, 0xC000, 0x1A);
+
+        assert_eq_hex!(expected_bank, mmc1.ppu_read(address));
+    }
+
+    #[parameterized]
+    #[case(0, Mirroring::SingleScreenLower)]
+    #[case(1, Mirroring::SingleScreenUpper)]
+    #[case(2, Mirroring::Vertical)]
+    #[case(3, Mirroring::Horizontal)]
+    fn test_control_sets_mirroring(control: u8, expected: Mirroring) {
+        let mut mmc1 = create_mmc1();
+
+        load_register(&mut mmc1, 0x8000, control);
+
+        assert_eq!(expected, mmc1.mirroring());
+    }
+
+    #[test]
+    fn test_cpu_write_with_bit_7_resets_shift_register() {
+        let mut mmc1 = create_mmc1();
+
+        load_register(&mut mmc1, 0x8000, 0b0_0000);
+        mmc1.cpu_write(0xE000, 0x1);
+        mmc1.cpu_write(0xE000, 0x1);
+        mmc1.cpu_write(0xE000, 0x80);
+        load_register(&mut mmc1, 0xE000, 0x02);
+
+        // The reset also went back to fixing the last bank, and the half written bank was lost.
+        assert_eq_hex!(0x02, mmc1.cpu_read(0x8000));
+        assert_eq_hex!(0x0F, mmc1.cpu_read(0xC000));
+    }
+
+    #[test]
+    fn test_register_is_picked_by_address_of_fifth_write() {
+        let mut mmc1 = create_mmc1();
+
+        for _ in 0..4 {
+            mmc1.cpu_write(0x8000, 0x1);
+        }
+        mmc1.cpu_write(0xFFFF, 0x0);
+
+        assert_eq_hex!(0x0F, mmc1.cpu_read(0x8000));
+    }
+
+    #[parameterized]
+    #[case(0x00, 0xAA)]
+    #[case(0x10, 0x00)]
+    fn test_prg_ram_is_only_accessible_when_enabled(prg_bank: u8, expected: u8) {
+        let mut mmc1 = create_mmc1();
+
+        mmc1.cpu_write(0x6004, 0xAA);
+        load_register(&mut mmc1, 0xE000, prg_bank);
+
+        assert_eq_hex!(expected, mmc1.cpu_read(0x6004));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::roms::{
+    ROM,
+    mappers::{
+        Mapper, PRG_ROM_START,
+        chr_latch::{ChrLatch, LatchState, LatchTrigger},
+        read_banked, write_banked,
+    },
+    mirroring::Mirroring,
+};
+
+pub const MAPPER_ID: u8 = 9;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+// Register addresses, each covering a 4KB range.
+const PRG_BANK_SELECT: u16 = 0xA000;
+const CHR_FD_0000_SELECT: u16 = 0xB000;
+const CHR_FE_0000_SELECT: u16 = 0xC000;
+const CHR_FD_1000_SELECT: u16 = 0xD000;
+const CHR_FE_1000_SELECT: u16 = 0xE000;
+const MIRRORING_SELECT: u16 = 0xF000;
+
+// MMC2 (PxROM), as used by Punch-Out!!.
+//
+// $8000 - $9FFF is a switchable 8KB PRG bank, with $A000 - $FFFF fixed to the last three.
+// CHR is two 4KB windows switched by the $FD/$FE tile latches.
+// Only the left pattern table latch is picky, triggering on exactly $0FD8 and $0FE8.
+#[derive(Debug)]
+pub struct Mmc2 {
+    rom: ROM,
+    prg_bank: u8,
+    chr_latch: ChrLatch,
+    mirroring: Mirroring,
+}
+
+impl Mmc2 {
+    pub fn new(rom: ROM) -> Self {
+        let mirroring = rom.metadata().mirroring();
+
+        Self {
+            rom,
+            prg_bank: 0,
+            chr_latch: ChrLatch::new([
+                LatchTrigger {
+                    fd_start: 0x0FD8,
+                    fd_end: 0x0FD8,
+                    fe_start: 0x0FE8,
+                    fe_end: 0x0FE8,
+                },
+                LatchTrigger {
+                    fd_start: 0x1FD8,
+                    fd_end: 0x1FDF,
+                    fe_start: 0x1FE8,
+                    fe_end: 0x1FEF,
+                },
+            ]),
+            mirroring,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        self.rom.program_rom().len() / PRG_BANK_SIZE
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        if address < PRG_ROM_START {
+            return 0;
+        }
+
+        // The window at $8000 is switchable, the rest are fixed to the last three banks.
+        let window = ((address - PRG_ROM_START) as usize) / PRG_BANK_SIZE;
+        let bank = match window {
+            0 => self.prg_bank as usize,
+            _ => self.prg_bank_count().saturating_sub(4 - window),
+        };
+
+        read_banked(self.rom.program_rom(), bank, PRG_BANK_SIZE, address)
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address & 0xF000 {
+            PRG_BANK_SELECT => self.prg_bank = data & 0x0F,
+            CHR_FD_0000_SELECT => self.chr_latch.set_bank(0, LatchState::FD, data & 0x1F),
+            CHR_FE_0000_SELECT => self.chr_latch.set_bank(0, LatchState::FE, data & 0x1F),
+            CHR_FD_1000_SELECT => self.chr_latch.set_bank(1, LatchState::FD, data & 0x1F),
+            CHR_FE_1000_SELECT => self.chr_latch.set_bank(1, LatchState::FE, data & 0x1F),
+            MIRRORING_SELECT => {
+                self.mirroring = if data & 0x1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let bank = self.chr_latch.bank(address) as usize;
+        let data = read_banked(self.rom.character_rom(), bank, CHR_BANK_SIZE, address);
+
+        self.chr_latch.observe(address);
+
+        data
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let bank = self.chr_latch.bank(address) as usize;
+
+        if let Some(chr_ram) = self.rom.character_ram_mut() {
+            write_banked(chr_ram, bank, CHR_BANK_SIZE, address, data);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    // Fills each 8KB PRG bank and 4KB CHR bank with its own bank number.
+    fn create_mmc2() -> Mmc2 {
+        let prg_rom = (0..0x20000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+        let chr_rom = (0..0x20000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+
+        Mmc2::new(ROM {
+            metadata: Loader::parse_metadata(0b1001_0000, 0b0),
+            prg_rom,
+            chr_rom,
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    #[parameterized]
+    #[case(0x8000, 0x03)]
+    #[case(0x9FFF, 0x03)]
+    #[case(0xA000, 0x0D)]
+    #[case(0xC000, 0x0E)]
+    #[case(0xFFFF, 0x0F)]
+    fn test_cpu_read_maps_prg_banks(address: u16, expected_bank: u8) {
+        let mut mmc2 = create_mmc2();
+
+        mmc2.cpu_write(0xA000, 0x03);
+
+        assert_eq_hex!(expected_bank, mmc2.cpu_read(address));
+    }
+
+    #[test]
+    fn test_ppu_read_switches_bank_after_latch_fetch() {
+        let mut mmc2 = create_mmc2();
+
+        mmc2.cpu_write(0xB000, 0x01);
+        mmc2.cpu_write(0xC000, 0x02);
+
+        assert_eq_hex!(0x02, mmc2.ppu_read(0x0000));
+
+        // The fetch of tile $FD still comes from the $FE bank, then the latch flips.
+        assert_eq_hex!(0x02, mmc2.ppu_read(0x0FD8));
+        assert_eq_hex!(0x01, mmc2.ppu_read(0x0000));
+
+        // MMC2 only triggers on the first byte of the tile in the left pattern table.
+        mmc2.ppu_read(0x0FE9);
+        assert_eq_hex!(0x01, mmc2.ppu_read(0x0000));
+
+        mmc2.ppu_read(0x0FE8);
+        assert_eq_hex!(0x02, mmc2.ppu_read(0x0000));
+    }
+
+    #[test]
+    fn test_ppu_read_switches_right_pattern_table_on_tile_range() {
+        let mut mmc2 = create_mmc2();
+
+        mmc2.cpu_write(0xD000, 0x05);
+        mmc2.cpu_write(0xE000, 0x06);
+
+        mmc2.ppu_read(0x1FDC);
+        assert_eq_hex!(0x05, mmc2.ppu_read(0x1000));
+
+        mmc2.ppu_read(0x1FEF);
+        assert_eq_hex!(0x06, mmc2.ppu_read(0x1000));
+    }
+
+    #[parameterized]
+    #[case(0x00, Mirroring::Vertical)]
+    #[case(0x01, Mirroring::Horizontal)]
+    fn test_cpu_write_sets_mirroring(data: u8, expected: Mirroring) {
+        let mut mmc2 = create_mmc2();
+
+        mmc2.cpu_write(0xF000, data);
+
+        assert_eq!(expected, mmc2.mirroring());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::roms::{
+    ROM,
+    mappers::{
+        Mapper, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, PRG_ROM_START,
+        chr_latch::{ChrLatch, LatchState, LatchTrigger},
+        read_banked, write_banked,
+    },
+    mirroring::Mirroring,
+};
+
+pub const MAPPER_ID: u8 = 10;
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+// Register addresses, each covering a 4KB range.
+const PRG_BANK_SELECT: u16 = 0xA000;
+const CHR_FD_0000_SELECT: u16 = 0xB000;
+const CHR_FE_0000_SELECT: u16 = 0xC000;
+const CHR_FD_1000_SELECT: u16 = 0xD000;
+const CHR_FE_1000_SELECT: u16 = 0xE000;
+const MIRRORING_SELECT: u16 = 0xF000;
+
+// MMC4 (FxROM), as used by the Fire Emblem games.
+//
+// Works like MMC2, but with a switchable 16KB PRG bank at $8000, the last 16KB fixed at
+// $C000, 8KB of PRG RAM, and both pattern table latches triggering on the whole tile.
+#[derive(Debug)]
+pub struct Mmc4 {
+    rom: ROM,
+    prg_ram: Vec<u8>,
+    prg_bank: u8,
+    chr_latch: ChrLatch,
+    mirroring: Mirroring,
+}
+
+impl Mmc4 {
+    pub fn new(rom: ROM) -> Self {
+        let mirroring = rom.metadata().mirroring();
+
+        Self {
+            rom,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            prg_bank: 0,
+            chr_latch: ChrLatch::new([
+                LatchTrigger {
+                    fd_start: 0x0FD8,
+                    fd_end: 0x0FDF,
+                    fe_start: 0x0FE8,
+                    fe_end: 0x0FEF,
+                },
+                LatchTrigger {
+                    fd_start: 0x1FD8,
+                    fd_end: 0x1FDF,
+                    fe_start: 0x1FE8,
+                    fe_end: 0x1FEF,
+                },
+            ]),
+            mirroring,
+        }
+    }
+}
+
+impl Mapper for Mmc4 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            PRG_RAM_START..=PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
+            PRG_ROM_START..=0xBFFF => read_banked(
+                self.rom.program_rom(),
+                self.prg_bank as usize,
+                PRG_BANK_SIZE,
+                address,
+            ),
+            0xC000.. => {
+                let last_bank = self.rom.program_rom().len() / PRG_BANK_SIZE - 1;
+                read_banked(self.rom.program_rom(), last_bank, PRG_BANK_SIZE, address)
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let PRG_RAM_START..=PRG_RAM_END = address {
+            self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            return;
+        }
+
+        match address & 0xF000 {
+            PRG_BANK_SELECT => self.prg_bank = data & 0x0F,
+            CHR_FD_0000_SELECT => self.chr_latch.set_bank(0, LatchState::FD, data & 0x1F),
+            CHR_FE_0000_SELECT => self.chr_latch.set_bank(0, LatchState::FE, data & 0x1F),
+            CHR_FD_1000_SELECT => self.chr_latch.set_bank(1, LatchState::FD, data & 0x1F),
+            CHR_FE_1000_SELECT => self.chr_latch.set_bank(1, LatchState::FE, data & 0x1F),
+            MIRRORING_SELECT => {
+                self.mirroring = if data & 0x1 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let bank = self.chr_latch.bank(address) as usize;
+        let data = read_banked(self.rom.character_rom(), bank, CHR_BANK_SIZE, address);
+
+        self.chr_latch.observe(address);
+
+        data
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let bank = self.chr_latch.bank(address) as usize;
+
+        if let Some(chr_ram) = self.rom.character_ram_mut() {
+            write_banked(chr_ram, bank, CHR_BANK_SIZE, address, data);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    // Fills each 16KB PRG bank and 4KB CHR bank with its own bank number.
+    fn create_mmc4() -> Mmc4 {
+        let prg_rom = (0..0x20000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+        let chr_rom = (0..0x20000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+
+        Mmc4::new(ROM {
+            metadata: Loader::parse_metadata(0b1010_0010, 0b0),
+            prg_rom,
+            chr_rom,
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    #[parameterized]
+    #[case(0x8000, 0x02)]
+    #[case(0xBFFF, 0x02)]
+    #[case(0xC000, 0x07)]
+    #[case(0xFFFF, 0x07)]
+    fn test_cpu_read_maps_prg_banks(address: u16, expected_bank: u8) {
+        let mut mmc4 = create_mmc4();
+
+        mmc4.cpu_write(0xA000, 0x02);
+
+        assert_eq_hex!(expected_bank, mmc4.cpu_read(address));
+    }
+
+    #[test]
+    fn test_cpu_write_sets_prg_ram() {
+        let mut mmc4 = create_mmc4();
+
+        mmc4.cpu_write(0x7FFF, 0xAA);
+
+        assert_eq_hex!(0xAA, mmc4.cpu_read(0x7FFF));
+    }
+
+    #[parameterized]
+    #[case(0x0FD8, 0x01)]
+    #[case(0x0FDF, 0x01)]
+    #[case(0x0FE8, 0x02)]
+    #[case(0x0FEF, 0x02)]
+    fn test_ppu_read_switches_left_pattern_table_on_tile_range(fetch_address: u16, expected: u8) {
+        let mut mmc4 = create_mmc4();
+
+        mmc4.cpu_write(0xB000, 0x01);
+        mmc4.cpu_write(0xC000, 0x02);
+
+        mmc4.ppu_read(0x0FD8);
+        mmc4.ppu_read(fetch_address);
+
+        assert_eq_hex!(expected, mmc4.ppu_read(0x0000));
+    }
+}
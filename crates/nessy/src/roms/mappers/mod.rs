@@ -0,0 +1,170 @@
+use std::fmt::Debug;
+
+use crate::roms::{ROM, mirroring::Mirroring};
+
+pub mod chr_latch;
+pub mod fds;
+pub mod fme7;
+pub mod mmc1;
+pub mod mmc2;
+pub mod mmc4;
+pub mod namco_163;
+pub mod nrom;
+pub mod nsf;
+
+// Cartridge address space, as seen from the CPU.
+const PRG_RAM_START: u16 = 0x6000;
+const PRG_RAM_END: u16 = 0x7FFF;
+const PRG_ROM_START: u16 = 0x8000;
+
+// Pattern tables, as seen from the PPU.
+const PATTERN_TABLE_END: u16 = 0x1FFF;
+
+const PRG_RAM_SIZE: usize = 0x2000;
+
+// Where a nametable fetch ($2000 - $2FFF) is served from.
+#[derive(Debug, PartialEq)]
+pub enum NametableSource {
+    // One of the console's 1KB pages of VRAM, or of the cartridge's for four screen boards.
+    Vram(u8),
+    // A byte from the cartridge itself, such as CHR ROM mapped in as a nametable.
+    Cartridge(u8),
+}
+
+// A mapper is the board inside the cartridge, deciding which parts of PRG and CHR memory
+// are visible to the CPU and PPU at any given time.
+pub trait Mapper: Debug {
+    // Reads from the cartridge space of the CPU bus ($4020 - $FFFF).
+    fn cpu_read(&self, address: u16) -> u8;
+
+    // Writes to the cartridge space of the CPU bus, which is typically how games talk to
+    // the mapper registers.
+    fn cpu_write(&mut self, address: u16, data: u8);
+
+    // Reads the cartridge space without the side effects some registers have on being
+    // read, for debuggers and trace logs. Boards with such registers override this.
+    fn cpu_peek(&self, address: u16) -> u8 {
+        self.cpu_read(address)
+    }
+
+    // Every PPU pattern table fetch ($0000 - $1FFF) comes through here, rather than
+    // reading CHR directly, so boards that watch the fetched address can react to it.
+    fn ppu_read(&mut self, address: u16) -> u8;
+
+    fn ppu_write(&mut self, address: u16, data: u8);
+
+    fn mirroring(&self) -> Mirroring;
+
+    // Boards which arrange the nametables themselves override this, otherwise they come
+    // from VRAM as laid out by `mirroring()`.
+    fn map_nametable(&mut self, address: u16) -> NametableSource {
+        NametableSource::Vram(self.mirroring().vram_page(address))
+    }
+
+    // Advances any counters on the board by a single CPU cycle.
+    fn clock(&mut self) {}
+
+    // Whether the board is currently asserting the CPU's IRQ line.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    // The output level of any expansion audio on the board, for mixing with the APU.
+    fn audio_output(&self) -> f32 {
+        0.0
+    }
+
+    // Ejects the disk or inserts the next side, on boards with a disk drive attached.
+    fn switch_disk_side(&mut self) {}
+
+    // Anything the board has written which should outlive the session, such as the writes
+    // made to a disk.
+    fn save_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+pub fn is_supported(mapper: u8) -> bool {
+    matches!(
+        mapper,
+        nrom::MAPPER_ID
+            | mmc1::MAPPER_ID
+            | mmc2::MAPPER_ID
+            | mmc4::MAPPER_ID
+            | namco_163::MAPPER_ID
+            | fme7::MAPPER_ID
+    )
+}
+
+pub fn create(rom: ROM) -> Box<dyn Mapper> {
+    match rom.metadata().mapper() {
+        nrom::MAPPER_ID => Box::new(nrom::Nrom::new(rom)),
+        mmc1::MAPPER_ID => Box::new(mmc1::Mmc1::new(rom)),
+        mmc2::MAPPER_ID => Box::new(mmc2::Mmc2::new(rom)),
+        mmc4::MAPPER_ID => Box::new(mmc4::Mmc4::new(rom)),
+        namco_163::MAPPER_ID => Box::new(namco_163::Namco163::new(rom)),
+        fme7::MAPPER_ID => Box::new(fme7::Fme7::new(rom)),
+        mapper => panic!("Mapper {} is not supported.", mapper),
+    }
+}
+
+// Gets the offset into `memory` for an address inside a switchable bank. Bank numbers wrap
+// around the amount of memory on the cartridge, as the unused register bits aren't wired up.
+fn banked_offset(memory: &[u8], bank: usize, bank_size: usize, address: u16) -> usize {
+    let bank_count = (memory.len() / bank_size).max(1);
+
+    (bank % bank_count) * bank_size + (address as usize % bank_size)
+}
+
+fn read_banked(memory: &[u8], bank: usize, bank_size: usize, address: u16) -> u8 {
+    memory
+        .get(banked_offset(memory, bank, bank_size, address))
+        .copied()
+        .unwrap_or_default()
+}
+
+fn write_banked(memory: &mut [u8], bank: usize, bank_size: usize, address: u16, data: u8) {
+    let offset = banked_offset(memory, bank, bank_size, address);
+
+    if let Some(byte) = memory.get_mut(offset) {
+        *byte = data;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    #[parameterized]
+    #[case(0, true)]
+    #[case(1, true)]
+    #[case(9, true)]
+    #[case(10, true)]
+    #[case(19, true)]
+    #[case(69, true)]
+    #[case(4, false)]
+    fn test_is_supported(mapper: u8, expected: bool) {
+        assert_eq!(expected, is_supported(mapper));
+    }
+
+    #[parameterized]
+    #[case(0, 0x8000, 0x0000)]
+    #[case(1, 0x8000, 0x2000)]
+    #[case(5, 0x9FFF, 0x3FFF)]
+    fn test_banked_offset_wraps_bank_number(bank: usize, address: u16, expected: usize) {
+        let memory = vec![0; 0x4000];
+
+        assert_eq!(expected, banked_offset(&memory, bank, 0x2000, address));
+    }
+
+    #[test]
+    fn test_write_banked_sets_byte_in_bank() {
+        let mut memory = vec![0; 0x4000];
+
+        write_banked(&mut memory, 3, 0x2000, 0x0010, 0xAA);
+
+        assert_eq!(0xAA, memory[0x2010]);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    audio::{ExpansionAudio, namco_163::Namco163Audio},
+    roms::{
+        ROM,
+        mappers::{
+            Mapper, NametableSource, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, read_banked,
+            write_banked,
+        },
+        mirroring::Mirroring,
+    },
+};
+
+pub const MAPPER_ID: u8 = 19;
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// Register addresses, each covering a 2KB range.
+const DATA_PORT: u16 = 0x4800;
+const IRQ_COUNTER_LO: u16 = 0x5000;
+const IRQ_COUNTER_HI: u16 = 0x5800;
+const CHR_BANK_0: u16 = 0x8000;
+const NAMETABLE_BANK_0: u16 = 0xC000;
+const PRG_BANK_8000: u16 = 0xE000;
+const PRG_BANK_A000: u16 = 0xE800;
+const PRG_BANK_C000: u16 = 0xF000;
+const ADDRESS_PORT: u16 = 0xF800;
+
+// Bank numbers from here up select one of the console's VRAM pages rather than CHR ROM.
+const VRAM_BANK_START: u8 = 0xE0;
+
+const SOUND_DISABLE: u8 = 0b0100_0000;
+
+const IRQ_ENABLE: u16 = 0x8000;
+const IRQ_COUNTER_MAX: u16 = 0x7FFF;
+
+// Writes to PRG RAM need the upper nibble of $F800 set to this, then each of the lower
+// bits write protects a 2KB chunk.
+const PRG_RAM_WRITE_ENABLE: u8 = 0b0100_0000;
+const PRG_RAM_PROTECT_CHUNK_SIZE: u16 = 0x0800;
+
+// Namco 163, used by a good chunk of Namco's later Famicom library.
+//
+// Three switchable 8KB PRG banks with the last fixed at $E000, eight 1KB CHR banks, and four
+// nametable registers which can put CHR ROM in place of the console's VRAM. There's a 15-bit
+// IRQ counter which counts up every CPU cycle, and the wavetable sound generator.
+//
+// Banks $E0 and up in the CHR registers would map VRAM into the pattern tables, which isn't
+// supported as the cartridge can't see VRAM, so those read from CHR ROM instead.
+#[derive(Debug)]
+pub struct Namco163 {
+    rom: ROM,
+    prg_ram: Vec<u8>,
+    prg_ram_protect: u8,
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    nametable_banks: [u8; 4],
+    irq_counter: u16,
+    irq_pending: bool,
+    audio: Namco163Audio,
+}
+
+impl Namco163 {
+    pub fn new(rom: ROM) -> Self {
+        Self {
+            rom,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            prg_ram_protect: 0,
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            nametable_banks: [VRAM_BANK_START; 4],
+            irq_counter: 0,
+            irq_pending: false,
+            audio: Namco163Audio::default(),
+        }
+    }
+
+    fn prg_ram_writable(&self, address: u16) -> bool {
+        let chunk = (address - PRG_RAM_START) / PRG_RAM_PROTECT_CHUNK_SIZE;
+
+        self.prg_ram_protect & 0xF0 == PRG_RAM_WRITE_ENABLE
+            && self.prg_ram_protect & (1 << chunk) == 0
+    }
+}
+
+impl Mapper for Namco163 {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            DATA_PORT..IRQ_COUNTER_LO => self.audio.read_data(),
+            IRQ_COUNTER_LO..IRQ_COUNTER_HI => self.irq_counter as u8,
+            IRQ_COUNTER_HI..PRG_RAM_START => (self.irq_counter >> 8) as u8,
+            PRG_RAM_START..=PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
+            0x8000..=0xDFFF => {
+                let bank = self.prg_banks[(address as usize - 0x8000) / PRG_BANK_SIZE] & 0x3F;
+                read_banked(
+                    self.rom.program_rom(),
+                    bank as usize,
+                    PRG_BANK_SIZE,
+                    address,
+                )
+            }
+            0xE000.. => {
+                let last_bank = (self.rom.program_rom().len() / PRG_BANK_SIZE).saturating_sub(1);
+                read_banked(self.rom.program_rom(), last_bank, PRG_BANK_SIZE, address)
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_peek(&self, address: u16) -> u8 {
+        match address {
+            DATA_PORT..IRQ_COUNTER_LO => self.audio.peek_data(),
+            _ => self.cpu_read(address),
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            DATA_PORT..IRQ_COUNTER_LO => self.audio.write_data(data),
+            IRQ_COUNTER_LO..IRQ_COUNTER_HI => {
+                self.irq_counter = (self.irq_counter & 0xFF00) | data as u16;
+                self.irq_pending = false;
+            }
+            IRQ_COUNTER_HI..PRG_RAM_START => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((data as u16) << 8);
+                self.irq_pending = false;
+            }
+            PRG_RAM_START..=PRG_RAM_END if self.prg_ram_writable(address) => {
+                self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            }
+            CHR_BANK_0..NAMETABLE_BANK_0 => {
+                self.chr_banks[((address - CHR_BANK_0) / 0x800) as usize] = data;
+            }
+            NAMETABLE_BANK_0..PRG_BANK_8000 => {
+                self.nametable_banks[((address - NAMETABLE_BANK_0) / 0x800) as usize] = data;
+            }
+            PRG_BANK_8000..PRG_BANK_A000 => self.prg_banks[0] = data,
+            PRG_BANK_A000..PRG_BANK_C000 => self.prg_banks[1] = data,
+            PRG_BANK_C000..ADDRESS_PORT => self.prg_banks[2] = data,
+            ADDRESS_PORT.. => {
+                self.audio.set_address(data);
+                self.prg_ram_protect = data;
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let bank = self.chr_banks[(address as usize / CHR_BANK_SIZE) & 0x7] as usize;
+
+        read_banked(self.rom.character_rom(), bank, CHR_BANK_SIZE, address)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let bank = self.chr_banks[(address as usize / CHR_BANK_SIZE) & 0x7] as usize;
+
+        if let Some(chr_ram) = self.rom.character_ram_mut() {
+            write_banked(chr_ram, bank, CHR_BANK_SIZE, address, data);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.rom.metadata().mirroring()
+    }
+
+    fn map_nametable(&mut self, address: u16) -> NametableSource {
+        let bank = self.nametable_banks[((address >> 10) & 0x3) as usize];
+
+        if bank >= VRAM_BANK_START {
+            NametableSource::Vram(bank & 0x1)
+        } else {
+            NametableSource::Cartridge(read_banked(
+                self.rom.character_rom(),
+                bank as usize,
+                CHR_BANK_SIZE,
+                address,
+            ))
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.prg_banks[0] & SOUND_DISABLE == 0 {
+            self.audio.clock();
+        }
+
+        if self.irq_counter & IRQ_ENABLE == 0 {
+            return;
+        }
+
+        let count = self.irq_counter & IRQ_COUNTER_MAX;
+
+        // The counter stops once it's hit the top, holding the IRQ until acknowledged.
+        if count == IRQ_COUNTER_MAX {
+            return;
+        }
+
+        self.irq_counter = IRQ_ENABLE | (count + 1);
+
+        if count + 1 == IRQ_COUNTER_MAX {
+            self.irq_pending = true;
+        }
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    // Fills each 8KB PRG bank and 1KB CHR bank with its own bank number.
+    fn create_namco_163() -> Namco163 {
+        let prg_rom = (0..0x40000).map(|i| (i / PRG_BANK_SIZE) as u8).collect();
+        let chr_rom = (0..0x40000).map(|i| (i / CHR_BANK_SIZE) as u8).collect();
+
+        Namco163::new(ROM {
+            metadata: Loader::parse_metadata(0b0011_0000, 0b0001_0000),
+            prg_rom,
+            chr_rom,
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    #[parameterized]
+    #[case(PRG_BANK_8000, 0x8000, 0x03)]
+    #[case(PRG_BANK_A000, 0xBFFF, 0x04)]
+    #[case(PRG_BANK_C000, 0xC000, 0x05)]
+    fn test_cpu_read_maps_prg_banks(register: u16, address: u16, expected_bank: u8) {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(register, expected_bank);
+
+        assert_eq_hex!(expected_bank, namco_163.cpu_read(address));
+    }
+
+    #[test]
+    fn test_cpu_read_handles_prg_smaller_than_a_bank() {
+        let namco_163 = Namco163::new(ROM {
+            metadata: Loader::parse_metadata(0b0011_0000, 0b0001_0000),
+            prg_rom: vec![0xAB; 0x1000],
+            chr_rom: vec![0; 0x2000],
+            has_chr_ram: false,
+            corrections: vec![],
+        });
+
+        assert_eq_hex!(0xAB, namco_163.cpu_read(0xE000));
+    }
+
+    #[test]
+    fn test_cpu_read_fixes_last_prg_bank() {
+        let namco_163 = create_namco_163();
+
+        assert_eq_hex!(0x1F, namco_163.cpu_read(0xFFFF));
+    }
+
+    #[test]
+    fn test_ppu_read_maps_chr_banks() {
+        let mut namco_163 = create_namco_163();
+
+        for window in 0..8u16 {
+            namco_163.cpu_write(CHR_BANK_0 + window * 0x800, 0x20 + window as u8);
+        }
+
+        for window in 0..8u16 {
+            assert_eq_hex!(0x20 + window as u8, namco_163.ppu_read(window * 0x400));
+        }
+    }
+
+    #[parameterized]
+    #[case(0xE0, 0x2000, NametableSource::Vram(0))]
+    #[case(0xE1, 0x2000, NametableSource::Vram(1))]
+    #[case(0x12, 0x2000, NametableSource::Cartridge(0x12))]
+    fn test_map_nametable(bank: u8, address: u16, expected: NametableSource) {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(NAMETABLE_BANK_0, bank);
+
+        assert_eq!(expected, namco_163.map_nametable(address));
+    }
+
+    #[test]
+    fn test_map_nametable_uses_register_per_quadrant() {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(NAMETABLE_BANK_0 + 0x1800, 0x30);
+
+        assert_eq!(NametableSource::Vram(0), namco_163.map_nametable(0x2800));
+        assert_eq!(
+            NametableSource::Cartridge(0x30),
+            namco_163.map_nametable(0x2C00)
+        );
+    }
+
+    #[parameterized]
+    #[case(0x40, 0xAA)]
+    #[case(0x41, 0x00)]
+    #[case(0x00, 0x00)]
+    fn test_prg_ram_write_protect(protect: u8, expected: u8) {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(ADDRESS_PORT, protect);
+        namco_163.cpu_write(0x6000, 0xAA);
+
+        assert_eq_hex!(expected, namco_163.cpu_read(0x6000));
+    }
+
+    #[test]
+    fn test_irq_fires_when_counter_reaches_max() {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(IRQ_COUNTER_LO, 0xFD);
+        namco_163.cpu_write(IRQ_COUNTER_HI, 0xFF);
+
+        namco_163.clock();
+        assert!(!namco_163.irq_pending());
+
+        namco_163.clock();
+        assert!(namco_163.irq_pending());
+
+        // Once it's at the top it stops counting.
+        namco_163.clock();
+        assert_eq_hex!(0xFF, namco_163.cpu_read(IRQ_COUNTER_LO));
+        assert_eq_hex!(0xFF, namco_163.cpu_read(IRQ_COUNTER_HI));
+
+        namco_163.cpu_write(IRQ_COUNTER_LO, 0x00);
+        assert!(!namco_163.irq_pending());
+    }
+
+    #[test]
+    fn test_data_port_reaches_sound_ram() {
+        let mut namco_163 = create_namco_163();
+
+        namco_163.cpu_write(ADDRESS_PORT, 0x80 | 0x7F);
+        namco_163.cpu_write(DATA_PORT, 0x70);
+
+        namco_163.cpu_write(ADDRESS_PORT, 0x7F);
+        assert_eq_hex!(0x70, namco_163.cpu_read(DATA_PORT));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::roms::{
+    ROM,
+    mappers::{Mapper, PATTERN_TABLE_END, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, PRG_ROM_START},
+    mirroring::Mirroring,
+};
+
+pub const MAPPER_ID: u8 = 0;
+
+// NROM, the board with no bank switching at all.
+//
+// PRG ROM is either 16KB (NROM-128), which is mirrored into both halves of $8000 - $FFFF,
+// or 32KB (NROM-256). CHR is a fixed 8KB. Family BASIC (and plenty of test ROMs) expect
+// PRG RAM at $6000, so it's always provided.
+#[derive(Debug)]
+pub struct Nrom {
+    rom: ROM,
+    prg_ram: Vec<u8>,
+}
+
+impl Nrom {
+    pub fn new(rom: ROM) -> Self {
+        Self {
+            rom,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            PRG_RAM_START..=PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
+            PRG_ROM_START.. => {
+                let prg_rom = self.rom.program_rom();
+                prg_rom[(address - PRG_ROM_START) as usize % prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let PRG_RAM_START..=PRG_RAM_END = address {
+            self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.rom
+            .character_rom()
+            .get((address & PATTERN_TABLE_END) as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        if let Some(byte) = self
+            .rom
+            .character_ram_mut()
+            .and_then(|chr_ram| chr_ram.get_mut((address & PATTERN_TABLE_END) as usize))
+        {
+            *byte = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.rom.metadata().mirroring()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+    use sif::parameterized;
+
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    fn create_nrom(prg_rom_size: usize) -> Nrom {
+        let prg_rom = (0..prg_rom_size).map(|i| (i >> 8) as u8).collect();
+
+        Nrom::new(ROM {
+            metadata: Loader::parse_metadata(0b0000_0001, 0b0),
+            prg_rom,
+            chr_rom: vec![0x55; 0x2000],
+            has_chr_ram: false,
+            corrections: vec![],
+        })
+    }
+
+    #[parameterized]
+    #[case(0x4000, 0x8000, 0x00)]
+    #[case(0x4000, 0xC000, 0x00)]
+    #[case(0x4000, 0xFFFF, 0x3F)]
+    #[case(0x8000, 0xC000, 0x40)]
+    #[case(0x8000, 0xFFFF, 0x7F)]
+    fn test_cpu_read_mirrors_prg_rom(prg_rom_size: usize, address: u16, expected: u8) {
+        let nrom = create_nrom(prg_rom_size);
+
+        assert_eq_hex!(expected, nrom.cpu_read(address));
+    }
+
+    #[test]
+    fn test_cpu_write_sets_prg_ram() {
+        let mut nrom = create_nrom(0x4000);
+
+        nrom.cpu_write(0x6004, 0xAA);
+
+        assert_eq_hex!(0xAA, nrom.cpu_read(0x6004));
+    }
+
+    #[test]
+    fn test_cpu_write_ignores_prg_rom() {
+        let mut nrom = create_nrom(0x4000);
+
+        nrom.cpu_write(0x8000, 0xAA);
+
+        assert_eq_hex!(0x00, nrom.cpu_read(0x8000));
+    }
+
+    #[test]
+    fn test_ppu_read_returns_chr_rom() {
+        let mut nrom = create_nrom(0x4000);
+
+        assert_eq_hex!(0x55, nrom.ppu_read(0x1FFF));
+        assert_eq!(Mirroring::Vertical, nrom.mirroring());
+    }
+
+    #[test]
+    fn test_ppu_write_ignores_chr_rom() {
+        let mut nrom = create_nrom(0x4000);
+
+        nrom.ppu_write(0x0010, 0xAA);
+
+        assert_eq_hex!(0x55, nrom.ppu_read(0x0010));
+    }
+
+    #[test]
+    fn test_ppu_write_sets_chr_ram() {
+        let mut nrom = Nrom::new(ROM {
+            metadata: Loader::parse_metadata(0b0, 0b0),
+            prg_rom: vec![0; 0x4000],
+            chr_rom: vec![0; 0x2000],
+            has_chr_ram: true,
+            corrections: vec![],
+        });
+
+        nrom.ppu_write(0x1FF0, 0xAA);
+
+        assert_eq_hex!(0xAA, nrom.ppu_read(0x1FF0));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    audio::{ExpansionAudio, namco_163::Namco163Audio, sunsoft_5b::Sunsoft5b},
+    roms::{
+        mappers::{Mapper, PRG_RAM_END, PRG_RAM_SIZE, PRG_RAM_START, PRG_ROM_START, read_banked},
+        mirroring::Mirroring,
+        nsf::{ExpansionChips, Nsf},
+    },
+};
+
+const BANK_SIZE: usize = 0x1000;
+
+// Bank registers for each 4KB window of $8000 - $FFFF.
+const BANK_REGISTER_START: u16 = 0x5FF8;
+const BANK_REGISTER_END: u16 = 0x5FFF;
+
+// Expansion audio registers, where they sit on the boards the chips came from.
+const NAMCO_163_DATA_PORT: u16 = 0x4800;
+const NAMCO_163_DATA_PORT_END: u16 = 0x4FFF;
+const NAMCO_163_ADDRESS_PORT: u16 = 0xF800;
+const SUNSOFT_5B_REGISTER_SELECT: u16 = 0xC000;
+const SUNSOFT_5B_REGISTER_WRITE: u16 = 0xE000;
+
+// The pretend cartridge an NSF plays from, which is only what the NSF spec asks for: PRG RAM,
+// the tune's data in 4KB banks, and whichever expansion sound chips it was written for.
+//
+// Without bankswitching, the data simply sits at the load address. With it, the data is
+// padded by the load address' offset within its 4KB bank, and the banks are switched by
+// writing $5FF8 - $5FFF. Only the 5B and Namco 163 are emulated, so tunes for the other
+// chips play without them.
+#[derive(Debug)]
+pub struct NsfCartridge {
+    image: Vec<u8>,
+    prg_ram: Vec<u8>,
+    banks: [u8; 8],
+    is_bankswitched: bool,
+    sunsoft_5b: Option<Sunsoft5b>,
+    namco_163: Option<Namco163Audio>,
+}
+
+impl NsfCartridge {
+    pub fn new(nsf: &Nsf) -> Self {
+        let is_bankswitched = nsf.is_bankswitched();
+
+        let padding = if is_bankswitched {
+            nsf.load_address() as usize % BANK_SIZE
+        } else {
+            (nsf.load_address() - PRG_ROM_START) as usize
+        };
+
+        let mut image = vec![0; padding];
+        image.extend(nsf.data());
+
+        // Without bankswitching the whole 32KB is there, even if the tune is smaller.
+        if !is_bankswitched {
+            image.resize(0x8000, 0);
+        }
+
+        let chips = nsf.expansion_chips();
+
+        Self {
+            image,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            banks: if is_bankswitched {
+                nsf.bank_init()
+            } else {
+                [0, 1, 2, 3, 4, 5, 6, 7]
+            },
+            is_bankswitched,
+            sunsoft_5b: chips
+                .contains(ExpansionChips::SUNSOFT_5B)
+                .then(Sunsoft5b::default),
+            namco_163: chips
+                .contains(ExpansionChips::NAMCO_163)
+                .then(Namco163Audio::default),
+        }
+    }
+}
+
+impl Mapper for NsfCartridge {
+    fn cpu_read(&self, address: u16) -> u8 {
+        match address {
+            NAMCO_163_DATA_PORT..=NAMCO_163_DATA_PORT_END => self
+                .namco_163
+                .as_ref()
+                .map_or(0, |namco_163| namco_163.read_data()),
+            PRG_RAM_START..=PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
+            PRG_ROM_START.. => {
+                let window = (address - PRG_ROM_START) as usize / BANK_SIZE;
+                read_banked(&self.image, self.banks[window] as usize, BANK_SIZE, address)
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_peek(&self, address: u16) -> u8 {
+        match address {
+            NAMCO_163_DATA_PORT..=NAMCO_163_DATA_PORT_END => self
+                .namco_163
+                .as_ref()
+                .map_or(0, |namco_163| namco_163.peek_data()),
+            _ => self.cpu_read(address),
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            NAMCO_163_DATA_PORT..=NAMCO_163_DATA_PORT_END => {
+                if let Some(namco_163) = self.namco_163.as_mut() {
+                    namco_163.write_data(data);
+                }
+            }
+            BANK_REGISTER_START..=BANK_REGISTER_END if self.is_bankswitched => {
+                self.banks[(address - BANK_REGISTER_START) as usize] = data;
+            }
+            PRG_RAM_START..=PRG_RAM_END => {
+                self.prg_ram[(address - PRG_RAM_START) as usize] = data;
+            }
+            SUNSOFT_5B_REGISTER_SELECT.. => {
+                if let Some(sunsoft_5b) = self.sunsoft_5b.as_mut() {
+                    match address & 0xE000 {
+                        SUNSOFT_5B_REGISTER_SELECT => sunsoft_5b.select_register(data),
+                        SUNSOFT_5B_REGISTER_WRITE => sunsoft_5b.write_register(data),
+                        _ => {}
+                    }
+                }
+
+                if let (NAMCO_163_ADDRESS_PORT.., Some(namco_163)) =
+                    (address, self.namco_163.as_mut())
+                {
+                    namco_163.set_address(data);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, _address: u16) -> u8 {
+        0
+    }
+
+    fn ppu_write(&mut self, _address: u16, _data: u8) {}
+
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Horizontal
+    }
+
+    fn clock(&mut self) {
+        if let Some(sunsoft_5b) = self.sunsoft_5b.as_mut() {
+            sunsoft_5b.clock();
+        }
+
+        if let Some(namco_163) = self.namco_163.as_mut() {
+            namco_163.clock();
+        }
+    }
+
+    fn audio_output(&self) -> f32 {
+        let outputs: Vec<f32> = [
+            self.sunsoft_5b.as_ref().map(|chip| chip.output()),
+            self.namco_163.as_ref().map(|chip| chip.output()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if outputs.is_empty() {
+            0.0
+        } else {
+            outputs.iter().sum::<f32>() / outputs.len() as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_hex::assert_eq_hex;
+
+    use super::*;
+    use crate::roms::nsf::test::create_nsf_data;
+
+    fn create_cartridge(load_address: u16, bank_init: [u8; 8]) -> NsfCartridge {
+        let mut data = create_nsf_data(&[], bank_init);
+        data[0x08..0x0A].copy_from_slice(&load_address.to_le_bytes());
+        data.extend((0..0x8000).map(|i| (i / BANK_SIZE) as u8 + 0x10));
+
+        NsfCartridge::new(&Nsf::load(&data).unwrap())
+    }
+
+    #[test]
+    fn test_cpu_read_places_data_at_load_address() {
+        let cartridge = create_cartridge(0x8400, [0; 8]);
+
+        assert_eq_hex!(0x00, cartridge.cpu_read(0x83FF));
+        assert_eq_hex!(0x10, cartridge.cpu_read(0x8400));
+        assert_eq_hex!(0x17, cartridge.cpu_read(0xFFFF));
+    }
+
+    #[test]
+    fn test_cpu_read_pads_bankswitched_data_within_bank() {
+        let cartridge = create_cartridge(0x8400, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        assert_eq_hex!(0x00, cartridge.cpu_read(0x83FF));
+        assert_eq_hex!(0x10, cartridge.cpu_read(0x8400));
+        assert_eq_hex!(0x11, cartridge.cpu_read(0x9400));
+    }
+
+    #[test]
+    fn test_cpu_write_switches_banks() {
+        let mut cartridge = create_cartridge(0x8000, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        cartridge.cpu_write(0x5FF8, 7);
+        cartridge.cpu_write(0x5FFF, 0);
+
+        assert_eq_hex!(0x17, cartridge.cpu_read(0x8000));
+        assert_eq_hex!(0x10, cartridge.cpu_read(0xF000));
+    }
+
+    #[test]
+    fn test_cpu_write_ignores_banks_unless_bankswitched() {
+        let mut cartridge = create_cartridge(0x8000, [0; 8]);
+
+        cartridge.cpu_write(0x5FF8, 7);
+
+        assert_eq_hex!(0x10, cartridge.cpu_read(0x8000));
+    }
+
+    #[test]
+    fn test_cpu_write_sets_prg_ram() {
+        let mut cartridge = create_cartridge(0x8000, [0; 8]);
+
+        cartridge.cpu_write(0x6123, 0xAA);
+
+        assert_eq_hex!(0xAA, cartridge.cpu_read(0x6123));
+    }
+
+    #[test]
+    fn test_sunsoft_5b_is_audible() {
+        let mut cartridge = create_cartridge(0x8000, [0; 8]);
+
+        // Tone and noise disabled leaves channel A high, at full volume.
+        cartridge.cpu_write(0xC000, 0x07);
+        cartridge.cpu_write(0xE000, 0b0011_1111);
+        cartridge.cpu_write(0xC000, 0x08);
+        cartridge.cpu_write(0xE000, 0x0F);
+
+        assert!(cartridge.audio_output() > 0.0);
+    }
+}
@@ -1,9 +1,46 @@
 // Describes how nametable mirroring works, what is shown on reads of the bottom/right of the
 // current nametable.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Mirroring {
     Horizontal,
     Vertical,
-    SingleScreen,
+    SingleScreenLower,
+    SingleScreenUpper,
     FourScreen,
 }
+
+impl Mirroring {
+    // Which 1KB page of VRAM a nametable address lands on.
+    pub fn vram_page(&self, address: u16) -> u8 {
+        let quadrant = ((address >> 10) & 0x3) as u8;
+
+        match self {
+            Mirroring::Horizontal => quadrant >> 1,
+            Mirroring::Vertical => quadrant & 0x1,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => quadrant,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    #[parameterized]
+    #[case(Mirroring::Horizontal, [0, 0, 1, 1])]
+    #[case(Mirroring::Vertical, [0, 1, 0, 1])]
+    #[case(Mirroring::SingleScreenLower, [0, 0, 0, 0])]
+    #[case(Mirroring::SingleScreenUpper, [1, 1, 1, 1])]
+    #[case(Mirroring::FourScreen, [0, 1, 2, 3])]
+    fn test_vram_page(mirroring: Mirroring, expected_pages: [u8; 4]) {
+        for (quadrant, expected_page) in expected_pages.iter().enumerate() {
+            let address = 0x2000 + quadrant as u16 * 0x400 + 0x123;
+
+            assert_eq!(*expected_page, mirroring.vram_page(address));
+        }
+    }
+}
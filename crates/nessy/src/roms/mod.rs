@@ -1,21 +1,55 @@
-use crate::roms::loader::Metadata;
+use crate::roms::{database::HeaderCorrection, loader::Metadata};
 
+pub mod crc32;
+pub mod database;
+pub mod fds;
 pub mod loader;
+pub mod mappers;
 pub mod mirroring;
+pub mod nsf;
+pub mod patches;
+pub mod program;
+pub mod region;
+pub mod unif;
 
 #[derive(Debug)]
 pub struct ROM {
-    #[allow(dead_code)]
     metadata: Metadata,
 
     prg_rom: Vec<u8>,
 
-    #[allow(dead_code)]
+    // CHR ROM, or the cartridge's CHR RAM when the header declares no CHR ROM at all.
     chr_rom: Vec<u8>,
+
+    has_chr_ram: bool,
+
+    // Header values the game database disagreed with, and replaced.
+    corrections: Vec<HeaderCorrection>,
 }
 
 impl ROM {
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
     pub fn program_rom(&self) -> &Vec<u8> {
         &self.prg_rom
     }
+
+    pub fn character_rom(&self) -> &Vec<u8> {
+        &self.chr_rom
+    }
+
+    pub fn has_character_ram(&self) -> bool {
+        self.has_chr_ram
+    }
+
+    // CHR memory the PPU may write to, which is only the case on boards with CHR RAM.
+    pub fn character_ram_mut(&mut self) -> Option<&mut Vec<u8>> {
+        self.has_chr_ram.then_some(&mut self.chr_rom)
+    }
+
+    pub fn corrections(&self) -> &[HeaderCorrection] {
+        &self.corrections
+    }
 }
@@ -0,0 +1,225 @@
+use bitflags::bitflags;
+
+use crate::roms::region::Region;
+
+// The NSF magic - NESM^Z
+pub const NSF_MAGIC: [u8; 5] = [0x4E, 0x45, 0x53, 0x4D, 0x1A];
+
+// Size of the header in bytes.
+const HEADER_SIZE_BYTES: usize = 0x80;
+
+// The title, artist and copyright fields are each 32 bytes of null-padded text.
+const TEXT_FIELD_SIZE: usize = 32;
+
+bitflags! {
+    // The expansion sound chips a tune is written for, from byte $7B.
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct ExpansionChips: u8 {
+        const VRC6 = 0b0000_0001;
+        const VRC7 = 0b0000_0010;
+        const FDS = 0b0000_0100;
+        const MMC5 = 0b0000_1000;
+        const NAMCO_163 = 0b0001_0000;
+        const SUNSOFT_5B = 0b0010_0000;
+    }
+}
+
+// An NES Sound Format file: just the sound code and data ripped out of a game, plus a header
+// saying where it goes and which routines set up and play each song.
+#[derive(Debug)]
+pub struct Nsf {
+    total_songs: u8,
+    starting_song: u8,
+    load_address: u16,
+    init_address: u16,
+    play_address: u16,
+    title: String,
+    artist: String,
+    copyright: String,
+    ntsc_play_period_us: u16,
+    pal_play_period_us: u16,
+    bank_init: [u8; 8],
+    region: Region,
+    expansion_chips: ExpansionChips,
+    data: Vec<u8>,
+}
+
+impl Nsf {
+    pub fn is_nsf(data: &[u8]) -> bool {
+        data.starts_with(&NSF_MAGIC)
+    }
+
+    pub fn load(data: &[u8]) -> Result<Self, &'static str> {
+        if !Self::is_nsf(data) {
+            return Err("Incorrect NSF magic in file.");
+        }
+
+        let header = data
+            .get(..HEADER_SIZE_BYTES)
+            .ok_or("Truncated header in NSF file.")?;
+
+        let read_u16 = |offset: usize| u16::from_le_bytes([header[offset], header[offset + 1]]);
+
+        let read_text = |offset: usize| {
+            let field = &header[offset..offset + TEXT_FIELD_SIZE];
+            let text = field.split(|&byte| byte == 0).next().unwrap_or_default();
+            String::from_utf8_lossy(text).into_owned()
+        };
+
+        let load_address = read_u16(0x08);
+
+        if load_address < 0x8000 {
+            return Err("NSF load address is below $8000.");
+        }
+
+        let region = match header[0x7A] & 0b11 {
+            0 => Region::Ntsc,
+            1 => Region::Pal,
+            _ => Region::MultiRegion,
+        };
+
+        let mut bank_init = [0; 8];
+        bank_init.copy_from_slice(&header[0x70..0x78]);
+
+        Ok(Self {
+            total_songs: header[0x06],
+            // Songs are numbered from 1 in the header, but passed to INIT from 0.
+            starting_song: header[0x07].saturating_sub(1),
+            load_address,
+            init_address: read_u16(0x0A),
+            play_address: read_u16(0x0C),
+            title: read_text(0x0E),
+            artist: read_text(0x2E),
+            copyright: read_text(0x4E),
+            ntsc_play_period_us: read_u16(0x6E),
+            pal_play_period_us: read_u16(0x78),
+            bank_init,
+            region,
+            expansion_chips: ExpansionChips::from_bits_truncate(header[0x7B]),
+            data: data[HEADER_SIZE_BYTES..].to_vec(),
+        })
+    }
+
+    pub fn total_songs(&self) -> u8 {
+        self.total_songs
+    }
+
+    pub fn starting_song(&self) -> u8 {
+        self.starting_song
+    }
+
+    pub fn load_address(&self) -> u16 {
+        self.load_address
+    }
+
+    pub fn init_address(&self) -> u16 {
+        self.init_address
+    }
+
+    pub fn play_address(&self) -> u16 {
+        self.play_address
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn artist(&self) -> &str {
+        &self.artist
+    }
+
+    pub fn copyright(&self) -> &str {
+        &self.copyright
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    // How often PLAY should be called, in microseconds, for the region being played.
+    pub fn play_period_us(&self, region: Region) -> u16 {
+        match region {
+            Region::Pal => self.pal_play_period_us,
+            _ => self.ntsc_play_period_us,
+        }
+    }
+
+    pub fn bank_init(&self) -> [u8; 8] {
+        self.bank_init
+    }
+
+    // Any non-zero initial bank means the tune is bankswitched.
+    pub fn is_bankswitched(&self) -> bool {
+        self.bank_init.iter().any(|&bank| bank != 0)
+    }
+
+    pub fn expansion_chips(&self) -> ExpansionChips {
+        self.expansion_chips
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    pub(crate) fn create_nsf_data(code: &[u8], bank_init: [u8; 8]) -> Vec<u8> {
+        let mut header = vec![0; HEADER_SIZE_BYTES];
+        header[..5].copy_from_slice(&NSF_MAGIC);
+        header[0x05] = 1;
+        header[0x06] = 3;
+        header[0x07] = 2;
+        header[0x08..0x0A].copy_from_slice(&0x8000u16.to_le_bytes());
+        header[0x0A..0x0C].copy_from_slice(&0x8000u16.to_le_bytes());
+        header[0x0C..0x0E].copy_from_slice(&0x8010u16.to_le_bytes());
+        header[0x0E..0x0E + 4].copy_from_slice(b"Tune");
+        header[0x2E..0x2E + 6].copy_from_slice(b"Artist");
+        header[0x4E..0x4E + 4].copy_from_slice(b"1986");
+        header[0x6E..0x70].copy_from_slice(&16639u16.to_le_bytes());
+        header[0x70..0x78].copy_from_slice(&bank_init);
+        header[0x78..0x7A].copy_from_slice(&19997u16.to_le_bytes());
+        header[0x7B] = ExpansionChips::SUNSOFT_5B.bits();
+
+        [header, code.to_vec()].concat()
+    }
+
+    #[test]
+    fn test_load_reads_header() {
+        let nsf = Nsf::load(&create_nsf_data(&[0x60], [0; 8])).unwrap();
+
+        assert_eq!(3, nsf.total_songs());
+        assert_eq!(1, nsf.starting_song());
+        assert_eq!(0x8000, nsf.load_address());
+        assert_eq!(0x8000, nsf.init_address());
+        assert_eq!(0x8010, nsf.play_address());
+        assert_eq!("Tune", nsf.title());
+        assert_eq!("Artist", nsf.artist());
+        assert_eq!("1986", nsf.copyright());
+        assert_eq!(16639, nsf.play_period_us(Region::Ntsc));
+        assert_eq!(19997, nsf.play_period_us(Region::Pal));
+        assert_eq!(Region::Ntsc, nsf.region());
+        assert_eq!(ExpansionChips::SUNSOFT_5B, nsf.expansion_chips());
+        assert!(!nsf.is_bankswitched());
+        assert_eq!([0x60], nsf.data());
+    }
+
+    #[test]
+    fn test_load_detects_bankswitching() {
+        let nsf = Nsf::load(&create_nsf_data(&[0x60], [0, 1, 2, 3, 4, 5, 6, 7])).unwrap();
+
+        assert!(nsf.is_bankswitched());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_invalid_magic() {
+        assert!(Nsf::load(b"NESM\x1B").is_err_and(|err| err == "Incorrect NSF magic in file."));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_truncated_header() {
+        assert!(Nsf::load(&NSF_MAGIC).is_err_and(|err| err == "Truncated header in NSF file."));
+    }
+}
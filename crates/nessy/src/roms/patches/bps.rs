@@ -0,0 +1,222 @@
+use crate::roms::{
+    crc32::crc32,
+    patches::{MAX_TARGET_SIZE_BYTES, PatchReader, read_footer},
+};
+
+// The BPS magic - BPS1
+const BPS_MAGIC: [u8; 4] = *b"BPS1";
+
+// The action, in the bottom two bits of each action's number.
+const SOURCE_READ: usize = 0;
+const TARGET_READ: usize = 1;
+const SOURCE_COPY: usize = 2;
+const TARGET_COPY: usize = 3;
+
+pub fn is_bps_patch(patch: &[u8]) -> bool {
+    patch.starts_with(&BPS_MAGIC)
+}
+
+// Applies a BPS patch to `data`, checking the source and result against the patch's CRCs.
+pub fn apply(data: &[u8], patch: &[u8]) -> Result<Vec<u8>, &'static str> {
+    const TRUNCATED: &str = "Truncated BPS patch.";
+    const OUT_OF_RANGE: &str = "BPS patch reads out of range.";
+
+    if !is_bps_patch(patch) {
+        return Err("Incorrect BPS magic in patch.");
+    }
+
+    let footer = read_footer(patch, "BPS patch is corrupt.")?;
+
+    if crc32(data) != footer.source_crc32 {
+        return Err("Data doesn't match the BPS patch source.");
+    }
+
+    let mut reader = PatchReader::new(patch, BPS_MAGIC.len(), TRUNCATED);
+    let _source_size = reader.number()?;
+    let target_size = reader.number()?;
+
+    if target_size > MAX_TARGET_SIZE_BYTES {
+        return Err("BPS patch target is too large.");
+    }
+
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+
+    let mut patched: Vec<u8> = Vec::with_capacity(target_size);
+    let mut source_offset = 0usize;
+    let mut target_offset = 0usize;
+
+    // Copies move their offset back or forward, with the sign in the bottom bit.
+    let seek = |offset: usize, reader: &mut PatchReader| -> Result<usize, &'static str> {
+        let number = reader.number()?;
+        let distance = number >> 1;
+
+        if number & 1 != 0 {
+            offset.checked_sub(distance)
+        } else {
+            offset.checked_add(distance)
+        }
+        .ok_or(OUT_OF_RANGE)
+    };
+
+    while !reader.is_done() {
+        let action = reader.number()?;
+        let length = (action >> 2) + 1;
+
+        if patched.len() + length > target_size {
+            return Err("BPS patch writes past its target size.");
+        }
+
+        match action & 0x03 {
+            SOURCE_READ => {
+                let position = patched.len();
+                let bytes = data.get(position..position + length).ok_or(OUT_OF_RANGE)?;
+                patched.extend(bytes);
+            }
+            TARGET_READ => patched.extend(reader.bytes(length)?),
+            SOURCE_COPY => {
+                source_offset = seek(source_offset, &mut reader)?;
+                let bytes = data
+                    .get(source_offset..source_offset + length)
+                    .ok_or(OUT_OF_RANGE)?;
+                patched.extend(bytes);
+                source_offset += length;
+            }
+            TARGET_COPY => {
+                target_offset = seek(target_offset, &mut reader)?;
+
+                // This can overlap what it's writing, so has to go a byte at a time.
+                for _ in 0..length {
+                    let byte = *patched.get(target_offset).ok_or(OUT_OF_RANGE)?;
+                    patched.push(byte);
+                    target_offset += 1;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if patched.len() != target_size || crc32(&patched) != footer.target_crc32 {
+        return Err("Patched data doesn't match the BPS patch target.");
+    }
+
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::roms::patches::test::{encode_number, with_footer};
+
+    fn create_patch(source: &[u8], target: &[u8], actions: &[u8]) -> Vec<u8> {
+        let mut patch = BPS_MAGIC.to_vec();
+        patch.extend(encode_number(source.len()));
+        patch.extend(encode_number(target.len()));
+        patch.extend(encode_number(2));
+        patch.extend(b"hi");
+        patch.extend(actions);
+
+        with_footer(patch, source, target)
+    }
+
+    fn action(action: usize, length: usize) -> Vec<u8> {
+        encode_number(((length - 1) << 2) | action)
+    }
+
+    #[test]
+    fn test_apply_runs_actions() {
+        let source = [0x00, 0x11, 0x22, 0x33];
+        let target = [0x00, 0x11, 0xAA, 0x22, 0x33, 0xAA, 0x22, 0x33, 0xAA];
+        let actions = [
+            action(SOURCE_READ, 2),
+            action(TARGET_READ, 1),
+            vec![0xAA],
+            action(SOURCE_COPY, 2),
+            encode_number(2 << 1),
+            // Copies from the start of the third byte, running into what it writes.
+            action(TARGET_COPY, 4),
+            encode_number(2 << 1),
+        ]
+        .concat();
+
+        let patch = create_patch(&source, &target, &actions);
+
+        assert_eq!(Ok(target.to_vec()), apply(&source, &patch));
+    }
+
+    #[test]
+    fn test_apply_seeks_backwards() {
+        let source = [0x00, 0x11];
+        let target = [0x11, 0x00];
+        let actions = [
+            action(SOURCE_COPY, 1),
+            encode_number(1 << 1),
+            action(SOURCE_COPY, 1),
+            encode_number((2 << 1) | 1),
+        ]
+        .concat();
+
+        let patch = create_patch(&source, &target, &actions);
+
+        assert_eq!(Ok(target.to_vec()), apply(&source, &patch));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_invalid_magic() {
+        assert_eq!(Err("Incorrect BPS magic in patch."), apply(&[], b"BPS2"));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_wrong_source() {
+        let patch = create_patch(&[0x00], &[0x00], &action(SOURCE_READ, 1));
+
+        assert_eq!(
+            Err("Data doesn't match the BPS patch source."),
+            apply(&[0x01], &patch)
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_out_of_range_read() {
+        let patch = create_patch(&[0x00], &[0x00, 0x00], &action(SOURCE_READ, 2));
+
+        assert_eq!(Err("BPS patch reads out of range."), apply(&[0x00], &patch));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_wrong_target() {
+        let patch = create_patch(&[0x00], &[0x01], &action(SOURCE_READ, 1));
+
+        assert_eq!(
+            Err("Patched data doesn't match the BPS patch target."),
+            apply(&[0x00], &patch)
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_huge_target_size() {
+        let mut patch = BPS_MAGIC.to_vec();
+        patch.extend(encode_number(1));
+        patch.extend(encode_number(MAX_TARGET_SIZE_BYTES + 1));
+        let patch = with_footer(patch, &[0x00], &[]);
+
+        assert_eq!(
+            Err("BPS patch target is too large."),
+            apply(&[0x00], &patch)
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_write_past_target_size() {
+        let patch = create_patch(
+            &[0x00],
+            &[0x00],
+            &[action(TARGET_READ, 2), vec![0, 0]].concat(),
+        );
+
+        assert_eq!(
+            Err("BPS patch writes past its target size."),
+            apply(&[0x00], &patch)
+        );
+    }
+}
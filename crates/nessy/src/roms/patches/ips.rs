@@ -0,0 +1,175 @@
+// The IPS magic - PATCH
+const IPS_MAGIC: [u8; 5] = *b"PATCH";
+
+// The IPS footer - EOF
+const IPS_FOOTER: [u8; 3] = *b"EOF";
+
+// Offset which would read as the footer, so records can never start here.
+const FOOTER_OFFSET: usize = 0x454F46;
+
+// Offsets are 24 bits, so IPS can't reach any further than 16MB.
+const MAX_OFFSET: usize = 0xFFFFFF;
+
+// Record sizes are 16 bits, and a size of zero marks a run-length encoded record.
+const MAX_RECORD_SIZE: usize = 0xFFFF;
+
+pub fn is_ips_patch(patch: &[u8]) -> bool {
+    patch.starts_with(&IPS_MAGIC)
+}
+
+// Applies an IPS patch to `data`, growing it if the patch writes past the end.
+pub fn apply(data: &[u8], patch: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if !is_ips_patch(patch) {
+        return Err("Incorrect IPS magic in patch.");
+    }
+
+    let mut patched = data.to_vec();
+    let mut position = IPS_MAGIC.len();
+
+    let mut take = |length: usize| -> Result<&[u8], &'static str> {
+        let bytes = patch
+            .get(position..position + length)
+            .ok_or("Truncated record in IPS patch.")?;
+        position += length;
+        Ok(bytes)
+    };
+
+    loop {
+        let offset = take(3)?;
+
+        if offset == IPS_FOOTER {
+            return Ok(patched);
+        }
+
+        let offset = u32::from_be_bytes([0, offset[0], offset[1], offset[2]]) as usize;
+        let size = take(2)?;
+        let size = u16::from_be_bytes([size[0], size[1]]) as usize;
+
+        let record = if size == 0 {
+            let rle = take(3)?;
+            let count = u16::from_be_bytes([rle[0], rle[1]]) as usize;
+            vec![rle[2]; count]
+        } else {
+            take(size)?.to_vec()
+        };
+
+        if patched.len() < offset + record.len() {
+            patched.resize(offset + record.len(), 0);
+        }
+
+        patched[offset..offset + record.len()].copy_from_slice(&record);
+    }
+}
+
+// Creates an IPS patch which turns `original` into `modified`. IPS can't shrink data, so
+// `modified` should be at least as long as `original`.
+pub fn create(original: &[u8], modified: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if modified.len() > MAX_OFFSET {
+        return Err("Data is too large for an IPS patch.");
+    }
+
+    let differs = |offset: usize| original.get(offset) != Some(&modified[offset]);
+
+    let mut patch = IPS_MAGIC.to_vec();
+    let mut offset = 0;
+
+    while offset < modified.len() {
+        if !differs(offset) {
+            offset += 1;
+            continue;
+        }
+
+        // Back up a byte rather than write a record the patcher would read as the footer.
+        let start = if offset == FOOTER_OFFSET {
+            offset - 1
+        } else {
+            offset
+        };
+
+        let mut end = offset;
+        while end < modified.len() && end - start < MAX_RECORD_SIZE && differs(end) {
+            end += 1;
+        }
+
+        patch.extend(&(start as u32).to_be_bytes()[1..]);
+        patch.extend(((end - start) as u16).to_be_bytes());
+        patch.extend(&modified[start..end]);
+
+        offset = end;
+    }
+
+    patch.extend(IPS_FOOTER);
+
+    Ok(patch)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_writes_records() {
+        let patch = [
+            b"PATCH".as_slice(),
+            &[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB],
+            &[0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x03, 0xCC],
+            b"EOF",
+        ]
+        .concat();
+
+        let result = apply(&[0; 4], &patch);
+
+        assert_eq!(Ok(vec![0x00, 0xAA, 0xBB, 0x00, 0xCC, 0xCC, 0xCC]), result);
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_invalid_magic() {
+        assert_eq!(
+            Err("Incorrect IPS magic in patch."),
+            apply(&[0; 4], b"PATCJ")
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_truncated_patch() {
+        let patch = [b"PATCH".as_slice(), &[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA]].concat();
+
+        assert_eq!(
+            Err("Truncated record in IPS patch."),
+            apply(&[0; 4], &patch)
+        );
+    }
+
+    #[test]
+    fn test_create_round_trips() {
+        let original = vec![0x00; 0x100];
+        let mut modified = original.clone();
+        modified[0x10] = 0xAA;
+        modified[0x11] = 0xBB;
+        modified[0xFF] = 0xCC;
+        modified.extend([0xDD; 4]);
+
+        let patch = create(&original, &modified).unwrap();
+
+        assert_eq!(Ok(modified), apply(&original, &patch));
+    }
+
+    #[test]
+    fn test_create_returns_empty_patch_given_same_data() {
+        let data = vec![0x55; 0x10];
+
+        assert_eq!(Ok(b"PATCHEOF".to_vec()), create(&data, &data));
+    }
+
+    #[test]
+    fn test_create_avoids_footer_offset() {
+        let original = vec![0x00; FOOTER_OFFSET + 1];
+        let mut modified = original.clone();
+        modified[FOOTER_OFFSET] = 0xAA;
+
+        let patch = create(&original, &modified).unwrap();
+
+        assert_eq!([0x45, 0x4F, 0x45], patch[5..8]);
+        assert_eq!(Ok(modified), apply(&original, &patch));
+    }
+}
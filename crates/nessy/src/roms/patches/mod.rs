@@ -0,0 +1,186 @@
+use crate::roms::crc32::crc32;
+
+pub mod bps;
+pub mod ips;
+pub mod ups;
+
+// UPS and BPS both end with CRC-32s of the source, the target and the patch before it.
+const FOOTER_SIZE_BYTES: usize = 12;
+
+// Far bigger than any NES ROM, so a target size past this is a corrupt or hostile patch.
+pub(super) const MAX_TARGET_SIZE_BYTES: usize = 16 * 1024 * 1024;
+
+// Applies an IPS, UPS or BPS patch to `data`, picking the format from the patch's magic.
+pub fn apply(data: &[u8], patch: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if ips::is_ips_patch(patch) {
+        ips::apply(data, patch)
+    } else if ups::is_ups_patch(patch) {
+        ups::apply(data, patch)
+    } else if bps::is_bps_patch(patch) {
+        bps::apply(data, patch)
+    } else {
+        Err("Unknown patch format.")
+    }
+}
+
+pub(super) struct Footer {
+    pub source_crc32: u32,
+    pub target_crc32: u32,
+}
+
+// Reads the footer, checking the patch against its own CRC first.
+pub(super) fn read_footer(
+    patch: &[u8],
+    corrupt_error: &'static str,
+) -> Result<Footer, &'static str> {
+    if patch.len() < FOOTER_SIZE_BYTES {
+        return Err(corrupt_error);
+    }
+
+    let footer = &patch[patch.len() - FOOTER_SIZE_BYTES..];
+    let crc = |index: usize| u32::from_le_bytes(footer[index..index + 4].try_into().unwrap());
+
+    if crc32(&patch[..patch.len() - 4]) != crc(8) {
+        return Err(corrupt_error);
+    }
+
+    Ok(Footer {
+        source_crc32: crc(0),
+        target_crc32: crc(4),
+    })
+}
+
+// Reads through the body of a UPS or BPS patch, stopping short of the footer.
+pub(super) struct PatchReader<'a> {
+    body: &'a [u8],
+    position: usize,
+    truncated_error: &'static str,
+}
+
+impl<'a> PatchReader<'a> {
+    pub fn new(patch: &'a [u8], start: usize, truncated_error: &'static str) -> Self {
+        PatchReader {
+            body: &patch[..patch.len().saturating_sub(FOOTER_SIZE_BYTES)],
+            position: start,
+            truncated_error,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.position >= self.body.len()
+    }
+
+    pub fn byte(&mut self) -> Result<u8, &'static str> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], &'static str> {
+        let bytes = self
+            .body
+            .get(self.position..self.position + length)
+            .ok_or(self.truncated_error)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    // Numbers are 7 bits a byte, with the top bit marking the last byte. Each further byte
+    // also adds one to what came before, so there's only one way to write any number.
+    pub fn number(&mut self) -> Result<usize, &'static str> {
+        let mut number = 0usize;
+        let mut shift = 1usize;
+
+        loop {
+            let byte = self.byte()?;
+            number = (byte as usize & 0x7F)
+                .checked_mul(shift)
+                .and_then(|value| number.checked_add(value))
+                .ok_or(self.truncated_error)?;
+
+            if byte & 0x80 != 0 {
+                return Ok(number);
+            }
+
+            shift = shift.checked_shl(7).ok_or(self.truncated_error)?;
+            number = number.checked_add(shift).ok_or(self.truncated_error)?;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    pub(in crate::roms::patches) fn encode_number(mut number: usize) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        loop {
+            let byte = (number & 0x7F) as u8;
+            number >>= 7;
+
+            if number == 0 {
+                bytes.push(byte | 0x80);
+                return bytes;
+            }
+
+            bytes.push(byte);
+            number -= 1;
+        }
+    }
+
+    // Adds the footer UPS and BPS patches finish with.
+    pub(in crate::roms::patches) fn with_footer(
+        mut patch: Vec<u8>,
+        source: &[u8],
+        target: &[u8],
+    ) -> Vec<u8> {
+        patch.extend(crc32(source).to_le_bytes());
+        patch.extend(crc32(target).to_le_bytes());
+        patch.extend(crc32(&patch).to_le_bytes());
+        patch
+    }
+
+    #[parameterized]
+    #[case(0, vec![0x80])]
+    #[case(0x7F, vec![0xFF])]
+    #[case(0x80, vec![0x00, 0x80])]
+    #[case(0x407F, vec![0x7F, 0xFF])]
+    #[case(0x4080, vec![0x00, 0x00, 0x80])]
+    fn test_number_reads_encoding(number: usize, bytes: Vec<u8>) {
+        assert_eq!(bytes, encode_number(number));
+
+        let patch = [bytes, vec![0; FOOTER_SIZE_BYTES]].concat();
+        let mut reader = PatchReader::new(&patch, 0, "Truncated.");
+
+        assert_eq!(Ok(number), reader.number());
+        assert!(reader.is_done());
+    }
+
+    #[test]
+    fn test_number_returns_error_given_truncated_patch() {
+        let patch = [vec![0x00], vec![0; FOOTER_SIZE_BYTES]].concat();
+
+        assert_eq!(
+            Err("Truncated."),
+            PatchReader::new(&patch, 0, "Truncated.").number()
+        );
+    }
+
+    #[test]
+    fn test_read_footer_returns_error_given_corrupt_patch() {
+        let mut patch = with_footer(b"UPS1".to_vec(), &[], &[]);
+        patch[0] = b'X';
+
+        assert!(matches!(read_footer(&patch, "Corrupt."), Err("Corrupt.")));
+    }
+
+    #[test]
+    fn test_apply_picks_format_from_magic() {
+        assert_eq!(
+            Ok(vec![0x00, 0xAA]),
+            apply(&[0x00], b"PATCH\x00\x00\x01\x00\x01\xAAEOF")
+        );
+        assert_eq!(Err("Unknown patch format."), apply(&[0x00], b"NOPE"));
+    }
+}
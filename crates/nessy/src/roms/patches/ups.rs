@@ -0,0 +1,138 @@
+use crate::roms::{
+    crc32::crc32,
+    patches::{MAX_TARGET_SIZE_BYTES, PatchReader, read_footer},
+};
+
+// The UPS magic - UPS1
+const UPS_MAGIC: [u8; 4] = *b"UPS1";
+
+pub fn is_ups_patch(patch: &[u8]) -> bool {
+    patch.starts_with(&UPS_MAGIC)
+}
+
+// Applies a UPS patch to `data`. Hunks XOR the source, so both the source and the result
+// are checked against the CRCs in the patch.
+pub fn apply(data: &[u8], patch: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if !is_ups_patch(patch) {
+        return Err("Incorrect UPS magic in patch.");
+    }
+
+    let footer = read_footer(patch, "UPS patch is corrupt.")?;
+
+    if crc32(data) != footer.source_crc32 {
+        return Err("Data doesn't match the UPS patch source.");
+    }
+
+    let mut reader = PatchReader::new(patch, UPS_MAGIC.len(), "Truncated UPS patch.");
+    let _source_size = reader.number()?;
+    let target_size = reader.number()?;
+
+    if target_size > MAX_TARGET_SIZE_BYTES {
+        return Err("UPS patch target is too large.");
+    }
+
+    let mut patched = data.to_vec();
+    patched.resize(target_size, 0);
+
+    let mut position = 0;
+
+    while !reader.is_done() {
+        position += reader.number()?;
+
+        // XOR bytes run until a zero, which also steps over one unchanged byte.
+        loop {
+            let byte = reader.byte()?;
+            position += 1;
+
+            if byte == 0 {
+                break;
+            }
+
+            if let Some(target) = patched.get_mut(position - 1) {
+                *target ^= byte;
+            }
+        }
+    }
+
+    if crc32(&patched) != footer.target_crc32 {
+        return Err("Patched data doesn't match the UPS patch target.");
+    }
+
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::roms::patches::test::{encode_number, with_footer};
+
+    fn create_patch(source: &[u8], target: &[u8], hunks: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut patch = UPS_MAGIC.to_vec();
+        patch.extend(encode_number(source.len()));
+        patch.extend(encode_number(target.len()));
+
+        for (skip, xor) in hunks {
+            patch.extend(encode_number(*skip));
+            patch.extend(*xor);
+            patch.push(0x00);
+        }
+
+        with_footer(patch, source, target)
+    }
+
+    #[test]
+    fn test_apply_xors_hunks() {
+        // The zero ending each hunk steps over a byte, so the second hunk only skips one.
+        let source = [0x00, 0x11, 0x22, 0x33, 0x44];
+        let target = [0x00, 0x11, 0xAA, 0x33, 0x44, 0xBB];
+        let patch = create_patch(&source, &target, &[(2, &[0x22 ^ 0xAA]), (1, &[0xBB])]);
+
+        assert_eq!(Ok(target.to_vec()), apply(&source, &patch));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_invalid_magic() {
+        assert_eq!(Err("Incorrect UPS magic in patch."), apply(&[], b"UPS2"));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_wrong_source() {
+        let patch = create_patch(&[0x00], &[0x01], &[(0, &[0x01])]);
+
+        assert_eq!(
+            Err("Data doesn't match the UPS patch source."),
+            apply(&[0x02], &patch)
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_wrong_target() {
+        let patch = create_patch(&[0x00], &[0x01], &[(0, &[0x02])]);
+
+        assert_eq!(
+            Err("Patched data doesn't match the UPS patch target."),
+            apply(&[0x00], &patch)
+        );
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_corrupt_patch() {
+        let mut patch = create_patch(&[0x00], &[0x01], &[(0, &[0x01])]);
+        patch[5] ^= 0xFF;
+
+        assert_eq!(Err("UPS patch is corrupt."), apply(&[0x00], &patch));
+    }
+
+    #[test]
+    fn test_apply_returns_error_given_huge_target_size() {
+        let mut patch = UPS_MAGIC.to_vec();
+        patch.extend(encode_number(1));
+        patch.extend(encode_number(MAX_TARGET_SIZE_BYTES + 1));
+        let patch = with_footer(patch, &[0x00], &[]);
+
+        assert_eq!(
+            Err("UPS patch target is too large."),
+            apply(&[0x00], &patch)
+        );
+    }
+}
@@ -0,0 +1,308 @@
+// Plain 6502 programs, as opposed to cartridges: raw binaries, Intel HEX and Motorola
+// S-records, which get copied straight into RAM rather than going through a mapper.
+
+#[derive(Debug, PartialEq)]
+pub struct Segment {
+    pub address: u16,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Program {
+    segments: Vec<Segment>,
+    start_address: u16,
+}
+
+// Intel HEX record types.
+const HEX_DATA: u8 = 0x00;
+const HEX_END_OF_FILE: u8 = 0x01;
+const HEX_EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const HEX_START_SEGMENT_ADDRESS: u8 = 0x03;
+const HEX_EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const HEX_START_LINEAR_ADDRESS: u8 = 0x05;
+
+const HEX_OUTSIDE_64KB: &str = "Intel HEX address is outside 64KB.";
+const S_RECORD_OUTSIDE_64KB: &str = "S-record address is outside 64KB.";
+
+impl Program {
+    // Picks the format from the first character: ':' for Intel HEX, 'S' and a digit for an
+    // S-record, or anything else as a raw binary loaded at `load_address`.
+    pub fn load(data: &[u8], load_address: u16) -> Result<Program, &'static str> {
+        match data {
+            [b':', ..] => Self::from_intel_hex(as_text(data)?),
+            [b'S', b'0'..=b'9', ..] => Self::from_s_record(as_text(data)?),
+            _ => Self::from_binary(data, load_address),
+        }
+    }
+
+    // A raw binary starts running from where it's loaded.
+    pub fn from_binary(data: &[u8], load_address: u16) -> Result<Program, &'static str> {
+        if load_address as usize + data.len() > 0x10000 {
+            return Err("Program doesn't fit in 64KB.");
+        }
+
+        Ok(Program {
+            segments: vec![Segment {
+                address: load_address,
+                data: data.to_vec(),
+            }],
+            start_address: load_address,
+        })
+    }
+
+    pub fn from_intel_hex(text: &str) -> Result<Program, &'static str> {
+        let mut segments = vec![];
+        let mut start_address = None;
+        let mut base_address = 0usize;
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let record = line
+                .strip_prefix(':')
+                .ok_or("Intel HEX record doesn't start with ':'.")?;
+            let bytes = decode_hex(record).ok_or("Invalid hex digits in Intel HEX record.")?;
+
+            if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+                return Err("Intel HEX record has the wrong length.");
+            }
+
+            if bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) != 0 {
+                return Err("Intel HEX record has the wrong checksum.");
+            }
+
+            let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as usize;
+            let data = &bytes[4..bytes.len() - 1];
+
+            match bytes[3] {
+                HEX_DATA => segments.push(create_segment(
+                    base_address + offset,
+                    data,
+                    HEX_OUTSIDE_64KB,
+                )?),
+                HEX_END_OF_FILE => break,
+                HEX_EXTENDED_SEGMENT_ADDRESS if data.len() == 2 => {
+                    base_address = (u16::from_be_bytes([data[0], data[1]]) as usize) << 4
+                }
+                HEX_EXTENDED_LINEAR_ADDRESS if data.len() == 2 => {
+                    base_address = (u16::from_be_bytes([data[0], data[1]]) as usize) << 16
+                }
+                HEX_START_SEGMENT_ADDRESS if data.len() == 4 => {
+                    let segment = u16::from_be_bytes([data[0], data[1]]) as usize;
+                    let offset = u16::from_be_bytes([data[2], data[3]]) as usize;
+                    start_address = Some(to_address((segment << 4) + offset, HEX_OUTSIDE_64KB)?);
+                }
+                HEX_START_LINEAR_ADDRESS if data.len() == 4 => {
+                    let address = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+                    start_address = Some(to_address(address as usize, HEX_OUTSIDE_64KB)?);
+                }
+                _ => return Err("Unsupported Intel HEX record."),
+            }
+        }
+
+        Self::from_segments(segments, start_address)
+    }
+
+    pub fn from_s_record(text: &str) -> Result<Program, &'static str> {
+        let mut segments = vec![];
+        let mut start_address = None;
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (record_type, record) = match line.as_bytes() {
+                [b'S', record_type @ b'0'..=b'9', ..] => (record_type - b'0', &line[2..]),
+                _ => return Err("S-record doesn't start with 'S' and its type."),
+            };
+
+            let bytes = decode_hex(record).ok_or("Invalid hex digits in S-record.")?;
+
+            if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+                return Err("S-record has the wrong length.");
+            }
+
+            if bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) != 0xFF {
+                return Err("S-record has the wrong checksum.");
+            }
+
+            let address_size = match record_type {
+                0 | 1 | 5 | 9 => 2,
+                2 | 6 | 8 => 3,
+                3 | 7 => 4,
+                _ => return Err("Unsupported S-record."),
+            };
+
+            if bytes.len() < address_size + 2 {
+                return Err("S-record has the wrong length.");
+            }
+
+            let address = bytes[1..=address_size]
+                .iter()
+                .fold(0usize, |address, &byte| (address << 8) | byte as usize);
+            let data = &bytes[address_size + 1..bytes.len() - 1];
+
+            match record_type {
+                1..=3 => segments.push(create_segment(address, data, S_RECORD_OUTSIDE_64KB)?),
+                7..=9 => start_address = Some(to_address(address, S_RECORD_OUTSIDE_64KB)?),
+                // Headers and record counts don't carry anything to load.
+                _ => {}
+            }
+        }
+
+        Self::from_segments(segments, start_address)
+    }
+
+    // Without a start address, the program runs from the first thing it loads.
+    fn from_segments(
+        segments: Vec<Segment>,
+        start_address: Option<u16>,
+    ) -> Result<Program, &'static str> {
+        let start_address = match (start_address, segments.first()) {
+            (Some(address), _) => address,
+            (None, Some(segment)) => segment.address,
+            (None, None) => return Err("Program has nothing to load."),
+        };
+
+        Ok(Program {
+            segments,
+            start_address,
+        })
+    }
+
+    pub fn with_start_address(self, start_address: u16) -> Program {
+        Program {
+            start_address,
+            ..self
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn start_address(&self) -> u16 {
+        self.start_address
+    }
+}
+
+fn as_text(data: &[u8]) -> Result<&str, &'static str> {
+    std::str::from_utf8(data).map_err(|_| "Program text isn't valid UTF-8.")
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn to_address(address: usize, error: &'static str) -> Result<u16, &'static str> {
+    u16::try_from(address).map_err(|_| error)
+}
+
+// Segments have to fit below the top of memory, rather than wrap around it.
+fn create_segment(
+    address: usize,
+    data: &[u8],
+    error: &'static str,
+) -> Result<Segment, &'static str> {
+    to_address(address + data.len().saturating_sub(1), error)?;
+
+    Ok(Segment {
+        address: address as u16,
+        data: data.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn segment(address: u16, data: &[u8]) -> Segment {
+        Segment {
+            address,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_load_reads_binary_at_load_address() {
+        let program = Program::load(&[0xA9, 0x01], 0x0600).unwrap();
+
+        assert_eq!(&[segment(0x0600, &[0xA9, 0x01])], program.segments());
+        assert_eq!(0x0600, program.start_address());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_binary_past_end_of_memory() {
+        assert_eq!(
+            Err("Program doesn't fit in 64KB."),
+            Program::load(&[0; 3], 0xFFFE)
+        );
+    }
+
+    #[test]
+    fn test_load_reads_intel_hex() {
+        let text = ":03060000A901004D\n:02FFFC000006FD\n:0400000500000600F1\n:00000001FF\n";
+        let program = Program::load(text.as_bytes(), 0).unwrap();
+
+        assert_eq!(
+            &[
+                segment(0x0600, &[0xA9, 0x01, 0x00]),
+                segment(0xFFFC, &[0x00, 0x06])
+            ],
+            program.segments()
+        );
+        assert_eq!(0x0600, program.start_address());
+    }
+
+    #[test]
+    fn test_load_reads_intel_hex_with_extended_address() {
+        let program = Program::load(b":020000040000FA\n:01800000EA95\n:00000001FF", 0).unwrap();
+
+        assert_eq!(&[segment(0x8000, &[0xEA])], program.segments());
+        assert_eq!(0x8000, program.start_address());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_intel_hex_outside_64kb() {
+        assert_eq!(
+            Err("Intel HEX address is outside 64KB."),
+            Program::load(b":020000040001F9\n:01000000EA15\n", 0)
+        );
+    }
+
+    #[test]
+    fn test_load_returns_error_given_bad_intel_hex_checksum() {
+        assert_eq!(
+            Err("Intel HEX record has the wrong checksum."),
+            Program::load(b":01800000EA96", 0)
+        );
+    }
+
+    #[test]
+    fn test_load_reads_s_record() {
+        let text = "S00600004844521B\nS1060600A9010049\nS9030600F6\n";
+        let program = Program::load(text.as_bytes(), 0).unwrap();
+
+        assert_eq!(&[segment(0x0600, &[0xA9, 0x01, 0x00])], program.segments());
+        assert_eq!(0x0600, program.start_address());
+    }
+
+    #[test]
+    fn test_load_returns_error_given_bad_s_record_checksum() {
+        assert_eq!(
+            Err("S-record has the wrong checksum."),
+            Program::load(b"S1060600A9010048", 0)
+        );
+    }
+
+    #[test]
+    fn test_with_start_address_overrides_start() {
+        let program = Program::load(&[0xEA], 0x0000)
+            .unwrap()
+            .with_start_address(0x0400);
+
+        assert_eq!(0x0400, program.start_address());
+    }
+}
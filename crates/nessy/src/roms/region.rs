@@ -0,0 +1,55 @@
+// The TV system a game was made for, which decides the CPU/PPU timing it expects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    Ntsc,
+    Pal,
+    // Runs on either, usually by checking the timing at boot.
+    MultiRegion,
+    // The Russian famiclones, PAL output with NTSC-like CPU timing.
+    Dendy,
+}
+
+impl Region {
+    // iNES 1.0 keeps the TV system in bit 0 of F9, which few dumps ever set.
+    pub fn from_ines(flag_byte_9: u8) -> Self {
+        if flag_byte_9 & 0b1 != 0 {
+            Region::Pal
+        } else {
+            Region::Ntsc
+        }
+    }
+
+    // NES 2.0 keeps the CPU/PPU timing in the lower two bits of F12.
+    pub fn from_nes_2_0(flag_byte_12: u8) -> Self {
+        match flag_byte_12 & 0b11 {
+            0 => Region::Ntsc,
+            1 => Region::Pal,
+            2 => Region::MultiRegion,
+            _ => Region::Dendy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    #[parameterized]
+    #[case(0b0000_0000, Region::Ntsc)]
+    #[case(0b0000_0001, Region::Pal)]
+    #[case(0b1111_1110, Region::Ntsc)]
+    fn test_from_ines(flag_byte_9: u8, expected: Region) {
+        assert_eq!(expected, Region::from_ines(flag_byte_9));
+    }
+
+    #[parameterized]
+    #[case(0b0000_0000, Region::Ntsc)]
+    #[case(0b0000_0001, Region::Pal)]
+    #[case(0b0000_0010, Region::MultiRegion)]
+    #[case(0b1111_0011, Region::Dendy)]
+    fn test_from_nes_2_0(flag_byte_12: u8, expected: Region) {
+        assert_eq!(expected, Region::from_nes_2_0(flag_byte_12));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::roms::{
+    ROM,
+    crc32::crc32,
+    database,
+    loader::{CHR_RAM_DEFAULT_SIZE_BYTES, Metadata},
+    mappers,
+    mirroring::Mirroring,
+    region::Region,
+};
+
+// The UNIF magic - UNIF
+pub const UNIF_MAGIC: [u8; 4] = [0x55, 0x4E, 0x49, 0x46];
+
+// Size of the header in bytes, the magic and revision followed by padding.
+const HEADER_SIZE_BYTES: usize = 32;
+
+// Each chunk starts with a four character ID and a little endian length.
+const CHUNK_HEADER_SIZE_BYTES: usize = 8;
+
+// UNIF describes the board by name rather than number, with the name usually prefixed by
+// who made it. Only boards we have a mapper for are listed.
+fn mapper_for_board(board_name: &str) -> Option<u8> {
+    let board = match board_name.split_once('-') {
+        Some(("NES" | "HVC" | "UNL" | "BTL", board)) => board,
+        _ => board_name,
+    };
+
+    match board {
+        "NROM" | "NROM-128" | "NROM-256" | "RROM" | "RROM-128" => Some(mappers::nrom::MAPPER_ID),
+        "SAROM" | "SBROM" | "SCROM" | "SEROM" | "SGROM" | "SKROM" | "SLROM" | "SNROM" => {
+            Some(mappers::mmc1::MAPPER_ID)
+        }
+        "PNROM" | "PEEOROM" => Some(mappers::mmc2::MAPPER_ID),
+        "FJROM" | "FKROM" => Some(mappers::mmc4::MAPPER_ID),
+        "BTR" | "JLROM" | "JSROM" => Some(mappers::fme7::MAPPER_ID),
+        "NAMCOT-163" => Some(mappers::namco_163::MAPPER_ID),
+        _ => None,
+    }
+}
+
+fn parse_mirroring(data: u8) -> Mirroring {
+    match data {
+        1 => Mirroring::Vertical,
+        2 => Mirroring::SingleScreenLower,
+        3 => Mirroring::SingleScreenUpper,
+        4 => Mirroring::FourScreen,
+        // 5 leaves it to the mapper, which sets its own regardless.
+        _ => Mirroring::Horizontal,
+    }
+}
+
+fn parse_region(data: u8) -> Region {
+    match data {
+        1 => Region::Pal,
+        2 => Region::MultiRegion,
+        _ => Region::Ntsc,
+    }
+}
+
+pub fn load(data: &[u8]) -> Result<ROM, &str> {
+    if !data.starts_with(&UNIF_MAGIC) {
+        return Err("Incorrect UNIF magic in ROM.");
+    }
+
+    let mut board_name = None;
+    let mut mirroring = Mirroring::Horizontal;
+    let mut has_battery_ram = false;
+    let mut region = Region::Ntsc;
+
+    // PRG0 - PRGF and CHR0 - CHRF are concatenated in order of their number, not file order.
+    let mut prg_chunks: [&[u8]; 16] = [&[]; 16];
+    let mut chr_chunks: [&[u8]; 16] = [&[]; 16];
+
+    let mut offset = HEADER_SIZE_BYTES;
+
+    while offset < data.len() {
+        let chunk_header = data
+            .get(offset..offset + CHUNK_HEADER_SIZE_BYTES)
+            .ok_or("Truncated chunk in UNIF ROM.")?;
+
+        let id = &chunk_header[0..4];
+        let length = u32::from_le_bytes([
+            chunk_header[4],
+            chunk_header[5],
+            chunk_header[6],
+            chunk_header[7],
+        ]) as usize;
+
+        let chunk_start = offset + CHUNK_HEADER_SIZE_BYTES;
+        let chunk = data
+            .get(chunk_start..chunk_start + length)
+            .ok_or("Truncated chunk in UNIF ROM.")?;
+
+        match id {
+            b"MAPR" => {
+                let name = chunk.split(|&byte| byte == 0).next().unwrap_or_default();
+                board_name = Some(String::from_utf8_lossy(name).into_owned());
+            }
+            b"MIRR" if !chunk.is_empty() => mirroring = parse_mirroring(chunk[0]),
+            b"BATR" if !chunk.is_empty() => has_battery_ram = chunk[0] != 0,
+            b"TVCI" if !chunk.is_empty() => region = parse_region(chunk[0]),
+            [b'P', b'R', b'G', index] | [b'C', b'H', b'R', index] => {
+                let index = (*index as char)
+                    .to_digit(16)
+                    .ok_or("Unknown ROM chunk in UNIF ROM.")? as usize;
+
+                if id[0] == b'P' {
+                    prg_chunks[index] = chunk;
+                } else {
+                    chr_chunks[index] = chunk;
+                }
+            }
+            // Names, dumper info and the like aren't needed to run the game.
+            _ => {}
+        }
+
+        offset = chunk_start + length;
+    }
+
+    let board_name = board_name.ok_or("No board name in UNIF ROM.")?;
+    let mapper = mapper_for_board(&board_name).ok_or("Unsupported board in UNIF ROM.")?;
+
+    let mut metadata = Metadata::new(mapper, mirroring, has_battery_ram, region);
+
+    let prg_rom = prg_chunks.concat();
+    let chr_rom = chr_chunks.concat();
+
+    if prg_rom.is_empty() {
+        return Err("No PRG ROM in UNIF ROM.");
+    }
+
+    let corrections = database::lookup(crc32(&[prg_rom.as_slice(), &chr_rom].concat()))
+        .map(|game| database::correct(&mut metadata, game))
+        .unwrap_or_default();
+
+    let has_chr_ram = chr_rom.is_empty();
+
+    let chr_rom = if has_chr_ram {
+        vec![0; CHR_RAM_DEFAULT_SIZE_BYTES]
+    } else {
+        chr_rom
+    };
+
+    Ok(ROM {
+        metadata,
+        prg_rom,
+        chr_rom,
+        has_chr_ram,
+        corrections,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use sif::parameterized;
+
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((data.len() as u32).to_le_bytes());
+        chunk.extend(data);
+        chunk
+    }
+
+    fn create_unif(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = UNIF_MAGIC.to_vec();
+        data.extend(7u32.to_le_bytes());
+        data.extend([0; 24]);
+        data.extend(chunks.concat());
+        data
+    }
+
+    #[parameterized]
+    #[case("NES-NROM-256", Some(0))]
+    #[case("NROM", Some(0))]
+    #[case("NES-PNROM", Some(9))]
+    #[case("HVC-FKROM", Some(10))]
+    #[case("NES-BTR", Some(69))]
+    #[case("NAMCOT-163", Some(19))]
+    #[case("NES-SNROM", Some(1))]
+    #[case("NES-UNROM", None)]
+    fn test_mapper_for_board(board_name: &str, expected: Option<u8>) {
+        assert_eq!(expected, mapper_for_board(board_name));
+    }
+
+    #[test]
+    fn test_load_returns_rom() {
+        let data = create_unif(&[
+            chunk(b"MAPR", b"NES-PNROM\0"),
+            chunk(b"NAME", b"Test\0"),
+            chunk(b"PRG1", &[0xBB; 0x4000]),
+            chunk(b"PRG0", &[0xAA; 0x4000]),
+            chunk(b"CHR0", &[0x55; 0x2000]),
+            chunk(b"MIRR", &[1]),
+            chunk(b"BATR", &[1]),
+            chunk(b"TVCI", &[1]),
+        ]);
+
+        let rom = load(&data).unwrap();
+
+        assert_eq!(9, rom.metadata().mapper());
+        assert_eq!(Mirroring::Vertical, rom.metadata().mirroring());
+        assert!(rom.metadata().has_battery_ram());
+        assert_eq!(Region::Pal, rom.metadata().region());
+
+        assert_eq!(0x8000, rom.program_rom().len());
+        assert!(rom.program_rom()[..0x4000].iter().all(|x| x == &0xAA));
+        assert!(rom.program_rom()[0x4000..].iter().all(|x| x == &0xBB));
+
+        assert!(rom.character_rom().iter().all(|x| x == &0x55));
+        assert!(!rom.has_character_ram());
+    }
+
+    #[test]
+    fn test_load_allocates_chr_ram_given_no_chr_chunks() {
+        let data = create_unif(&[
+            chunk(b"MAPR", b"NES-NROM-128\0"),
+            chunk(b"PRG0", &[0xAA; 0x4000]),
+        ]);
+
+        let rom = load(&data).unwrap();
+
+        assert!(rom.has_character_ram());
+        assert_eq!(CHR_RAM_DEFAULT_SIZE_BYTES, rom.character_rom().len());
+    }
+
+    #[parameterized]
+    #[case(vec![chunk(b"PRG0", &[0xAA; 0x10])], "No board name in UNIF ROM.")]
+    #[case(vec![chunk(b"MAPR", b"NES-UNROM\0")], "Unsupported board in UNIF ROM.")]
+    #[case(vec![chunk(b"MAPR", b"NES-NROM\0")], "No PRG ROM in UNIF ROM.")]
+    #[case(vec![b"PRG0\x10\0\0\0\xAA".to_vec()], "Truncated chunk in UNIF ROM.")]
+    fn test_load_returns_error(chunks: Vec<Vec<u8>>, expected: &str) {
+        let data = create_unif(&chunks);
+
+        assert!(load(&data).is_err_and(|err| err == expected));
+    }
+
+    #[test]
+    fn test_load_returns_error_given_invalid_magic() {
+        assert!(
+            load(&[0x4E, 0x45, 0x53, 0x1A]).is_err_and(|err| err == "Incorrect UNIF magic in ROM.")
+        );
+    }
+}
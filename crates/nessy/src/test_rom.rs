@@ -0,0 +1,268 @@
+use crate::{
+    cpus::mos_6502::cpu::{Mos6502, RESET_VECTOR},
+    interpret_result::InstructionResult,
+};
+
+// blargg's test ROMs report through PRG RAM: a status byte at $6000, a signature at
+// $6001 - $6003 to show the status is real, then the result as NUL terminated text.
+const STATUS_ADDRESS: u16 = 0x6000;
+const SIGNATURE_ADDRESS: u16 = 0x6001;
+const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const MESSAGE_ADDRESS: u16 = 0x6004;
+const MESSAGE_END: u16 = 0x7FFF;
+
+const STATUS_RUNNING: u8 = 0x80;
+const STATUS_RESET_REQUESTED: u8 = 0x81;
+
+// The ROM wants reset pressed at least 100ms after asking for it.
+const RESET_DELAY_CYCLES: u64 = 1_789_773 / 10;
+
+#[derive(Debug, PartialEq)]
+pub enum TestRomOutcome {
+    Passed,
+    // The ROM's result code, which says which of its tests failed.
+    Failed(u8),
+    TimedOut,
+    // The CPU stopped on something it couldn't run.
+    Halted(InstructionResult),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TestRomResult {
+    pub outcome: TestRomOutcome,
+    pub message: String,
+}
+
+// Runs a test ROM which has already been loaded until it reports a result, pressing reset
+// whenever it asks, or until it's spent `max_cycles`.
+pub fn run(cpu: &mut Mos6502, max_cycles: u64) -> TestRomResult {
+    let mut last_status = None;
+    let mut reset_at = None;
+    // PRG RAM reads 0 before the ROM gets going, which would look like a pass.
+    let mut started = false;
+
+    let outcome = loop {
+        if cpu.cycles >= max_cycles {
+            break TestRomOutcome::TimedOut;
+        }
+
+        match cpu.step() {
+            InstructionResult::Ok => {}
+            result => break TestRomOutcome::Halted(result),
+        }
+
+        if reset_at.is_some_and(|reset_at| cpu.cycles >= reset_at) {
+            reset_at = None;
+            cpu.reset();
+            cpu.program_counter = cpu.bus.read_u16(RESET_VECTOR);
+        }
+
+        let status = status(cpu);
+
+        match status {
+            None => {}
+            Some(STATUS_RUNNING) => started = true,
+            Some(STATUS_RESET_REQUESTED) => {
+                started = true;
+
+                // Only the first sight of the request counts, as it stays put until the ROM
+                // is back up after the reset.
+                if last_status != status {
+                    reset_at = Some(cpu.cycles + RESET_DELAY_CYCLES);
+                }
+            }
+            Some(_) if !started => {}
+            Some(0) => break TestRomOutcome::Passed,
+            Some(code) => break TestRomOutcome::Failed(code),
+        }
+
+        last_status = status;
+    };
+
+    TestRomResult {
+        outcome,
+        message: message(cpu),
+    }
+}
+
+// The status byte, once the ROM has written the signature to show it's using the protocol.
+fn status(cpu: &Mos6502) -> Option<u8> {
+    let has_signature = SIGNATURE
+        .iter()
+        .enumerate()
+        .all(|(index, &byte)| cpu.bus.read(SIGNATURE_ADDRESS + index as u16) == byte);
+
+    has_signature.then(|| cpu.bus.read(STATUS_ADDRESS))
+}
+
+fn message(cpu: &Mos6502) -> String {
+    let text: Vec<u8> = (MESSAGE_ADDRESS..=MESSAGE_END)
+        .map(|address| cpu.bus.read(address))
+        .take_while(|&byte| byte != 0)
+        .collect();
+
+    String::from_utf8_lossy(&text).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::roms::loader::Loader;
+
+    const PRG_ROM_SIZE: usize = 0x4000;
+
+    // LDA #value, STA address
+    fn store(value: u8, address: u16) -> Vec<u8> {
+        let [lo, hi] = address.to_le_bytes();
+        vec![0xA9, value, 0x8D, lo, hi]
+    }
+
+    fn signature() -> Vec<u8> {
+        [
+            store(0xDE, 0x6001),
+            store(0xB0, 0x6002),
+            store(0x61, 0x6003),
+        ]
+        .concat()
+    }
+
+    fn message(text: &[u8]) -> Vec<u8> {
+        text.iter()
+            .enumerate()
+            .flat_map(|(index, &byte)| store(byte, MESSAGE_ADDRESS + index as u16))
+            .collect()
+    }
+
+    // Builds an NROM test ROM running `code` from $8000, which is left spinning on a
+    // `JMP *` if it ever finishes.
+    fn create_cpu(code: &[u8]) -> Mos6502 {
+        let end = 0x8000 + code.len() as u16;
+        let [lo, hi] = end.to_le_bytes();
+
+        let mut prg_rom = vec![0; PRG_ROM_SIZE];
+        prg_rom[..code.len()].copy_from_slice(code);
+        prg_rom[code.len()..code.len() + 3].copy_from_slice(&[0x4C, lo, hi]);
+        prg_rom[PRG_ROM_SIZE - 4..PRG_ROM_SIZE - 2].copy_from_slice(&[0x00, 0x80]);
+
+        let data = [
+            b"NES\x1A\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".as_slice(),
+            &prg_rom,
+            &[0; 0x2000],
+        ]
+        .concat();
+
+        let mut cpu = Mos6502::default();
+        cpu.load_program(Loader::load(&data).unwrap());
+        cpu
+    }
+
+    #[test]
+    fn test_run_returns_passed_with_message() {
+        let code = [
+            signature(),
+            store(STATUS_RUNNING, STATUS_ADDRESS),
+            message(b"Passed\n"),
+            store(0x00, STATUS_ADDRESS),
+        ]
+        .concat();
+        let mut cpu = create_cpu(&code);
+
+        assert_eq!(
+            TestRomResult {
+                outcome: TestRomOutcome::Passed,
+                message: "Passed".to_string(),
+            },
+            run(&mut cpu, 100_000)
+        );
+    }
+
+    #[test]
+    fn test_run_returns_failed_with_code() {
+        let code = [
+            store(STATUS_RUNNING, STATUS_ADDRESS),
+            signature(),
+            message(b"Failed #3"),
+            store(0x03, STATUS_ADDRESS),
+        ]
+        .concat();
+        let mut cpu = create_cpu(&code);
+
+        assert_eq!(
+            TestRomResult {
+                outcome: TestRomOutcome::Failed(3),
+                message: "Failed #3".to_string(),
+            },
+            run(&mut cpu, 100_000)
+        );
+    }
+
+    #[test]
+    fn test_run_ignores_status_without_signature() {
+        let code = [
+            store(STATUS_RUNNING, STATUS_ADDRESS),
+            store(0x03, STATUS_ADDRESS),
+        ]
+        .concat();
+        let mut cpu = create_cpu(&code);
+
+        assert_eq!(TestRomOutcome::TimedOut, run(&mut cpu, 100_000).outcome);
+    }
+
+    #[test]
+    fn test_run_presses_reset_when_asked() {
+        // Counts its boots in RAM, which survives the reset, and passes on the second.
+        let code = [
+            vec![0xE6, 0xF0, 0xA5, 0xF0, 0xC9, 0x02, 0xF0, 0x17],
+            signature(),
+            store(STATUS_RESET_REQUESTED, STATUS_ADDRESS),
+            vec![0x4C, 0x1C, 0x80],
+            store(0x00, STATUS_ADDRESS),
+        ]
+        .concat();
+        let mut cpu = create_cpu(&code);
+
+        let result = run(&mut cpu, RESET_DELAY_CYCLES * 2);
+
+        assert_eq!(TestRomOutcome::Passed, result.outcome);
+        assert_eq!(2, cpu.bus.read(0xF0));
+        assert!(cpu.cycles >= RESET_DELAY_CYCLES);
+    }
+
+    #[test]
+    fn test_run_continues_through_brk() {
+        let mut cpu = create_cpu(&[0x00, 0x00]);
+
+        // The IRQ vector is left at $0000, so the handler goes in RAM.
+        let handler = [
+            signature(),
+            store(STATUS_RUNNING, STATUS_ADDRESS),
+            store(0x00, STATUS_ADDRESS),
+        ]
+        .concat();
+        cpu.bus.write_slice(0x0000, &handler);
+
+        assert_eq!(TestRomOutcome::Passed, run(&mut cpu, 100_000).outcome);
+    }
+
+    #[test]
+    fn test_run_waits_on_vblank_and_nmi() {
+        let code = [
+            // BIT $2002, BPL -5: wait for vblank.
+            vec![0x2C, 0x02, 0x20, 0x10, 0xFB],
+            store(0x80, 0x2000),
+            // LDA $F0, BEQ -4: wait for the NMI handler to run.
+            vec![0xA5, 0xF0, 0xF0, 0xFC],
+            signature(),
+            store(STATUS_RUNNING, STATUS_ADDRESS),
+            store(0x00, STATUS_ADDRESS),
+        ]
+        .concat();
+        let mut cpu = create_cpu(&code);
+
+        // The NMI vector is left at $0000, so the handler (INC $F0, RTI) goes in RAM.
+        cpu.bus.write_slice(0x0000, &[0xE6, 0xF0, 0x40]);
+
+        assert_eq!(TestRomOutcome::Passed, run(&mut cpu, 100_000).outcome);
+        assert_eq!(1, cpu.bus.read(0xF0));
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+
+use nessy::{
+    nes::NES,
+    roms::loader::Loader,
+    test_rom::{self, TestRomOutcome},
+};
+
+use crate::integration::utils::get_asset_file_path;
+
+// A minute of NTSC time, which is longer than any of the ROMs take.
+const MAX_CYCLES: u64 = 1_789_773 * 60;
+
+// The ROMs aren't bundled, so put whichever are wanted (instr_test-v5, cpu_interrupts and so
+// on, as single ROMs rather than the multi-ROM sets) in assets/blargg. The PPU only has its
+// vblank timing so far, so ROMs which check anything drawn won't pass.
+#[test]
+#[ignore = "needs blargg test ROMs in assets/blargg"]
+fn test_blargg_test_roms() {
+    let roms_path = get_asset_file_path("blargg");
+
+    let mut rom_paths: Vec<_> = match fs::read_dir(&roms_path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "nes"))
+            .collect(),
+        Err(reason) => panic!("Failed to read {}: {}.", roms_path.display(), reason),
+    };
+    rom_paths.sort();
+
+    assert!(!rom_paths.is_empty(), "No ROMs in {}.", roms_path.display());
+
+    let failures: Vec<String> = rom_paths
+        .iter()
+        .filter_map(|rom_path| {
+            let rom_data = fs::read(rom_path).unwrap();
+            let rom = Loader::load(&rom_data).unwrap();
+
+            let mut nes = NES::default();
+            nes.cpu.load_program(rom);
+
+            let result = test_rom::run(&mut nes.cpu, MAX_CYCLES);
+            let name = rom_path.file_name().unwrap().to_string_lossy();
+
+            println!("{}: {:?}\n{}\n", name, result.outcome, result.message);
+
+            (result.outcome != TestRomOutcome::Passed)
+                .then(|| format!("{}: {:?} {}", name, result.outcome, result.message))
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "Failed:\n{}", failures.join("\n"));
+}
+
+// LDA #value, STA address
+fn store(value: u8, address: u16) -> Vec<u8> {
+    let [lo, hi] = address.to_le_bytes();
+    vec![0xA9, value, 0x8D, lo, hi]
+}
+
+// A stand in for the real ROMs on the board most of them use: MMC1, with 64KB of PRG. The
+// fixed bank at $C000 shifts bank 1 into $8000 and jumps there, which reports a pass in the
+// same way blargg's ROMs do.
+fn create_mmc1_test_rom() -> Vec<u8> {
+    const BANK_SIZE: usize = 0x4000;
+
+    let select_bank_1 = [1, 0, 0, 0, 0]
+        .into_iter()
+        .flat_map(|bit| store(bit, 0xE000))
+        .collect::<Vec<_>>();
+    let fixed_code = [select_bank_1, vec![0x4C, 0x00, 0x80]].concat();
+
+    let message = b"MMC1 passed\n"
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &byte)| store(byte, 0x6004 + index as u16))
+        .collect::<Vec<_>>();
+    let switched_code = [
+        store(0x80, 0x6000),
+        store(0xDE, 0x6001),
+        store(0xB0, 0x6002),
+        store(0x61, 0x6003),
+        message,
+        store(0x00, 0x6000),
+    ]
+    .concat();
+
+    let mut prg_rom = vec![0; BANK_SIZE * 4];
+    prg_rom[BANK_SIZE..BANK_SIZE + switched_code.len()].copy_from_slice(&switched_code);
+    prg_rom[BANK_SIZE * 3..BANK_SIZE * 3 + fixed_code.len()].copy_from_slice(&fixed_code);
+    prg_rom[BANK_SIZE * 4 - 4..BANK_SIZE * 4 - 2].copy_from_slice(&[0x00, 0xC0]);
+
+    [
+        b"NES\x1A\x04\x01\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00".as_slice(),
+        &prg_rom,
+        &[0; 0x2000],
+    ]
+    .concat()
+}
+
+#[test]
+fn test_mmc1_test_rom_passes() {
+    let rom = Loader::load(&create_mmc1_test_rom()).unwrap();
+
+    let mut nes = NES::default();
+    nes.cpu.load_program(rom);
+
+    let result = test_rom::run(&mut nes.cpu, MAX_CYCLES);
+
+    assert_eq!(TestRomOutcome::Passed, result.outcome);
+    assert_eq!("MMC1 passed", result.message);
+}
@@ -0,0 +1,48 @@
+use std::fs;
+
+use nessy::roms::{
+    database::HeaderCorrection, loader::Loader, mirroring::Mirroring, region::Region,
+};
+
+use crate::integration::utils::get_asset_file_path;
+
+#[test]
+fn test_database_corrects_nestest_header() {
+    let mut rom_data = fs::read(get_asset_file_path("nestest/nestest.nes")).unwrap();
+
+    // Vertical mirroring, battery RAM and mapper 9, none of which nestest's board has.
+    rom_data[6] = 0b1001_0011;
+
+    let rom = Loader::load(&rom_data).unwrap();
+
+    assert_eq!(
+        [
+            HeaderCorrection::Mapper {
+                header: 9,
+                database: 0
+            },
+            HeaderCorrection::Mirroring {
+                header: Mirroring::Vertical,
+                database: Mirroring::Horizontal
+            },
+            HeaderCorrection::BatteryRam {
+                header: true,
+                database: false
+            },
+        ],
+        rom.corrections()
+    );
+    assert_eq!(0, rom.metadata().mapper());
+    assert_eq!(Mirroring::Horizontal, rom.metadata().mirroring());
+    assert!(!rom.metadata().has_battery_ram());
+    assert_eq!(Region::Ntsc, rom.metadata().region());
+}
+
+#[test]
+fn test_database_leaves_correct_nestest_header_alone() {
+    let rom_data = fs::read(get_asset_file_path("nestest/nestest.nes")).unwrap();
+
+    let rom = Loader::load(&rom_data).unwrap();
+
+    assert!(rom.corrections().is_empty());
+}
@@ -0,0 +1,127 @@
+use std::fs;
+
+use nessy::{
+    cpus::mos_6502::{cpu::Mos6502, flat_bus::FlatBus, variant::Variant},
+    interpret_result::InstructionResult,
+    roms::program::Program,
+};
+
+use sif::parameterized;
+
+use crate::integration::utils::get_asset_file_path;
+
+// The binaries aren't bundled: build them from https://github.com/Klaus2m5/6502_65C02_functional_tests
+// and drop them in assets/klaus_dormann. The functional test is the prebuilt one from its
+// bin_files directory, a full 64KB image started at $0400.
+const FUNCTIONAL_TEST_START: u16 = 0x0400;
+const FUNCTIONAL_TEST_SUCCESS: u16 = 0x3469;
+
+// The decimal test is assembled as a 64KB image too, but starts at $0200 and reports
+// through its ERROR byte once it traps.
+const DECIMAL_TEST_START: u16 = 0x0200;
+const DECIMAL_TEST_ERROR: u16 = 0x000B;
+
+// Both tests get through in well under this.
+const MAX_INSTRUCTIONS: u64 = 100_000_000;
+
+fn build_cpu(binary_name: &str, start_address: u16, variant: Variant) -> Mos6502 {
+    let binary_path = get_asset_file_path(&format!("klaus_dormann/{}", binary_name));
+
+    let binary = match fs::read(&binary_path) {
+        Ok(data) => data,
+        Err(reason) => panic!("Failed to read {}: {}.", binary_path.display(), reason),
+    };
+
+    build_cpu_from_binary(&binary, start_address, variant)
+}
+
+fn build_cpu_from_binary(binary: &[u8], start_address: u16, variant: Variant) -> Mos6502 {
+    let program = Program::from_binary(binary, 0x0000)
+        .unwrap()
+        .with_start_address(start_address);
+
+    let mut cpu = Mos6502::new(Box::new(FlatBus::default())).with_variant(variant);
+    cpu.load_program_image(&program);
+    cpu
+}
+
+// Runs until the program traps itself in a jump or branch to its own address, which is how
+// these tests finish, passing or not.
+fn run_until_trapped(cpu: &mut Mos6502) -> u16 {
+    for _ in 0..MAX_INSTRUCTIONS {
+        let address = cpu.program_counter;
+
+        match cpu.step() {
+            InstructionResult::Ok if cpu.program_counter == address => return address,
+            // The functional test checks BRK goes through its vector, so it's carried on from.
+            InstructionResult::Ok | InstructionResult::EndProgram => {}
+            result => panic!("Stopped with {:?} at 0x{:04X}.", result, address),
+        }
+    }
+
+    panic!("Never trapped, ended at 0x{:04X}.", cpu.program_counter);
+}
+
+// Until the real binaries are bundled, this is all that runs: a loop which counts down and
+// then traps itself the same two ways the tests do.
+#[parameterized]
+#[case(&[0x4C, 0x05, 0x04])] // JMP $0405
+#[case(&[0xF0, 0xFE])] // BEQ $0405
+fn test_run_until_trapped_finds_the_trap(trap: &[u8]) {
+    let code = [
+        0xA2, 0x05, // LDX #$05
+        0xCA, // DEX
+        0xD0, 0xFD, // BNE $0402
+    ];
+    let binary = [&[0; FUNCTIONAL_TEST_START as usize][..], &code, trap].concat();
+    let mut cpu = build_cpu_from_binary(&binary, FUNCTIONAL_TEST_START, Variant::Nmos6502);
+
+    assert_eq!(0x0405, run_until_trapped(&mut cpu));
+    assert_eq!(0, cpu.registers.x);
+}
+
+#[test]
+#[ignore = "needs 6502_functional_test.bin in assets/klaus_dormann"]
+fn test_klaus_dormann_functional_test() {
+    let mut cpu = build_cpu(
+        "6502_functional_test.bin",
+        FUNCTIONAL_TEST_START,
+        Variant::Nmos6502,
+    );
+
+    let trap_address = run_until_trapped(&mut cpu);
+
+    // Any other trap is the test that failed, which the listing has the details of.
+    assert_eq!(
+        FUNCTIONAL_TEST_SUCCESS, trap_address,
+        "Failed at 0x{:04X}.",
+        trap_address
+    );
+}
+
+// Bruce Clark's decimal test checks every ADC and SBC result and flag, valid BCD or not. It's
+// assembled once per chip, with `cputype = 0` for the NMOS 6502 and `cputype = 1` for the 65C02.
+fn run_decimal_test(binary_name: &str, variant: Variant) {
+    let mut cpu = build_cpu(binary_name, DECIMAL_TEST_START, variant);
+
+    let trap_address = run_until_trapped(&mut cpu);
+
+    assert_eq!(
+        0,
+        cpu.bus.read(DECIMAL_TEST_ERROR),
+        "Failed, trapped at 0x{:04X}.",
+        trap_address
+    );
+}
+
+#[test]
+#[ignore = "needs 6502_decimal_test.bin in assets/klaus_dormann"]
+fn test_klaus_dormann_decimal_test() {
+    run_decimal_test("6502_decimal_test.bin", Variant::Nmos6502);
+}
+
+#[test]
+#[ignore = "needs 65C02_decimal_test.bin in assets/klaus_dormann"]
+fn test_klaus_dormann_65c02_decimal_test() {
+    run_decimal_test("65C02_decimal_test.bin", Variant::Wdc65C02);
+}
@@ -1,3 +1,6 @@
+mod blargg;
+mod database;
+mod klaus_dormann;
 mod nestest;
-mod tracing_policy;
+mod single_step_tests;
 mod utils;
@@ -0,0 +1,315 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fs,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    rc::Rc,
+};
+
+use nessy::{
+    cpus::mos_6502::{
+        bus::MemoryBus,
+        cpu::{Mos6502, Registers},
+        opcode::NMOS_6502_OPCODES,
+        status::Flags,
+        variant::Variant,
+    },
+    roms::{ROM, mappers::Mapper},
+};
+use serde::Deserialize;
+
+use crate::integration::utils::get_asset_file_path;
+
+// Point this at the 6502/v1 directory of https://github.com/SingleStepTests/65x02, which
+// has a JSON file of test cases for each opcode, named like 6d.json. They're for a stock NMOS
+// 6502, decimal mode and all.
+const TESTS_DIR_VARIABLE: &str = "SINGLE_STEP_TESTS_DIR";
+
+// A few cases written by hand in the same format, so the harness runs without the full set.
+const BUNDLED_TESTS_DIR: &str = "single_step_tests";
+
+#[derive(Debug, Deserialize)]
+struct CpuSnapshot {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestCase {
+    name: String,
+    initial: CpuSnapshot,
+    #[serde(rename = "final")]
+    expected: CpuSnapshot,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+type BusActivity = Rc<RefCell<Vec<(u16, u8, String)>>>;
+
+// 64KB of RAM which keeps a note of every read and write, to compare against the bus
+// activity in the tests.
+struct RecordingBus {
+    memory: Box<[u8; 0x10000]>,
+    activity: BusActivity,
+}
+
+impl MemoryBus for RecordingBus {
+    fn read(&self, address: u16) -> u8 {
+        let data = self.memory[address as usize];
+        self.activity
+            .borrow_mut()
+            .push((address, data, "read".to_string()));
+        data
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        self.memory[address as usize] = data;
+        self.activity
+            .borrow_mut()
+            .push((address, data, "write".to_string()));
+    }
+
+    fn write_slice(&mut self, start_address: u16, data: &[u8]) {
+        for (offset, &byte) in data.iter().enumerate() {
+            self.write(start_address.wrapping_add(offset as u16), byte);
+        }
+    }
+
+    fn read_u16(&self, address: u16) -> u16 {
+        let lo_byte = self.read(address) as u16;
+        let hi_byte = self.read(address.wrapping_add(1)) as u16;
+        (hi_byte << 8) | lo_byte
+    }
+
+    fn write_u16(&mut self, address: u16, data: u16) {
+        self.write(address, (data & 0xFF) as u8);
+        self.write(address.wrapping_add(1), (data >> 8) as u8);
+    }
+
+    fn insert_rom(&mut self, _rom: ROM) {}
+
+    fn insert_cartridge(&mut self, _cartridge: Box<dyn Mapper>) {}
+}
+
+#[derive(Default)]
+struct OpcodeReport {
+    cases: usize,
+    register_failures: usize,
+    ram_failures: usize,
+    bus_failures: usize,
+    panics: usize,
+    first_failure: Option<String>,
+}
+
+impl OpcodeReport {
+    fn failures(&self) -> usize {
+        self.register_failures + self.ram_failures + self.bus_failures + self.panics
+    }
+
+    fn fail(&mut self, case: &TestCase, reason: String) {
+        self.first_failure
+            .get_or_insert_with(|| format!("\"{}\": {}", case.name, reason));
+    }
+}
+
+fn build_cpu(case: &TestCase) -> (Mos6502, BusActivity) {
+    let activity = BusActivity::default();
+    let mut memory = Box::new([0; 0x10000]);
+
+    for &(address, data) in &case.initial.ram {
+        memory[address as usize] = data;
+    }
+
+    let mut cpu = Mos6502::new(Box::new(RecordingBus {
+        memory,
+        activity: activity.clone(),
+    }))
+    .with_variant(Variant::Nmos6502);
+
+    cpu.program_counter = case.initial.pc;
+    cpu.stack_pointer = case.initial.s;
+    cpu.status = Flags::from_bits_truncate(case.initial.p);
+    cpu.registers = Registers {
+        a: case.initial.a,
+        x: case.initial.x,
+        y: case.initial.y,
+    };
+
+    (cpu, activity)
+}
+
+fn run_case(case: &TestCase, report: &mut OpcodeReport) {
+    let (mut cpu, activity) = build_cpu(case);
+
+    let stepped = panic::catch_unwind(AssertUnwindSafe(|| cpu.step()));
+
+    let Ok(result) = stepped else {
+        report.panics += 1;
+        report.fail(case, "panicked".to_string());
+        return;
+    };
+
+    let expected = &case.expected;
+
+    // Pulled out before reading RAM back, which would be recorded too.
+    let bus_activity = activity.take();
+
+    let registers = (
+        cpu.program_counter,
+        cpu.stack_pointer,
+        cpu.registers.a,
+        cpu.registers.x,
+        cpu.registers.y,
+        cpu.status.bits(),
+    );
+    let expected_registers = (
+        expected.pc,
+        expected.s,
+        expected.a,
+        expected.x,
+        expected.y,
+        expected.p,
+    );
+
+    if registers != expected_registers {
+        report.register_failures += 1;
+        report.fail(
+            case,
+            format!(
+                "(pc, s, a, x, y, p) were {:02X?}, expected {:02X?}, step gave {:?}",
+                registers, expected_registers, result
+            ),
+        );
+    }
+
+    if let Some(&(address, data)) = expected
+        .ram
+        .iter()
+        .find(|&&(address, data)| cpu.bus.read(address) != data)
+    {
+        report.ram_failures += 1;
+        report.fail(
+            case,
+            format!(
+                "RAM at 0x{:04X} was 0x{:02X}, expected 0x{:02X}",
+                address,
+                cpu.bus.read(address),
+                data
+            ),
+        );
+    }
+
+    if bus_activity != case.cycles {
+        report.bus_failures += 1;
+        report.fail(
+            case,
+            format!(
+                "bus activity was {:02X?}, expected {:02X?}",
+                bus_activity, case.cycles
+            ),
+        );
+    }
+}
+
+fn run_opcode(tests_dir: &Path, opcode: u8) -> OpcodeReport {
+    let tests_path = tests_dir.join(format!("{:02x}.json", opcode));
+
+    let json = match fs::read_to_string(&tests_path) {
+        Ok(json) => json,
+        Err(reason) => panic!("Failed to read {}: {}.", tests_path.display(), reason),
+    };
+
+    let cases: Vec<TestCase> = match serde_json::from_str(&json) {
+        Ok(cases) => cases,
+        Err(reason) => panic!("Failed to parse {}: {}.", tests_path.display(), reason),
+    };
+
+    let mut report = OpcodeReport {
+        cases: cases.len(),
+        ..Default::default()
+    };
+
+    for case in &cases {
+        run_case(case, &mut report);
+    }
+
+    report
+}
+
+// Runs the tests for each of `opcodes`, printing a line for every one which fails.
+fn run_opcodes(tests_dir: &Path, opcodes: &[u8]) {
+    // The opcodes under test panic when they go wrong, which would bury the report.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let reports: HashMap<u8, OpcodeReport> = opcodes
+        .iter()
+        .map(|&opcode| (opcode, run_opcode(tests_dir, opcode)))
+        .collect();
+
+    panic::set_hook(default_hook);
+
+    let mut failed_opcodes = 0;
+
+    for opcode in opcodes {
+        let report = &reports[opcode];
+
+        if report.failures() == 0 {
+            continue;
+        }
+
+        failed_opcodes += 1;
+
+        println!(
+            "0x{:02X} {}: {} cases, failing {} on registers, {} on RAM, {} on bus activity and {} with panics. First: {}",
+            opcode,
+            NMOS_6502_OPCODES[opcode].mnemonic,
+            report.cases,
+            report.register_failures,
+            report.ram_failures,
+            report.bus_failures,
+            report.panics,
+            report.first_failure.as_deref().unwrap_or_default()
+        );
+    }
+
+    assert_eq!(0, failed_opcodes, "Opcodes failed their SingleStepTests.");
+}
+
+#[test]
+fn test_bundled_single_step_tests() {
+    let tests_dir = get_asset_file_path(BUNDLED_TESTS_DIR);
+
+    let mut opcodes: Vec<u8> = fs::read_dir(&tests_dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let stem = path.file_stem().unwrap().to_str().unwrap().to_string();
+            u8::from_str_radix(&stem, 16).unwrap()
+        })
+        .collect();
+    opcodes.sort();
+
+    assert!(!opcodes.is_empty());
+
+    run_opcodes(&tests_dir, &opcodes);
+}
+
+#[test]
+#[ignore = "needs SINGLE_STEP_TESTS_DIR pointing at the SingleStepTests 6502 JSON files"]
+fn test_single_step_tests() {
+    let tests_dir = match env::var(TESTS_DIR_VARIABLE) {
+        Ok(dir) => dir,
+        Err(_) => panic!("Set {} to run the SingleStepTests.", TESTS_DIR_VARIABLE),
+    };
+
+    let mut opcodes: Vec<u8> = NMOS_6502_OPCODES.keys().copied().collect();
+    opcodes.sort();
+
+    run_opcodes(Path::new(&tests_dir), &opcodes);
+}
@@ -0,0 +1,152 @@
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+// Files which can be loaded out of a zip, when no entry is asked for.
+const ROM_EXTENSIONS: [&str; 4] = ["nes", "unf", "fds", "nsf"];
+
+// Splits `path` into the archive and the entry asked for, e.g. "games.zip#Game.nes".
+pub fn split_entry(path: &str) -> (&str, Option<&str>) {
+    match path.rsplit_once('#') {
+        Some((archive, entry)) if archive.to_lowercase().ends_with(".zip") => {
+            (archive, Some(entry))
+        }
+        _ => (path, None),
+    }
+}
+
+// Reads a ROM, unpacking it in memory if it's inside a zip or gzip file.
+pub fn read_rom(path: &Path, entry: Option<&str>) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|reason| format!("Failed to read ROM: {}.", reason))?;
+
+    if data.starts_with(&ZIP_MAGIC) {
+        unzip(data, entry)
+    } else if data.starts_with(&GZIP_MAGIC) {
+        gunzip(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+fn unzip(data: Vec<u8>, entry: Option<&str>) -> Result<Vec<u8>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(data)).map_err(|err| format!("Zip error: {}.", err))?;
+
+    let name = match entry {
+        Some(entry) => entry.to_string(),
+        None => archive
+            .file_names()
+            .filter(|name| {
+                Path::new(name).extension().is_some_and(|extension| {
+                    ROM_EXTENSIONS.contains(&extension.to_ascii_lowercase().to_str().unwrap_or(""))
+                })
+            })
+            .min_by_key(|name| archive.index_for_name(name))
+            .ok_or("No ROM found in zip.")?
+            .to_string(),
+    };
+
+    let mut file = archive
+        .by_name(&name)
+        .map_err(|err| format!("Zip error reading {}: {}.", name, err))?;
+
+    let mut rom = vec![];
+    file.read_to_end(&mut rom)
+        .map_err(|err| format!("Zip error reading {}: {}.", name, err))?;
+
+    Ok(rom)
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut rom = vec![];
+
+    GzDecoder::new(data)
+        .read_to_end(&mut rom)
+        .map_err(|err| format!("Gzip error: {}.", err))?;
+
+    Ok(rom)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+    use tempfile::NamedTempFile;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+
+    fn create_tempfile(data: &[u8]) -> NamedTempFile {
+        let mut tempfile = NamedTempFile::new().unwrap();
+        tempfile.write_all(data).unwrap();
+        tempfile
+    }
+
+    fn create_zip(files: &[(&str, &[u8])]) -> NamedTempFile {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+
+        for (name, data) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+
+        create_tempfile(&zip.finish().unwrap().into_inner())
+    }
+
+    #[test]
+    fn test_split_entry() {
+        assert_eq!(("games.zip", Some("a.nes")), split_entry("games.zip#a.nes"));
+        assert_eq!(("games.ZIP", Some("a.nes")), split_entry("games.ZIP#a.nes"));
+        assert_eq!(("#1.nes", None), split_entry("#1.nes"));
+    }
+
+    #[test]
+    fn test_read_rom_reads_plain_file() {
+        let file = create_tempfile(b"NES\x1A");
+
+        assert_eq!(Ok(b"NES\x1A".to_vec()), read_rom(file.path(), None));
+    }
+
+    #[test]
+    fn test_read_rom_reads_first_rom_in_zip() {
+        let file = create_zip(&[("readme.txt", b"hi"), ("b.NES", b"B"), ("a.nsf", b"A")]);
+
+        assert_eq!(Ok(b"B".to_vec()), read_rom(file.path(), None));
+    }
+
+    #[test]
+    fn test_read_rom_reads_named_entry_in_zip() {
+        let file = create_zip(&[("b.nes", b"B"), ("a.nes", b"A")]);
+
+        assert_eq!(Ok(b"A".to_vec()), read_rom(file.path(), Some("a.nes")));
+        assert!(read_rom(file.path(), Some("c.nes")).is_err());
+    }
+
+    #[test]
+    fn test_read_rom_returns_error_given_zip_without_rom() {
+        let file = create_zip(&[("readme.txt", b"hi")]);
+
+        assert_eq!(
+            Err("No ROM found in zip.".to_string()),
+            read_rom(file.path(), None)
+        );
+    }
+
+    #[test]
+    fn test_read_rom_reads_gzip() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"NES\x1A").unwrap();
+        let file = create_tempfile(&encoder.finish().unwrap());
+
+        assert_eq!(Ok(b"NES\x1A".to_vec()), read_rom(file.path(), None));
+    }
+}
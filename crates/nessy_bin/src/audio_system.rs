@@ -0,0 +1,38 @@
+use sdl2::{
+    Sdl,
+    audio::{AudioQueue, AudioSpecDesired},
+};
+
+pub struct AudioSystem {
+    queue: AudioQueue<f32>,
+}
+
+impl AudioSystem {
+    pub fn new(sdl_context: &Sdl, sample_rate: i32) -> Result<AudioSystem, String> {
+        let audio_subsystem = sdl_context.audio()?;
+
+        let desired_spec = AudioSpecDesired {
+            freq: Some(sample_rate),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let queue = audio_subsystem.open_queue::<f32, _>(None, &desired_spec)?;
+        queue.resume();
+
+        Ok(AudioSystem { queue })
+    }
+
+    pub fn queue(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.queue.queue_audio(samples)
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    // How many samples are still waiting to be played.
+    pub fn queued_samples(&self) -> u32 {
+        self.queue.size() / size_of::<f32>() as u32
+    }
+}
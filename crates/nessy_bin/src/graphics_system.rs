@@ -1,11 +1,12 @@
 use sdl2::{
-    EventPump,
+    EventPump, Sdl,
     pixels::{Color, PixelFormatEnum},
     render::{Canvas, Texture, TextureCreator},
     video::{Window, WindowContext},
 };
 
 pub struct GraphicsSystem {
+    pub sdl_context: Sdl,
     pub canvas: Canvas<Window>,
     pub event_pump: EventPump,
     pub texture_creator: TextureCreator<WindowContext>,
@@ -50,6 +51,7 @@ impl GraphicsSystem {
         let texture_creator = canvas.texture_creator();
 
         Ok(GraphicsSystem {
+            sdl_context,
             canvas,
             event_pump,
             texture_creator,
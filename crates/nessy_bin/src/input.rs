@@ -1,5 +1,5 @@
 use bitflags::bitflags;
-use nessy::cpus::mos_6502::cpu::Mos6502;
+use nessy::cpus::Cpu;
 use sdl2::{
     event::{Event, EventPollIterator},
     keyboard::Keycode,
@@ -8,13 +8,15 @@ use sdl2::{
 bitflags! {
     pub struct InputFlags: u8 {
         const Quit = 1 << 0;
+        const NextTrack = 1 << 1;
+        const PreviousTrack = 1 << 2;
     }
 }
 
 pub struct Input {}
 
 impl Input {
-    pub fn handle(cpu: &mut Mos6502, event_iter: &mut EventPollIterator) -> InputFlags {
+    pub fn handle(cpu: &mut impl Cpu, event_iter: &mut EventPollIterator) -> InputFlags {
         const INPUT_MEMORY_ADDRESS: u16 = 0xFF;
         const KEY_W_KEYCODE: u8 = 0x77;
         const KEY_A_KEYCODE: u8 = 0x61;
@@ -33,19 +35,49 @@ impl Input {
                 Event::KeyDown {
                     keycode: Some(Keycode::W),
                     ..
-                } => cpu.bus.write(INPUT_MEMORY_ADDRESS, KEY_W_KEYCODE),
+                } => cpu.bus_mut().write(INPUT_MEMORY_ADDRESS, KEY_W_KEYCODE),
                 Event::KeyDown {
                     keycode: Some(Keycode::S),
                     ..
-                } => cpu.bus.write(INPUT_MEMORY_ADDRESS, KEY_S_KEYCODE),
+                } => cpu.bus_mut().write(INPUT_MEMORY_ADDRESS, KEY_S_KEYCODE),
                 Event::KeyDown {
                     keycode: Some(Keycode::A),
                     ..
-                } => cpu.bus.write(INPUT_MEMORY_ADDRESS, KEY_A_KEYCODE),
+                } => cpu.bus_mut().write(INPUT_MEMORY_ADDRESS, KEY_A_KEYCODE),
                 Event::KeyDown {
                     keycode: Some(Keycode::D),
                     ..
-                } => cpu.bus.write(INPUT_MEMORY_ADDRESS, KEY_D_KEYCODE),
+                } => cpu.bus_mut().write(INPUT_MEMORY_ADDRESS, KEY_D_KEYCODE),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F),
+                    ..
+                } => cpu.bus_mut().switch_disk_side(),
+                _ => {}
+            }
+        }
+
+        input_flags
+    }
+
+    // The NSF player has no game to pass input to, just its own controls.
+    pub fn handle_player(event_iter: &mut EventPollIterator) -> InputFlags {
+        let mut input_flags = InputFlags::empty();
+
+        for event in event_iter {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::ESCAPE),
+                    ..
+                } => input_flags.insert(InputFlags::Quit),
+                Event::KeyDown {
+                    keycode: Some(Keycode::RIGHT),
+                    ..
+                } => input_flags.insert(InputFlags::NextTrack),
+                Event::KeyDown {
+                    keycode: Some(Keycode::LEFT),
+                    ..
+                } => input_flags.insert(InputFlags::PreviousTrack),
                 _ => {}
             }
         }
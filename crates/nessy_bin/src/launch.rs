@@ -0,0 +1,85 @@
+use crate::trace::{TRACE_FLAG, TRACE_FORMAT_FLAG};
+
+const DEFAULT_SETTINGS_PATH: &str = "./assets/settings.toml";
+
+#[derive(Debug, PartialEq)]
+pub struct LaunchOptions {
+    // A zipped ROM can pick its entry with "games.zip#Game.nes".
+    pub rom_path: String,
+    pub settings_path: String,
+}
+
+impl LaunchOptions {
+    // Parses the arguments after the program name: <rom> [settings], with any trace flags
+    // and their values skipped over as `TraceOptions` reads those.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == TRACE_FLAG || arg == TRACE_FORMAT_FLAG {
+                args.next();
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        match positional.as_slice() {
+            [rom_path] => Ok(LaunchOptions {
+                rom_path: rom_path.to_string(),
+                settings_path: DEFAULT_SETTINGS_PATH.to_string(),
+            }),
+            [rom_path, settings_path] => Ok(LaunchOptions {
+                rom_path: rom_path.to_string(),
+                settings_path: settings_path.to_string(),
+            }),
+            _ => Err(format!(
+                "Usage: nessy_bin <rom> [settings] [{} <log>] [{} <nestest|mesen|fceux>]",
+                TRACE_FLAG, TRACE_FORMAT_FLAG
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_uses_default_settings() {
+        assert_eq!(
+            Ok(LaunchOptions {
+                rom_path: "games.zip#Game.nes".to_string(),
+                settings_path: DEFAULT_SETTINGS_PATH.to_string(),
+            }),
+            LaunchOptions::parse(&args(&["games.zip#Game.nes"]))
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_trace_flags() {
+        let options = LaunchOptions::parse(&args(&[
+            "--trace",
+            "out.log",
+            "game.nes",
+            "--trace-format",
+            "mesen",
+            "settings.toml",
+        ]))
+        .unwrap();
+
+        assert_eq!("game.nes", options.rom_path);
+        assert_eq!("settings.toml", options.settings_path);
+    }
+
+    #[test]
+    fn test_parse_returns_err_given_bad_arguments() {
+        assert!(LaunchOptions::parse(&args(&[])).is_err());
+        assert!(LaunchOptions::parse(&args(&["--trace", "out.log"])).is_err());
+        assert!(LaunchOptions::parse(&args(&["a.nes", "a.toml", "b.toml"])).is_err());
+    }
+}
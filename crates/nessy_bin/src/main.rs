@@ -5,46 +5,229 @@ use std::{
     time::Duration,
 };
 
-use nessy::{nes::NES, roms::loader::Loader};
+use nessy::{
+    cpus::mos_6502::{
+        cpu::Mos6502, flat_bus::FlatBus, memory::PROGRAM_ROM_START, variant::Variant,
+    },
+    nes::NES,
+    nsf_player::NsfPlayer,
+    roms::{
+        fds::DiskImage, loader::Loader, mappers::fds::Fds, nsf::Nsf, patches, program::Program,
+    },
+};
 use rand::RngExt;
 
 use crate::{
+    audio_system::AudioSystem,
     graphics_system::{GraphicsSystem, GraphicsSystemOptions},
     input::{Input, InputFlags},
+    launch::LaunchOptions,
+    render::{RENDER_NSF_COMMAND, RenderOptions},
     renderer::Renderer,
     settings::Settings,
+    test_roms::{TEST_ROMS_COMMAND, TestRomsOptions},
+    trace::TraceOptions,
 };
 
+pub mod archive;
+pub mod audio_system;
 pub mod colour;
 pub mod graphics_system;
 pub mod input;
+pub mod launch;
+pub mod render;
 pub mod renderer;
 pub mod settings;
+pub mod test_roms;
+pub mod trace;
 
 const MEMORY_ADDRESS_RNG: u16 = 0xFE;
 
+// Writes made to a disk are kept next to it as an IPS patch, e.g. game.fds -> game.diff.
+const DISK_DIFF_EXTENSION: &str = "diff";
+
+// A patch found next to a ROM with one of these extensions is applied as it loads. Only the
+// first found is applied, in this order: BPS and UPS check both the ROM and the result
+// against CRCs, where IPS checks nothing, and they're all written against the clean ROM.
+const PATCH_EXTENSIONS: [&str; 3] = ["bps", "ups", "ips"];
+
+// Plain 6502 programs, which run on a flat 64KB of RAM instead of the NES bus.
+const PROGRAM_EXTENSIONS: [&str; 6] = ["bin", "hex", "ihx", "srec", "s19", "s28"];
+
+const NSF_SAMPLE_RATE: u32 = 44100;
+
+// Keep about this many samples queued up, which is what paces the player.
+const NSF_QUEUED_SAMPLES: u32 = NSF_SAMPLE_RATE / 10;
+
+fn play_nsf(nsf_data: &[u8], graphics_system: &mut GraphicsSystem) {
+    let nsf = match Nsf::load(nsf_data) {
+        Ok(nsf) => nsf,
+        Err(err) => panic!("NSF load error: {}", err),
+    };
+
+    let mut audio_system =
+        match AudioSystem::new(&graphics_system.sdl_context, NSF_SAMPLE_RATE as i32) {
+            Ok(v) => v,
+            Err(err) => panic!("Failed to init audio system: {}.", err),
+        };
+
+    let mut player = NsfPlayer::new(nsf, NSF_SAMPLE_RATE);
+
+    println!(
+        "Playing {} by {} ({}). Left/Right to change track.",
+        player.title(),
+        player.artist(),
+        player.copyright()
+    );
+
+    let mut track_result = player.start_track(player.track());
+
+    loop {
+        if let Err(err) = track_result {
+            panic!("NSF error: {}", err);
+        }
+
+        let input_flags = Input::handle_player(&mut graphics_system.event_pump.poll_iter());
+
+        if input_flags.contains(InputFlags::Quit) {
+            println!("Thanks for listening to Nessy!");
+            return;
+        }
+
+        if input_flags.intersects(InputFlags::NextTrack | InputFlags::PreviousTrack) {
+            track_result = if input_flags.contains(InputFlags::NextTrack) {
+                player.next_track()
+            } else {
+                player.previous_track()
+            };
+
+            audio_system.clear();
+            println!("Track {}/{}", player.track() + 1, player.track_count());
+            continue;
+        }
+
+        let samples = match player.play_frame() {
+            Ok(samples) => samples,
+            Err(err) => panic!("NSF error: {}", err),
+        };
+
+        if let Err(err) = audio_system.queue(&samples) {
+            panic!("Failed to queue audio: {}.", err);
+        }
+
+        while audio_system.queued_samples() > NSF_QUEUED_SAMPLES {
+            ::std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+fn apply_patch(rom_path: &Path, rom_data: Vec<u8>) -> Vec<u8> {
+    let found = PATCH_EXTENSIONS.iter().find_map(|extension| {
+        let patch_path = rom_path.with_extension(extension);
+        fs::read(&patch_path).ok().map(|patch| (patch_path, patch))
+    });
+
+    let Some((patch_path, patch)) = found else {
+        return rom_data;
+    };
+
+    let rom_data = match patches::apply(&rom_data, &patch) {
+        Ok(data) => data,
+        Err(err) => panic!("Patch error in {}: {}", patch_path.display(), err),
+    };
+
+    println!("Applied patch {}.", patch_path.display());
+
+    rom_data
+}
+
+fn load_disk(bios_path: Option<&str>, disk_data: &[u8], diff_path: &Path) -> Fds {
+    let Some(bios_path) = bios_path else {
+        panic!("Set `fds_bios` in the settings to play disk images.");
+    };
+
+    let bios = match fs::read(bios_path) {
+        Ok(data) => data,
+        Err(reason) => panic!("Failed to read FDS BIOS: {}.", reason),
+    };
+
+    let disk = match DiskImage::load(disk_data) {
+        Ok(disk) => disk,
+        Err(err) => panic!("Disk load error: {}", err),
+    };
+
+    let fds = match Fds::new(bios, disk) {
+        Ok(fds) => fds,
+        Err(err) => panic!("FDS error: {}", err),
+    };
+
+    match fs::read(diff_path) {
+        Ok(diff) => match fds.with_disk_diff(&diff) {
+            Ok(fds) => fds,
+            Err(err) => panic!("Disk diff error: {}", err),
+        },
+        Err(_) => fds,
+    }
+}
+
 fn main() {
     println!("Booting Nessy");
 
     let args: Vec<String> = env::args().collect();
-    let settings_path = if true {
-        "./assets/settings.toml"
-    } else {
-        &args[1]
+
+    // Headless commands run before anything SDL gets set up.
+    if args.get(1).map(String::as_str) == Some(RENDER_NSF_COMMAND) {
+        let result =
+            RenderOptions::parse(&args[2..]).and_then(|options| render::render_nsf(&options));
+
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some(TEST_ROMS_COMMAND) {
+        let result = TestRomsOptions::parse(&args[2..])
+            .and_then(|options| test_roms::run_test_roms(&options));
+
+        match result {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // `--trace out.log` logs every instruction the game runs.
+    let mut trace_logger = match TraceOptions::parse(&args[1..])
+        .and_then(|options| options.map(|options| options.create_logger()).transpose())
+    {
+        Ok(logger) => logger,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     };
 
-    let rom_path = if true {
-        "./assets/nestest.nes"
-    } else {
-        &args[2]
+    let launch_options = match LaunchOptions::parse(&args[1..]) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     };
 
     // Load emu settings:
+    let settings_path = &launch_options.settings_path;
     let settings = match Settings::new(Path::new(settings_path)) {
         Ok(s) => s,
         Err(err) => match err {
             settings::SettingsError::FileError(error) => {
-                panic!("File error while loading {}: {}.", &args[1], error)
+                panic!("File error while loading {}: {}.", settings_path, error)
             }
             settings::SettingsError::DeserialiseError(error) => {
                 panic!("DeserialiseError: {}.", error)
@@ -76,21 +259,57 @@ fn main() {
     let mut rng = rand::rng();
 
     // Load ROM file
-    let rom_data = match fs::read(rom_path) {
+    // Zipped ROMs can pick their entry with "games.zip#Game.nes".
+    let (rom_path, rom_entry) = archive::split_entry(&launch_options.rom_path);
+
+    let rom_data = match archive::read_rom(Path::new(rom_path), rom_entry) {
         Ok(data) => data,
-        Err(reason) => panic!("Failed to read ROM: {}.", reason),
+        Err(err) => panic!("{}", err),
     };
 
-    let rom = Loader::load(&rom_data);
+    let rom_data = apply_patch(Path::new(rom_path), rom_data);
 
-    if rom.is_err() {
-        panic!("ROM load error: {}", rom.err().unwrap());
+    if Nsf::is_nsf(&rom_data) {
+        play_nsf(&rom_data, &mut graphics_system);
+        return;
     }
 
     let mut nes = NES::default();
 
-    nes.cpu.load_program(rom.unwrap());
-    nes.cpu.reset();
+    let save_path = Path::new(rom_path).with_extension(DISK_DIFF_EXTENSION);
+
+    let is_program = Path::new(rom_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| PROGRAM_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+
+    if is_program {
+        let program = match Program::load(&rom_data, PROGRAM_ROM_START) {
+            Ok(program) => program,
+            Err(err) => panic!("Program load error: {}", err),
+        };
+
+        // Plain programs are written for a stock 6502, decimal mode and all.
+        nes.cpu = Mos6502::new(Box::new(FlatBus::default())).with_variant(Variant::Nmos6502);
+        nes.cpu.load_program_image(&program);
+    } else if DiskImage::is_disk_image(&rom_data) {
+        let fds = load_disk(settings.fds_bios.as_deref(), &rom_data, &save_path);
+        nes.cpu.load_cartridge(Box::new(fds));
+    } else {
+        let rom = Loader::load(&rom_data);
+
+        if rom.is_err() {
+            panic!("ROM load error: {}", rom.err().unwrap());
+        }
+
+        let rom = rom.unwrap();
+
+        for correction in rom.corrections() {
+            println!("ROM header fixed from game database: {}.", correction);
+        }
+
+        nes.cpu.load_program(rom);
+    }
 
     graphics_system.clear();
     texture.update(None, &renderer.buffer, 32 * 3).unwrap();
@@ -98,9 +317,32 @@ fn main() {
     graphics_system.swap();
 
     nes.cpu.run_with_callback(|cpu| {
+        if let Some(logger) = trace_logger.as_mut()
+            && let Err(reason) = logger.trace(cpu)
+        {
+            println!(
+                "Failed to write the trace, so it's been stopped: {}.",
+                reason
+            );
+            trace_logger = None;
+        }
+
         let input_flags = Input::handle(cpu, &mut graphics_system.event_pump.poll_iter());
 
         if input_flags.contains(InputFlags::Quit) {
+            if let Some(save_data) = cpu.bus.save_data()
+                && let Err(reason) = fs::write(&save_path, save_data)
+            {
+                println!("Failed to save to {}: {}.", save_path.display(), reason);
+            }
+
+            // Exiting skips the logger's drop, which would have flushed it.
+            if let Some(logger) = trace_logger.as_mut()
+                && let Err(reason) = logger.flush()
+            {
+                println!("Failed to write the trace: {}.", reason);
+            }
+
             println!("Thanks for playing Nessy!");
             std::process::exit(0);
         }
@@ -0,0 +1,161 @@
+use std::{fs::File, io::BufWriter, str::FromStr};
+
+use nessy::{audio::wav::write_wav, nsf_player::NsfPlayer, roms::nsf::Nsf};
+
+pub const RENDER_NSF_COMMAND: &str = "render-nsf";
+
+const DEFAULT_SECONDS: f32 = 180.0;
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, PartialEq)]
+pub struct RenderOptions {
+    pub nsf_path: String,
+    pub wav_path: String,
+    // None plays the NSF's own starting track.
+    pub track: Option<u8>,
+    pub seconds: f32,
+    pub sample_rate: u32,
+    // Stop early once the output has held still this long.
+    pub silence_seconds: Option<f32>,
+}
+
+impl RenderOptions {
+    // Parses the arguments after the command:
+    // <nsf> <wav> [--track n] [--seconds n] [--sample-rate n] [--stop-on-silence n]
+    // Tracks are numbered from 1, as players show them.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let [nsf_path, wav_path, flags @ ..] = args else {
+            return Err(format!(
+                "Usage: {} <nsf> <wav> [--track n] [--seconds n] [--sample-rate n] [--stop-on-silence n]",
+                RENDER_NSF_COMMAND
+            ));
+        };
+
+        let mut options = RenderOptions {
+            nsf_path: nsf_path.clone(),
+            wav_path: wav_path.clone(),
+            track: None,
+            seconds: DEFAULT_SECONDS,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            silence_seconds: None,
+        };
+
+        for pair in flags.chunks(2) {
+            let [flag, value] = pair else {
+                return Err(format!("Missing value for {}.", pair[0]));
+            };
+
+            match flag.as_str() {
+                "--track" => match parse_value::<u8>(flag, value)? {
+                    0 => return Err("Tracks are numbered from 1.".to_string()),
+                    track => options.track = Some(track - 1),
+                },
+                "--seconds" => options.seconds = parse_value(flag, value)?,
+                "--sample-rate" => options.sample_rate = parse_value(flag, value)?,
+                "--stop-on-silence" => options.silence_seconds = Some(parse_value(flag, value)?),
+                _ => return Err(format!("Unknown option {}.", flag)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}.", flag, value))
+}
+
+// Renders an NSF track straight to a WAV file, without opening a window or audio device.
+pub fn render_nsf(options: &RenderOptions) -> Result<(), String> {
+    let nsf_data = std::fs::read(&options.nsf_path)
+        .map_err(|reason| format!("Failed to read NSF: {}.", reason))?;
+
+    let nsf = Nsf::load(&nsf_data).map_err(|err| format!("NSF load error: {}", err))?;
+
+    let mut player = NsfPlayer::new(nsf, options.sample_rate);
+    let track = options.track.unwrap_or(player.track());
+
+    if track >= player.track_count() {
+        return Err(format!("The NSF only has {} tracks.", player.track_count()));
+    }
+
+    let samples = player
+        .render_track(track, options.seconds, options.silence_seconds)
+        .map_err(|err| format!("NSF error: {}", err))?;
+
+    let file = File::create(&options.wav_path)
+        .map_err(|reason| format!("Failed to create {}: {}.", options.wav_path, reason))?;
+
+    write_wav(&mut BufWriter::new(file), options.sample_rate, &samples)
+        .map_err(|reason| format!("Failed to write {}: {}.", options.wav_path, reason))?;
+
+    println!(
+        "Rendered track {}/{} of {} ({:.1}s) to {}.",
+        track + 1,
+        player.track_count(),
+        player.title(),
+        samples.len() as f32 / options.sample_rate as f32,
+        options.wav_path
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_uses_defaults() {
+        let options = RenderOptions::parse(&args(&["a.nsf", "a.wav"])).unwrap();
+
+        assert_eq!(
+            RenderOptions {
+                nsf_path: "a.nsf".to_string(),
+                wav_path: "a.wav".to_string(),
+                track: None,
+                seconds: DEFAULT_SECONDS,
+                sample_rate: DEFAULT_SAMPLE_RATE,
+                silence_seconds: None,
+            },
+            options
+        );
+    }
+
+    #[test]
+    fn test_parse_reads_flags() {
+        let options = RenderOptions::parse(&args(&[
+            "a.nsf",
+            "a.wav",
+            "--track",
+            "3",
+            "--seconds",
+            "30",
+            "--sample-rate",
+            "48000",
+            "--stop-on-silence",
+            "2.5",
+        ]))
+        .unwrap();
+
+        assert_eq!(Some(2), options.track);
+        assert_eq!(30.0, options.seconds);
+        assert_eq!(48000, options.sample_rate);
+        assert_eq!(Some(2.5), options.silence_seconds);
+    }
+
+    #[test]
+    fn test_parse_returns_err_given_bad_arguments() {
+        assert!(RenderOptions::parse(&args(&["a.nsf"])).is_err());
+        assert!(RenderOptions::parse(&args(&["a.nsf", "a.wav", "--track"])).is_err());
+        assert!(RenderOptions::parse(&args(&["a.nsf", "a.wav", "--track", "0"])).is_err());
+        assert!(RenderOptions::parse(&args(&["a.nsf", "a.wav", "--seconds", "x"])).is_err());
+        assert!(RenderOptions::parse(&args(&["a.nsf", "a.wav", "--loud", "1"])).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use nessy::cpus::mos_6502::cpu::Mos6502;
+use nessy::cpus::Cpu;
 
 use crate::colour::Colour;
 
@@ -15,21 +15,18 @@ impl Renderer {
         }
     }
 
-    pub fn handle(&mut self, cpu: &Mos6502) -> bool {
+    pub fn handle(&mut self, cpu: &impl Cpu) -> bool {
         let mut has_updated = false;
         let mut frame_index = 0;
 
         for i in 0x0200..0x600 {
-            let pixel_byte = cpu.bus.read(i as u16);
+            let pixel_byte = cpu.bus().read(i as u16);
             let (b1, b2, b3) = Colour::from_u8(pixel_byte).rgb();
 
-            if self.buffer[frame_index] != b1
-                && self.buffer[frame_index + 1] != b2
-                && self.buffer[frame_index + 2] != b3
-            {
-                self.buffer[frame_index] = b1;
-                self.buffer[frame_index + 1] = b2;
-                self.buffer[frame_index + 2] = b3;
+            let pixel = &mut self.buffer[frame_index..frame_index + 3];
+
+            if pixel != [b1, b2, b3] {
+                pixel.copy_from_slice(&[b1, b2, b3]);
                 has_updated = true;
             }
             frame_index += 3;
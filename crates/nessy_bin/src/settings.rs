@@ -13,6 +13,8 @@ pub struct Display {
 pub struct Settings {
     pub title: String,
     pub display: Display,
+    // Path to the Famicom Disk System BIOS, needed to play .fds disk images.
+    pub fds_bios: Option<String>,
 }
 
 #[derive(Debug)]
@@ -78,5 +80,6 @@ mod test {
         let unwrapped = settings.unwrap();
         assert_eq!(800, unwrapped.display.width);
         assert_eq!(600, unwrapped.display.height);
+        assert_eq!(None, unwrapped.fds_bios);
     }
 }
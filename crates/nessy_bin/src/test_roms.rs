@@ -0,0 +1,645 @@
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use nessy::{
+    cpus::mos_6502::cpu::Mos6502,
+    interpret_result::InstructionResult,
+    nes::NES,
+    roms::{crc32::crc32, loader::Loader},
+    test_rom::{self, TestRomOutcome, TestRomResult},
+};
+
+use crate::renderer::Renderer;
+
+pub const TEST_ROMS_COMMAND: &str = "test-roms";
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+const DEFAULT_JUNIT_PATH: &str = "test-roms.xml";
+
+const NTSC_CPU_CLOCK_HZ: u64 = 1_789_773;
+const NTSC_CPU_CYCLES_PER_FRAME: u64 = 29_781;
+
+const ROM_EXTENSIONS: [&str; 2] = ["nes", "unf"];
+
+// Next to a ROM, holds the CRC32 of the screen it shows once it has passed, in hex.
+const HASH_EXTENSION: &str = "hash";
+
+// The screen as the window draws it, from $0200 - $05FF.
+const SCREEN_WIDTH: usize = 32;
+const SCREEN_HEIGHT: usize = 32;
+const SCREEN_BYTES_PER_PIXEL: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub struct TestRomsOptions {
+    pub roms_dir: String,
+    // Emulated seconds each ROM gets before it's counted as hung.
+    pub timeout_seconds: u64,
+    pub junit_path: String,
+}
+
+impl TestRomsOptions {
+    // Parses the arguments after the command: <dir> [--timeout seconds] [--junit file]
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let [roms_dir, flags @ ..] = args else {
+            return Err(format!(
+                "Usage: {} <dir> [--timeout seconds] [--junit file]\n\
+                 ROMs report through the $6000 status protocol, or pass once their screen \
+                 matches the CRC32 in a .{} file of the same name.",
+                TEST_ROMS_COMMAND, HASH_EXTENSION
+            ));
+        };
+
+        let mut options = TestRomsOptions {
+            roms_dir: roms_dir.clone(),
+            timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+            junit_path: DEFAULT_JUNIT_PATH.to_string(),
+        };
+
+        for pair in flags.chunks(2) {
+            let [flag, value] = pair else {
+                return Err(format!("Missing value for {}.", pair[0]));
+            };
+
+            match flag.as_str() {
+                "--timeout" => {
+                    options.timeout_seconds = value
+                        .parse()
+                        .map_err(|_| format!("Invalid value for {}: {}.", flag, value))?
+                }
+                "--junit" => options.junit_path = value.clone(),
+                _ => return Err(format!("Unknown option {}.", flag)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RomStatus {
+    Passed,
+    // The ROM ran and reported a failure.
+    Failed(String),
+    // The ROM never got to report anything: it couldn't load, hung or crashed.
+    Error(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RomReport {
+    pub name: String,
+    pub status: RomStatus,
+    pub message: String,
+    pub seconds: f64,
+}
+
+type PanicHook = dyn Fn(&PanicHookInfo) + Sync + Send;
+
+// Keeps panics on this thread quiet while it's held, for crashes to be reported with the
+// rest rather than in the middle of the table. Panics on other threads still go to the
+// previous hook, which gets everything again once this is dropped, unwinding or not.
+struct QuietPanics {
+    previous: Arc<PanicHook>,
+    active: Arc<AtomicBool>,
+}
+
+impl QuietPanics {
+    fn new() -> Self {
+        let previous: Arc<PanicHook> = Arc::from(panic::take_hook());
+        let active = Arc::new(AtomicBool::new(true));
+        let quiet_thread = thread::current().id();
+
+        let hook = Arc::clone(&previous);
+        let hook_active = Arc::clone(&active);
+        panic::set_hook(Box::new(move |info| {
+            if !hook_active.load(Ordering::SeqCst) || thread::current().id() != quiet_thread {
+                hook(info);
+            }
+        }));
+
+        QuietPanics { previous, active }
+    }
+}
+
+impl Drop for QuietPanics {
+    fn drop(&mut self) {
+        self.active.store(false, Ordering::SeqCst);
+
+        // The hook can't be swapped while unwinding, but it passes everything on by now.
+        if !thread::panicking() {
+            let previous = Arc::clone(&self.previous);
+            panic::set_hook(Box::new(move |info| previous(info)));
+        }
+    }
+}
+
+// Runs every ROM in the directory headlessly, printing a summary table and writing JUnit
+// XML. Results come from the $6000 status protocol, or from the screen for ROMs with a hash
+// file. Returns whether they all passed.
+pub fn run_test_roms(options: &TestRomsOptions) -> Result<bool, String> {
+    let rom_paths = find_roms(Path::new(&options.roms_dir))?;
+    let max_cycles = options.timeout_seconds * NTSC_CPU_CLOCK_HZ;
+
+    let reports: Vec<RomReport> = {
+        let _quiet_panics = QuietPanics::new();
+
+        rom_paths
+            .iter()
+            .map(|rom_path| run_rom(rom_path, max_cycles))
+            .collect()
+    };
+
+    print!("{}", summary_table(&reports));
+
+    fs::write(&options.junit_path, junit_xml(&reports))
+        .map_err(|reason| format!("Failed to write {}: {}.", options.junit_path, reason))?;
+
+    Ok(reports
+        .iter()
+        .all(|report| report.status == RomStatus::Passed))
+}
+
+fn find_roms(roms_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(roms_dir)
+        .map_err(|reason| format!("Failed to read {}: {}.", roms_dir.display(), reason))?;
+
+    let mut rom_paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    ROM_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                })
+        })
+        .collect();
+    rom_paths.sort();
+
+    Ok(rom_paths)
+}
+
+fn run_rom(rom_path: &Path, max_cycles: u64) -> RomReport {
+    let started = Instant::now();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let expected_hash = expected_screen_hash(rom_path)?;
+        let rom_data = fs::read(rom_path).map_err(|reason| reason.to_string())?;
+        let rom = Loader::load(&rom_data).map_err(|err| err.to_string())?;
+
+        let mut nes = NES::default();
+        nes.cpu.load_program(rom);
+
+        Ok::<_, String>(match expected_hash {
+            Some(expected_hash) => run_until_screen(&mut nes.cpu, expected_hash, max_cycles),
+            None => {
+                let result = test_rom::run(&mut nes.cpu, max_cycles);
+                protocol_result(result, screen_hash(&nes.cpu))
+            }
+        })
+    }));
+
+    let (status, message) = match result {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => (
+            RomStatus::Error(format!("Failed to load: {}", err)),
+            String::new(),
+        ),
+        Err(payload) => {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+
+            (
+                RomStatus::Error(format!("Crashed: {}", reason)),
+                String::new(),
+            )
+        }
+    };
+
+    RomReport {
+        name: rom_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        status,
+        message,
+        seconds: started.elapsed().as_secs_f64(),
+    }
+}
+
+fn expected_screen_hash(rom_path: &Path) -> Result<Option<u32>, String> {
+    let hash_path = rom_path.with_extension(HASH_EXTENSION);
+
+    if !hash_path.exists() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(&hash_path).map_err(|reason| reason.to_string())?;
+
+    u32::from_str_radix(text.trim(), 16)
+        .map(Some)
+        .map_err(|_| format!("Invalid hash in {}", hash_path.display()))
+}
+
+fn protocol_result(result: TestRomResult, screen_hash: u32) -> (RomStatus, String) {
+    let status = match result.outcome {
+        TestRomOutcome::Passed => RomStatus::Passed,
+        TestRomOutcome::Failed(code) => RomStatus::Failed(format!("Failed with code {}", code)),
+        // The screen it ended on is the one to put in a hash file, if it shows its result.
+        TestRomOutcome::TimedOut => RomStatus::Error(format!(
+            "Timed out waiting for a $6000 result, screen hash {:08X}",
+            screen_hash
+        )),
+        TestRomOutcome::Halted(result) => RomStatus::Error(format!("Halted on {:?}", result)),
+    };
+
+    (status, result.message)
+}
+
+// Runs a ROM a frame at a time until its screen matches the expected hash.
+fn run_until_screen(cpu: &mut Mos6502, expected_hash: u32, max_cycles: u64) -> (RomStatus, String) {
+    let mut next_frame = 0;
+
+    loop {
+        if cpu.cycles >= next_frame {
+            next_frame = cpu.cycles + NTSC_CPU_CYCLES_PER_FRAME;
+
+            if screen_hash(cpu) == expected_hash {
+                return (RomStatus::Passed, String::new());
+            }
+        }
+
+        if cpu.cycles >= max_cycles {
+            let status = RomStatus::Failed(format!(
+                "Screen hash {:08X} didn't match {:08X}",
+                screen_hash(cpu),
+                expected_hash
+            ));
+
+            return (status, String::new());
+        }
+
+        match cpu.step() {
+            InstructionResult::Ok => {}
+            result => {
+                return (
+                    RomStatus::Error(format!("Halted on {:?}", result)),
+                    String::new(),
+                );
+            }
+        }
+    }
+}
+
+fn screen_hash(cpu: &Mos6502) -> u32 {
+    let mut renderer = Renderer::new(SCREEN_WIDTH, SCREEN_HEIGHT, SCREEN_BYTES_PER_PIXEL);
+    renderer.handle(cpu);
+
+    crc32(&renderer.buffer)
+}
+
+fn summary_table(reports: &[RomReport]) -> String {
+    let name_width = reports
+        .iter()
+        .map(|report| report.name.len())
+        .chain(["ROM".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut table = format!(
+        "{:<name_width$}  {:<6}  {:>7}  Details\n",
+        "ROM", "Result", "Time"
+    );
+
+    for report in reports {
+        let (result, details) = match &report.status {
+            RomStatus::Passed => ("PASS", ""),
+            RomStatus::Failed(reason) => ("FAIL", reason.as_str()),
+            RomStatus::Error(reason) => ("ERROR", reason.as_str()),
+        };
+
+        // Only the message's first line fits in a table.
+        let message = report.message.lines().next().unwrap_or_default();
+        let details = [details, message]
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(": ");
+
+        table += &format!(
+            "{:<name_width$}  {:<6}  {:>6.2}s  {}\n",
+            report.name, result, report.seconds, details
+        );
+    }
+
+    let passed = reports
+        .iter()
+        .filter(|report| report.status == RomStatus::Passed)
+        .count();
+
+    table += &format!("{}/{} passed\n", passed, reports.len());
+    table
+}
+
+fn junit_xml(reports: &[RomReport]) -> String {
+    let count = |matches: fn(&RomStatus) -> bool| {
+        reports
+            .iter()
+            .filter(|report| matches(&report.status))
+            .count()
+    };
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        TEST_ROMS_COMMAND,
+        reports.len(),
+        count(|status| matches!(status, RomStatus::Failed(_))),
+        count(|status| matches!(status, RomStatus::Error(_))),
+        reports.iter().map(|report| report.seconds).sum::<f64>()
+    );
+
+    for report in reports {
+        xml += &format!(
+            "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape_xml(&report.name),
+            TEST_ROMS_COMMAND,
+            report.seconds
+        );
+
+        let (element, reason) = match &report.status {
+            RomStatus::Passed => {
+                xml += "/>\n";
+                continue;
+            }
+            RomStatus::Failed(reason) => ("failure", reason),
+            RomStatus::Error(reason) => ("error", reason),
+        };
+
+        xml += &format!(
+            ">\n    <{element} message=\"{}\">{}</{element}>\n  </testcase>\n",
+            escape_xml(reason),
+            escape_xml(&report.message)
+        );
+    }
+
+    xml += "</testsuite>\n";
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            // XML 1.0 can't hold most control characters at all, even escaped.
+            character if character.is_control() && !matches!(character, '\n' | '\t') => {
+                String::new()
+            }
+            character => character.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const PRG_ROM_SIZE: usize = 0x4000;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn create_reports() -> Vec<RomReport> {
+        vec![
+            RomReport {
+                name: "01-basics.nes".to_string(),
+                status: RomStatus::Passed,
+                message: "Passed".to_string(),
+                seconds: 0.5,
+            },
+            RomReport {
+                name: "02-implied.nes".to_string(),
+                status: RomStatus::Failed("Failed with code 2".to_string()),
+                message: "6A ROR A\nFailed".to_string(),
+                seconds: 1.25,
+            },
+            RomReport {
+                name: "a&b.nes".to_string(),
+                status: RomStatus::Error("Timed out".to_string()),
+                message: String::new(),
+                seconds: 2.0,
+            },
+        ]
+    }
+
+    // Writes an NROM ROM which draws a white pixel in the top left of the screen and then
+    // spins, along with a hash file if given.
+    fn create_rom(hash: Option<&str>) -> (TempDir, PathBuf) {
+        let code = [0xA9, 0x01, 0x8D, 0x00, 0x02, 0x4C, 0x05, 0x80];
+
+        let mut prg_rom = vec![0; PRG_ROM_SIZE];
+        prg_rom[..code.len()].copy_from_slice(&code);
+        prg_rom[PRG_ROM_SIZE - 4..PRG_ROM_SIZE - 2].copy_from_slice(&[0x00, 0x80]);
+
+        let rom_data = [
+            b"NES\x1A\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".as_slice(),
+            &prg_rom,
+            &[0; 0x2000],
+        ]
+        .concat();
+
+        let dir = tempfile::tempdir().unwrap();
+        let rom_path = dir.path().join("screen.nes");
+        fs::write(&rom_path, rom_data).unwrap();
+
+        if let Some(hash) = hash {
+            fs::write(rom_path.with_extension(HASH_EXTENSION), hash).unwrap();
+        }
+
+        (dir, rom_path)
+    }
+
+    fn white_pixel_hash() -> u32 {
+        let mut screen = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT * SCREEN_BYTES_PER_PIXEL];
+        screen[..3].copy_from_slice(&[0xFF; 3]);
+
+        crc32(&screen)
+    }
+
+    #[test]
+    fn test_parse_uses_defaults() {
+        assert_eq!(
+            Ok(TestRomsOptions {
+                roms_dir: "roms".to_string(),
+                timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+                junit_path: DEFAULT_JUNIT_PATH.to_string(),
+            }),
+            TestRomsOptions::parse(&args(&["roms"]))
+        );
+    }
+
+    #[test]
+    fn test_parse_reads_flags() {
+        let options =
+            TestRomsOptions::parse(&args(&["roms", "--timeout", "5", "--junit", "out.xml"]))
+                .unwrap();
+
+        assert_eq!(5, options.timeout_seconds);
+        assert_eq!("out.xml", options.junit_path);
+    }
+
+    #[test]
+    fn test_parse_returns_err_given_bad_arguments() {
+        assert!(TestRomsOptions::parse(&args(&[])).is_err());
+        assert!(TestRomsOptions::parse(&args(&["roms", "--timeout"])).is_err());
+        assert!(TestRomsOptions::parse(&args(&["roms", "--timeout", "x"])).is_err());
+        assert!(TestRomsOptions::parse(&args(&["roms", "--fast", "1"])).is_err());
+    }
+
+    #[test]
+    fn test_expected_screen_hash_reads_hash_file() {
+        let (_dir, rom_path) = create_rom(Some("1a2b3c4d\n"));
+
+        assert_eq!(Ok(Some(0x1A2B3C4D)), expected_screen_hash(&rom_path));
+    }
+
+    #[test]
+    fn test_expected_screen_hash_returns_none_without_hash_file() {
+        let (_dir, rom_path) = create_rom(None);
+
+        assert_eq!(Ok(None), expected_screen_hash(&rom_path));
+    }
+
+    #[test]
+    fn test_expected_screen_hash_returns_err_given_invalid_hash() {
+        let (_dir, rom_path) = create_rom(Some("passed"));
+
+        assert!(expected_screen_hash(&rom_path).is_err());
+    }
+
+    #[test]
+    fn test_run_rom_passes_when_screen_matches_hash() {
+        let (_dir, rom_path) = create_rom(Some(&format!("{:08X}", white_pixel_hash())));
+
+        let report = run_rom(&rom_path, NTSC_CPU_CLOCK_HZ);
+
+        assert_eq!(RomStatus::Passed, report.status);
+    }
+
+    #[test]
+    fn test_run_rom_fails_when_screen_never_matches_hash() {
+        let (_dir, rom_path) = create_rom(Some("00000000"));
+
+        let report = run_rom(&rom_path, NTSC_CPU_CLOCK_HZ);
+
+        assert_eq!(
+            RomStatus::Failed(format!(
+                "Screen hash {:08X} didn't match 00000000",
+                white_pixel_hash()
+            )),
+            report.status
+        );
+    }
+
+    #[test]
+    fn test_run_rom_reports_screen_hash_on_timeout() {
+        let (_dir, rom_path) = create_rom(None);
+
+        let report = run_rom(&rom_path, NTSC_CPU_CLOCK_HZ);
+
+        assert_eq!(
+            RomStatus::Error(format!(
+                "Timed out waiting for a $6000 result, screen hash {:08X}",
+                white_pixel_hash()
+            )),
+            report.status
+        );
+    }
+
+    #[test]
+    fn test_summary_table() {
+        assert_eq!(
+            "ROM             Result     Time  Details\n\
+             01-basics.nes   PASS      0.50s  Passed\n\
+             02-implied.nes  FAIL      1.25s  Failed with code 2: 6A ROR A\n\
+             a&b.nes         ERROR     2.00s  Timed out\n\
+             1/3 passed\n",
+            summary_table(&create_reports())
+        );
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        assert_eq!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuite name=\"test-roms\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"3.750\">\n\
+             \x20 <testcase name=\"01-basics.nes\" classname=\"test-roms\" time=\"0.500\"/>\n\
+             \x20 <testcase name=\"02-implied.nes\" classname=\"test-roms\" time=\"1.250\">\n\
+             \x20   <failure message=\"Failed with code 2\">6A ROR A\nFailed</failure>\n\
+             \x20 </testcase>\n\
+             \x20 <testcase name=\"a&amp;b.nes\" classname=\"test-roms\" time=\"2.000\">\n\
+             \x20   <error message=\"Timed out\"></error>\n\
+             \x20 </testcase>\n\
+             </testsuite>\n",
+            junit_xml(&create_reports())
+        );
+    }
+
+    #[test]
+    fn test_quiet_panics_restores_previous_hook() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let test_thread = thread::current().id();
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |_| {
+            if thread::current().id() == test_thread {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+
+        let quiet_panics = QuietPanics::new();
+        let _ = panic::catch_unwind(|| panic!("Quiet."));
+        assert_eq!(0, CALLS.load(Ordering::SeqCst));
+
+        drop(quiet_panics);
+        let _ = panic::catch_unwind(|| panic!("Heard."));
+        assert_eq!(1, CALLS.load(Ordering::SeqCst));
+
+        // Unwinding out of the guard puts the hook back too.
+        let _ = panic::catch_unwind(|| {
+            let _quiet_panics = QuietPanics::new();
+            panic!("Quiet.");
+        });
+        let _ = panic::catch_unwind(|| panic!("Heard."));
+        assert_eq!(2, CALLS.load(Ordering::SeqCst));
+
+        panic::set_hook(default_hook);
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            "&lt;a href=&quot;x&quot;&gt; &amp; &apos;b&apos;",
+            escape_xml("<a href=\"x\"> & 'b'\u{1}")
+        );
+    }
+}
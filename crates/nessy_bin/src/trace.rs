@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use nessy::cpus::mos_6502::trace::{TraceFormat, TraceLogger, sink::TraceSink};
+
+pub const TRACE_FLAG: &str = "--trace";
+pub const TRACE_FORMAT_FLAG: &str = "--trace-format";
+
+#[derive(Debug, PartialEq)]
+pub struct TraceOptions {
+    pub log_path: String,
+    pub format: TraceFormat,
+}
+
+impl TraceOptions {
+    // Picks `--trace <log>` and `--trace-format <nestest|mesen|fceux>` out of the arguments,
+    // wherever they are. Without `--trace` there's nothing to log.
+    pub fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let mut log_path = None;
+        let mut format = TraceFormat::default();
+
+        for (i, arg) in args.iter().enumerate() {
+            if arg != TRACE_FLAG && arg != TRACE_FORMAT_FLAG {
+                continue;
+            }
+
+            let Some(value) = args.get(i + 1) else {
+                return Err(format!("Missing value for {}.", arg));
+            };
+
+            if arg == TRACE_FLAG {
+                log_path = Some(value.clone());
+            } else {
+                format = value.parse().map_err(|err: &str| err.to_string())?;
+            }
+        }
+
+        Ok(log_path.map(|log_path| TraceOptions { log_path, format }))
+    }
+
+    pub fn create_logger(&self) -> Result<TraceLogger, String> {
+        let sink = TraceSink::file(Path::new(&self.log_path))
+            .map_err(|reason| format!("Failed to create {}: {}.", self.log_path, reason))?;
+
+        Ok(TraceLogger::new(self.format, sink))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_returns_none_without_trace() {
+        assert_eq!(Ok(None), TraceOptions::parse(&args(&["game.nes"])));
+    }
+
+    #[test]
+    fn test_parse_reads_flags() {
+        let options =
+            TraceOptions::parse(&args(&["--trace-format", "mesen", "--trace", "out.log"])).unwrap();
+
+        assert_eq!(
+            Some(TraceOptions {
+                log_path: "out.log".to_string(),
+                format: TraceFormat::Mesen,
+            }),
+            options
+        );
+    }
+
+    #[test]
+    fn test_parse_defaults_to_nestest() {
+        let options = TraceOptions::parse(&args(&["--trace", "out.log"])).unwrap();
+
+        assert_eq!(TraceFormat::Nestest, options.unwrap().format);
+    }
+
+    #[test]
+    fn test_parse_returns_err_given_bad_arguments() {
+        assert!(TraceOptions::parse(&args(&["--trace"])).is_err());
+        assert!(TraceOptions::parse(&args(&["--trace", "out.log", "--trace-format"])).is_err());
+        assert!(
+            TraceOptions::parse(&args(&["--trace", "out.log", "--trace-format", "x"])).is_err()
+        );
+    }
+}
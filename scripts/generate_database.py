@@ -0,0 +1,70 @@
+#!/usr/bin/env python3
+# Converts the NesCartDB XML export (https://nescartdb.com) into the table nessy embeds at
+# crates/nessy/src/roms/database.tsv.
+#
+#   scripts/generate_database.py nescartdb.xml > crates/nessy/src/roms/database.tsv
+#
+# Every cartridge is kept; nessy skips the mappers it can't run when it loads the table.
+
+import sys
+import xml.etree.ElementTree as ElementTree
+
+HEADER = """\
+# Header values for known cartridges, generated from the NesCartDB XML export by
+# scripts/generate_database.py. Regenerate rather than editing by hand.
+#
+# crc32\tmapper\tmirroring\tbattery\tregion\tname"""
+
+REGIONS = {
+    "NES-NTSC": "NTSC",
+    "Famicom": "NTSC",
+    "NES-PAL": "PAL",
+    "NES-PAL-A": "PAL",
+    "NES-PAL-B": "PAL",
+    "Dendy": "DENDY",
+}
+
+
+# The H and V solder pads name the arrangement of the nametables, so a closed H pad gives
+# vertical mirroring. Boards without pads switch mirroring themselves.
+def mirroring(board):
+    if board.find("vram[@size='2k']") is not None or board.find("vram[@size='4k']") is not None:
+        return "4"
+
+    pad = board.find("pad")
+    if pad is None:
+        return "-"
+
+    if pad.get("h") == "1":
+        return "V"
+    if pad.get("v") == "1":
+        return "H"
+    return "-"
+
+
+def battery(board):
+    return "1" if any(ram.get("battery") == "1" for ram in board.iter("wram")) else "0"
+
+
+def main(path):
+    print(HEADER)
+
+    seen = set()
+    for game in ElementTree.parse(path).getroot().iter("game"):
+        name = game.get("name", "").replace("\t", " ")
+
+        for cartridge in game.iter("cartridge"):
+            board = cartridge.find("board")
+            region = REGIONS.get(cartridge.get("system"))
+            crc = cartridge.get("crc")
+
+            if board is None or region is None or crc is None or crc in seen:
+                continue
+            seen.add(crc)
+
+            print("\t".join([crc.upper(), board.get("mapper", "0"), mirroring(board),
+                             battery(board), region, name]))
+
+
+if __name__ == "__main__":
+    main(sys.argv[1])